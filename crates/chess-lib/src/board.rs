@@ -1,27 +1,70 @@
-// use alloc::vec::Vec;
+use core::fmt::{Display, Formatter, Result as FmtResult};
+
+use core::error::Error;
+
+use alloc::vec::Vec;
 
 mod pieces;
 mod history;
+mod fen;
+mod setup;
+mod notation;
+mod attacks;
+mod exchange;
+
+use crate::{BoardPiece, Clock, Move, Outcome, Piece, Pos, PosInt, Rules, Side, Size, Standard};
 
-use crate::{Move, Side};
+pub use fen::ParseFenError;
+pub use setup::{Setup, SetupError};
+pub use notation::ParseSanError;
+pub use attacks::Pin;
 
-pub use pieces::Pieces;
+pub use pieces::{PieceUpdate, Pieces};
+pub use history::{BoardHistory, PreviousMove};
 
-#[derive(Default)]
+#[derive(Clone)]
 pub struct Board {
     pub pieces: Pieces,
     pub state: State,
-    pub history: history::BoardHistory,
+    pub history: BoardHistory,
+    pub clock: Option<Clock>,
+    pub rules: &'static dyn Rules,
     // moves: Vec<Move>,
 }
 
+impl Default for Board {
+    fn default() -> Self {
+        Self {
+            pieces: Default::default(),
+            state: Default::default(),
+            history: Default::default(),
+            clock: None,
+            rules: &Standard,
+        }
+    }
+}
+
 impl Board {
+
+    /// A board set up with the start position of a variant.
+    pub fn new(rules: &'static dyn Rules) -> Self {
+        Self::from_variant_fen(rules.start(), rules).expect("Could not parse variant start position!")
+    }
+
+    /// A board set up with Chess960 start position `index` (0 - 959), see [`Pieces::chess960`].
+    pub fn chess960(index: u16) -> Option<Self> {
+        let back_rank = Pieces::chess960(index)?;
+        let mut board = Self {
+            pieces: Pieces::setup(back_rank).collect(),
+            ..Default::default()
+        };
+        board.state.castling = CastlingRights::from_back_rank(&back_rank);
+        Some(board)
+    }
     
     pub fn move_piece(&mut self, mov: Move) -> Result<(), MoveError> {
-        
-        if self.state.check.as_ref().map(Vec::is_empty).unwrap_or(false) {
-            return Err(MoveError::Checkmate)
-        }
+
+        self.playable()?;
 
         if let Some(piece) = self.pieces.at(&mov.from).copied() {
             
@@ -29,9 +72,22 @@ impl Board {
                 return Err(MoveError::WrongSide)
             }
 
+            if mov.promotion.is_some_and(|kind| !Piece::PROMOTIONS.contains(&kind) || !self.promotes(mov)) {
+                return Err(MoveError::Promotion)
+            }
+
+            let mov = piece.resolve(self, mov);
+
             if let Some(step) = piece.can_move(self, mov) {
-                let taken = self.pieces.move_piece(mov);
+                let taken = step.perform(self, mov, piece.side);
+                self.state.en_passant = None;
                 step.on_move(self, mov, piece.side);
+                self.rules.on_move(self, mov, piece, taken);
+                self.state.castling.update(mov, &piece, self.size());
+                self.state.halfmoves = match piece.kind == Piece::Pawn || taken.is_some() {
+                    true => 0,
+                    false => self.state.halfmoves + 1,
+                };
                 
 
                 // let taken = self.pieces.0.iter().position(|p| p.position == mov.to).map(|i| self.pieces.0.remove(i));
@@ -40,37 +96,178 @@ impl Board {
 
                 self.history.add(mov, taken);
 
-                self.state.check = self.check(piece.side.other());
-
-                self.state.turn.increment();
+                self.end_turn();
 
                 // let piece = self.pieces.at(&mov.to).unwrap();
                 // Ok(piece)
                 Ok(())
             } else {
-                Err(MoveError::InvalidMove)
+                Err(piece.reason(self, mov))
             }
         } else {
             Err(MoveError::NoPiece)
         }
     }
 
+    /// Drops a piece from the hand of the side to move onto an empty square, in variants with drops.
+    pub fn drop_piece(&mut self, kind: Piece, pos: Pos) -> Result<(), MoveError> {
+
+        self.playable()?;
+
+        let side = self.state.turn.side;
+
+        if !self.state.hands[side as usize].contains(&kind) {
+            return Err(MoveError::NoPiece)
+        }
+
+        if !self.drops().any(|drop| drop == (kind, pos)) {
+            return Err(match self.pieces.at(&pos) {
+                Some(..) => MoveError::Occupied,
+                None if !self.size().contains(pos) || (kind == Piece::Pawn && (pos.y == 0 || pos.y == self.size().height - 1)) => MoveError::Pattern,
+                None => MoveError::Check,
+            })
+        }
+
+        let hand = &mut self.state.hands[side as usize];
+        hand.remove(hand.iter().position(|piece| *piece == kind).expect("Could not get dropped piece from hand!"));
+        self.pieces.insert(pos, BoardPiece { kind, side });
+        self.state.en_passant = None;
+        self.state.halfmoves += 1;
+        self.history.add_drop(kind, pos);
+
+        self.end_turn();
+
+        Ok(())
+    }
+
+    /// Ends the game other than by playing, such as by resignation, agreement or adjudication.
+    pub fn end(&mut self, outcome: Outcome) -> Result<(), MoveError> {
+        self.playable()?;
+        self.state.outcome = Some(outcome);
+        if let Some(clock) = self.clock.as_mut() {
+            clock.stop();
+        }
+        Ok(())
+    }
+
+    fn playable(&mut self) -> Result<(), MoveError> {
+        if let Some(side) = self.state.flagged.or_else(|| self.clock.as_ref().and_then(Clock::flagged)) {
+            self.state.flagged = Some(side);
+            self.state.outcome.get_or_insert(Outcome::Win(side.other(), "time"));
+        }
+
+        match self.state.outcome {
+            Some(..) if self.state.flagged.is_some() => Err(MoveError::Timeout),
+            Some(Outcome::Win(.., "checkmate")) => Err(MoveError::Checkmate),
+            Some(..) => Err(MoveError::GameOver),
+            None => Ok(()),
+        }
+    }
+
+    fn end_turn(&mut self) {
+        let side = self.state.turn.side;
+
+        self.state.check = self.check(side.other());
+
+        self.state.turn.increment();
+
+        if let Some(clock) = self.clock.as_mut() {
+            clock.switch(self.state.turn);
+        }
+
+        self.state.outcome = self.rules.outcome(self);
+    }
+
+    /// Recomputes the check and outcome of the position, after it has been set up.
+    pub(crate) fn refresh(&mut self) {
+        self.state.check = self.check(self.state.turn.side);
+        self.state.outcome = self.rules.outcome(self);
+    }
+
     pub fn check(&self, side: Side) -> Option<Vec<Move>> {
+        if !self.rules.royal() {
+            return None;
+        }
         let kings = self.pieces.iter().filter(|(.., piece)| piece.is_king(side)).map(|(pos, ..)| pos).collect::<Vec<_>>();
         self.pieces.of(side.other()).flat_map(|(pos, piece)| piece.targets(self, *pos)).any(|target| kings.contains(&&target)).then(|| {
             self.pieces.of(side).flat_map(|(pos, piece)| piece.moves(self, *pos).map(|to| Move::new(*pos, to))).collect()
         })
     }
 
+    /// Legal moves of the side to move, with one for each piece a pawn reaching the last rank may become.
+    pub fn moves(&self) -> impl Iterator<Item = Move> + '_ {
+        self.pieces.of(self.state.turn.side).flat_map(|(pos, piece)| piece.moves(self, *pos).map(|to| Move::new(*pos, to)))
+            .flat_map(|mov| {
+                let kinds = if self.promotes(mov) { &Piece::PROMOTIONS[..] } else { &Piece::PROMOTIONS[..1] };
+                kinds.iter().map(move |kind| mov.promoting(*kind))
+            })
+    }
+
+    /// Legal drops of the side to move.
+    pub fn drops(&self) -> impl Iterator<Item = (Piece, Pos)> + '_ {
+        let side = self.state.turn.side;
+        let mut kinds = self.state.hands[side as usize].clone();
+        kinds.sort();
+        kinds.dedup();
+        let in_check = self.king(side).is_some_and(|king| self.attacked(king, side.other()));
+        let size = self.size();
+        kinds.into_iter().filter(|_| self.rules.drops()).flat_map(move |kind| size.squares().map(move |pos| (kind, pos)))
+            .filter(move |(kind, pos)| {
+                self.pieces.at(pos).is_none() && (*kind != Piece::Pawn || (pos.y != 0 && pos.y != size.height - 1)) && (!in_check || {
+                    let mut after = self.clone();
+                    after.pieces.insert(*pos, BoardPiece { kind: *kind, side });
+                    after.king(side).is_some_and(|king| !after.attacked(king, side.other()))
+                })
+            })
+    }
+
+    /// The piece a move would take, including en passant.
+    pub fn capture(&self, mov: Move) -> Option<BoardPiece> {
+        let piece = self.pieces.at(&mov.from)?;
+        match self.pieces.at(&mov.to) {
+            Some(taken) => Some(*taken).filter(|taken| taken.side != piece.side),
+            None if piece.kind == Piece::Pawn && mov.from.x != mov.to.x => self.pieces.at(&Pos { x: mov.to.x, y: mov.from.y }).copied(),
+            None => None,
+        }
+    }
+
+    /// The pieces after a move, without castling or any other changes to the state.
+    pub fn preview(&self, mov: Move) -> Board {
+        let mut pieces = self.pieces.clone();
+        if self.capture(mov).is_some() && self.pieces.at(&mov.to).is_none() {
+            pieces.take(&Pos { x: mov.to.x, y: mov.from.y });
+        }
+        pieces.move_piece(mov);
+        Self {
+            pieces,
+            rules: self.rules,
+            ..Default::default()
+        }
+    }
+
+    pub fn size(&self) -> Size {
+        self.rules.size()
+    }
+
+    pub fn king(&self, side: Side) -> Option<Pos> {
+        self.pieces.iter().find(|(.., piece)| piece.is_king(side)).map(|(pos, ..)| *pos)
+    }
+
     pub fn reset(&mut self) {
-        self.state.reset();
-        self.pieces.reset();
+        let start = Self::new(self.rules);
+        self.state = start.state;
+        self.pieces.clear();
+        self.pieces.fill_with(start.pieces.iter().map(|(pos, piece)| (*pos, *piece)));
         self.history.reset();
+        if let Some(clock) = self.clock.as_mut() {
+            clock.reset();
+        }
     }
 
 }
 
 #[derive(Default, Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct Turn {
     pub side: Side,
     pub no: usize,
@@ -85,10 +282,77 @@ impl Turn {
     }
 }
 
-#[derive(Default)]
+/// Files of the rooks each side may still castle with.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct CastlingRights([[Option<PosInt>; 2]; 2]);
+
+impl CastlingRights {
+
+    pub const NONE: Self = Self([[None; 2]; 2]);
+
+    /// Rights for the outermost rooks on either side of the king.
+    pub fn from_back_rank(back_rank: &[Piece]) -> Self {
+        let king = back_rank.iter().position(|piece| *piece == Piece::King).unwrap_or(4);
+        let queen_side = back_rank[..king].iter().position(|piece| *piece == Piece::Rook).map(|x| x as PosInt);
+        let king_side = back_rank[king..].iter().rposition(|piece| *piece == Piece::Rook).map(|x| (x + king) as PosInt);
+        Self([[queen_side, king_side]; 2])
+    }
+
+    pub fn get(&self, side: Side, king_side: bool) -> Option<PosInt> {
+        self.0[side as usize][king_side as usize]
+    }
+
+    pub fn set(&mut self, side: Side, king_side: bool, file: Option<PosInt>) {
+        self.0[side as usize][king_side as usize] = file;
+    }
+
+    pub fn clear(&mut self, side: Side) {
+        self.0[side as usize] = [None; 2];
+    }
+
+    /// Removes the rights lost by `piece` making `mov`, from moving the king or a rook or capturing a rook.
+    pub fn update(&mut self, mov: Move, piece: &BoardPiece, size: Size) {
+        if piece.kind == Piece::King {
+            self.clear(piece.side);
+        }
+        for side in Side::sides() {
+            for file in self.0[side as usize].iter_mut() {
+                if file.is_some_and(|x| [mov.from, mov.to].contains(&Pos { x, y: size.origin(side) })) {
+                    *file = None;
+                }
+            }
+        }
+    }
+
+}
+
+impl Default for CastlingRights {
+    fn default() -> Self {
+        Self::from_back_rank(&Pieces::STANDARD)
+    }
+}
+
+#[derive(Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct State {
     pub turn: Turn,
+    pub castling: CastlingRights,
+    /// The square a pawn may be taken on en passant, after a double move.
+    pub en_passant: Option<Pos>,
+    /// Halfmoves since the last capture or pawn move.
+    pub halfmoves: u32,
+    /// Checks given by each side.
+    pub checks: [u32; 2],
+    /// Pieces in each side's hand, to be dropped.
+    pub hands: [Vec<Piece>; 2],
+    /// Squares of promoted pieces, which go back to being pawns when captured in variants with drops.
+    pub promoted: Vec<Pos>,
+    #[cfg_attr(feature = "serde", serde(skip))]
     check: Option<Vec<Move>>,
+    flagged: Option<Side>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    outcome: Option<Outcome>,
 }
 
 impl State {
@@ -96,16 +360,66 @@ impl State {
         self.check.as_ref()
     }
 
+    /// The side that ran out of time, if any.
+    pub fn flagged(&self) -> Option<Side> {
+        self.flagged
+    }
+
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.outcome
+    }
+
     pub fn reset(&mut self) {
         *self = Self::default();
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum MoveError {
     WrongSide,
     NoPiece,
-    InvalidMove,
+    /// The piece does not move that way.
+    Pattern,
+    /// Another piece stands in the way.
+    Blocked,
+    /// A piece of the same side is on the square.
+    Occupied,
+    /// The piece may not leave the line between its king and an attacker.
+    Pinned,
+    /// The king would be in check after the move.
     Check,
+    /// The king would castle out of, through or into check.
+    CastlingCheck,
+    /// The right to castle on that side has been lost.
+    CastlingRights,
+    /// A pawn can not become that piece, or the move does not promote.
+    Promotion,
+    /// The move is not allowed by the rules of the variant, such as when captures are compulsory.
+    Variant,
     Checkmate,
-}
\ No newline at end of file
+    Timeout,
+    GameOver,
+}
+
+impl Display for MoveError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str(match self {
+            Self::WrongSide => "It is the other side's turn",
+            Self::NoPiece => "There is no piece to move",
+            Self::Pattern => "The piece can not move that way",
+            Self::Blocked => "The way is blocked",
+            Self::Occupied => "The square is taken by an own piece",
+            Self::Pinned => "The piece is pinned to its king",
+            Self::Check => "The king would be in check",
+            Self::CastlingCheck => "The king can not castle out of, through or into check",
+            Self::CastlingRights => "The right to castle on that side has been lost",
+            Self::Promotion => "The pawn can not be promoted to that piece there",
+            Self::Variant => "The move is not allowed in this variant",
+            Self::Checkmate => "The game has ended in checkmate",
+            Self::Timeout => "The game has ended on time",
+            Self::GameOver => "The game is over",
+        })
+    }
+}
+
+impl Error for MoveError {}
\ No newline at end of file
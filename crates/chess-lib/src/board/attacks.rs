@@ -0,0 +1,118 @@
+use alloc::vec::Vec;
+
+use crate::{Board, Pos, Side};
+
+/// A piece that can not leave the line between its king and an attacker without exposing the king.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pin {
+    pub pinned: Pos,
+    pub attacker: Pos,
+    /// The squares from the attacker up to the king, on which the pinned piece still shields it.
+    pub ray: Vec<Pos>,
+}
+
+impl Board {
+
+    /// Pieces of `side` that attack the square.
+    pub fn attackers(&self, pos: Pos, side: Side) -> impl Iterator<Item = Pos> + '_ {
+        self.pieces.of(side).filter(move |(from, ..)| self.hits(**from, pos)).map(|(from, ..)| *from)
+    }
+
+    /// Whether a piece of `side` attacks the square.
+    pub fn attacked(&self, pos: Pos, side: Side) -> bool {
+        self.attackers(pos, side).next().is_some()
+    }
+
+    /// Pieces giving check to the king of `side`.
+    pub fn checkers(&self, side: Side) -> Vec<Pos> {
+        self.king(side).map(|king| self.attackers(king, side.other()).collect()).unwrap_or_default()
+    }
+
+    /// Pieces of `side` pinned to their king, with the pieces pinning them.
+    pub fn pins(&self, side: Side) -> Vec<Pin> {
+        let Some(king) = self.king(side) else {
+            return Vec::new();
+        };
+        let mut pins = Vec::new();
+        for (pos, ..) in self.pieces.of(side).filter(|(pos, ..)| **pos != king) {
+            let without = self.without(*pos);
+            for attacker in without.attackers(king, side.other()).filter(|attacker| !self.hits(*attacker, king)) {
+                let ray = core::iter::once(attacker).chain(attacker.between(king)).collect();
+                pins.push(Pin { pinned: *pos, attacker, ray });
+            }
+        }
+        pins
+    }
+
+    /// Whether the piece on `pos` shields its king from an attacker, which would attack the king once it moves away.
+    pub fn pinned(&self, pos: Pos) -> bool {
+        let Some(piece) = self.pieces.at(&pos) else {
+            return false;
+        };
+        let Some(king) = self.king(piece.side).filter(|king| *king != pos) else {
+            return false;
+        };
+        let without = self.without(pos);
+        self.pieces.of(piece.side.other()).any(|(from, ..)| without.hits(*from, king) && !self.hits(*from, king))
+    }
+
+    /// Pieces of `side` that attack the square through exactly one other piece, such as a rook behind a queen.
+    pub fn xrays(&self, pos: Pos, side: Side) -> Vec<Pos> {
+        self.pieces.of(side).filter(|(from, ..)| !self.hits(**from, pos)).filter_map(|(from, ..)| {
+            let mut blockers = from.between(pos).filter(|square| self.pieces.at(square).is_some());
+            match (blockers.next(), blockers.next()) {
+                (Some(blocker), None) => self.without(blocker).hits(*from, pos).then_some(*from),
+                _ => None,
+            }
+        }).collect()
+    }
+
+    /// Whether the piece on `from` attacks `to`.
+    fn hits(&self, from: Pos, to: Pos) -> bool {
+        self.pieces.at(&from).is_some_and(|piece| piece.targets(self, from).any(|target| target == to))
+    }
+
+    /// The pieces with one taken off, to see what it stands in the way of.
+    fn without(&self, pos: Pos) -> Board {
+        let mut board = Self { pieces: self.pieces.clone(), rules: self.rules, ..Default::default() };
+        board.pieces.take(&pos);
+        board
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::vec::Vec;
+
+    use crate::{Board, Pos, Side};
+
+    fn pos(s: &str) -> Pos {
+        s.parse().unwrap()
+    }
+
+    fn sorted(mut squares: Vec<Pos>) -> Vec<Pos> {
+        squares.sort_by_key(|pos| (pos.x, pos.y));
+        squares
+    }
+
+    #[test]
+    fn attacks() {
+        let board = Board::from_fen("4k3/8/8/8/1b6/n7/3N4/1Q2K2r w - - 0 1").unwrap();
+        assert_eq!(sorted(board.checkers(Side::White)), [pos("h1")]);
+        assert_eq!(sorted(board.attackers(pos("b1"), Side::Black).collect()), [pos("a3")]);
+        assert!(!board.attacked(pos("e8"), Side::White) && board.attacked(pos("d2"), Side::Black));
+
+        let pins = board.pins(Side::White);
+        assert_eq!(pins.len(), 1);
+        assert_eq!((pins[0].pinned, pins[0].attacker), (pos("d2"), pos("b4")));
+        assert_eq!(pins[0].ray, [pos("b4"), pos("c3"), pos("d2")]);
+        assert!(board.pinned(pos("d2")) && !board.pinned(pos("b1")));
+
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/R2QK3 w - - 0 1").unwrap();
+        assert_eq!(board.xrays(pos("d8"), Side::White), Vec::new());
+        assert_eq!(board.xrays(pos("h1"), Side::White), [pos("d1")]);
+        let board = Board::from_fen("3qk3/8/8/8/8/8/3Q4/3RK3 w - - 0 1").unwrap();
+        assert_eq!(board.xrays(pos("d8"), Side::White), [pos("d1")]);
+    }
+}
@@ -0,0 +1,79 @@
+use alloc::vec;
+
+use crate::{Board, Move, Piece, Pos};
+
+/// What taking a king is worth in an exchange, so that it is never left en prise.
+const KING_VALUE: i32 = 100 * Piece::Queen.value();
+
+impl Board {
+
+    /// Static exchange evaluation: the material, in centipawns, the side to move gains from a move once both
+    /// sides have recaptured on its square for as long as it pays, cheapest piece first. Pieces behind the
+    /// capturers, such as a rook behind a queen, join in as the way clears. Pins are not taken into account.
+    pub fn see(&self, mov: Move) -> i32 {
+        let Some(piece) = self.pieces.at(&mov.from).copied() else {
+            return 0;
+        };
+        let mov = piece.resolve(self, mov);
+        if self.castles(mov).is_some() {
+            return 0;
+        }
+        let value = |kind: Piece| match kind {
+            Piece::King => KING_VALUE,
+            kind => kind.value(),
+        };
+
+        let mut board = Self { pieces: self.pieces.clone(), rules: self.rules, ..Default::default() };
+        let mut gains = vec![self.capture(mov).map_or(0, |taken| value(taken.kind))];
+        if self.capture(mov).is_some() && self.pieces.at(&mov.to).is_none() {
+            board.pieces.take(&Pos { x: mov.to.x, y: mov.from.y });
+        }
+        board.pieces.move_piece(mov);
+
+        let mut on_square = value(piece.kind);
+        let mut side = piece.side.other();
+        while let Some(from) = board.attackers(mov.to, side).min_by_key(|from| board.pieces.at(from).map(|piece| value(piece.kind))) {
+            gains.push(on_square - gains[gains.len() - 1]);
+            on_square = board.pieces.at(&from).map_or(0, |piece| value(piece.kind));
+            board.pieces.move_piece(Move::new(from, mov.to));
+            side = side.other();
+        }
+
+        // Either side may stop recapturing when it would lose by going on.
+        while gains.len() > 1 {
+            let last = gains.pop().unwrap_or_default();
+            let previous = gains.len() - 1;
+            gains[previous] = -(-gains[previous]).max(last);
+        }
+        gains[0]
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Board, Piece};
+
+    fn exchange(fen: &str, mov: &str) -> i32 {
+        let board = Board::from_fen(fen).unwrap();
+        board.see(mov.parse().unwrap())
+    }
+
+    #[test]
+    fn see() {
+        // An undefended pawn, and one defended by a pawn.
+        assert_eq!(exchange("4k3/8/8/3p4/8/8/8/3QK3 w - - 0 1", "d1 d5"), 100);
+        assert_eq!(exchange("4k3/8/2p5/3p4/8/8/8/3QK3 w - - 0 1", "d1 d5"), 100 - 900);
+        // The queen behind the rook wins the pawn in the end, unless there is a second rook to take it.
+        assert_eq!(exchange("3rk3/8/8/3p4/8/8/3R4/3QK3 w - - 0 1", "d2 d5"), 100);
+        assert_eq!(exchange("3rk3/3r4/8/3p4/8/8/3R4/3QK3 w - - 0 1", "d2 d5"), 100 - 500);
+        // Knight takes a defended pawn, and quiet moves onto safe and attacked squares.
+        assert_eq!(exchange("4k3/8/2p5/3p4/8/4N3/8/4K3 w - - 0 1", "e3 d5"), 100 - 300);
+        assert_eq!(exchange("4k3/8/8/4p3/8/5N2/8/4K3 w - - 0 1", "f3 h4"), 0);
+        assert_eq!(exchange("4k3/8/8/4p3/8/5N2/8/4K3 w - - 0 1", "f3 d4"), -300);
+        // En passant, and the king only takes when the square is not defended.
+        assert_eq!(exchange("4k3/8/8/3pP3/8/8/8/4K3 w - d6 0 2", "e5 d6"), 100);
+        assert_eq!(exchange("4k3/8/8/8/8/8/3p4/4K3 w - - 0 1", "e1 d2"), 100);
+        assert!(exchange("4k3/8/8/8/8/2b5/3p4/4K3 w - - 0 1", "e1 d2") < -Piece::Queen.value());
+    }
+}
@@ -0,0 +1,409 @@
+use core::fmt::{Display, Formatter, Result as FmtResult, Write};
+
+use core::error::Error;
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use crate::{Board, BoardPiece, CastlingRights, ParsePosError, Piece, Pieces, Pos, PosInt, Rules, Side, Size, Standard, State, Turn};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseFenError {
+    Fields,
+    Placement,
+    Side,
+    Castling,
+    EnPassant(ParsePosError),
+    Counter,
+}
+
+impl Display for ParseFenError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::EnPassant(err) => write!(f, "Invalid en passant square with error {err}"),
+            err => write!(f, "Invalid {}", match err {
+                Self::Fields => "number of fields",
+                Self::Placement => "piece placement",
+                Self::Side => "side to move",
+                Self::Castling => "castling rights",
+                _ => "move counter",
+            }),
+        }
+    }
+}
+
+impl Error for ParseFenError {}
+
+impl Board {
+
+    pub const START_FEN: &'static str = "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1";
+
+    /// Parses a position in Forsyth-Edwards Notation. Castling rights may be given as in X-FEN or
+    /// Shredder-FEN, by rook file (e.g. "HAha"), for Chess960 positions.
+    /// The move counters may be left out.
+    ///
+    /// Pieces in hand may follow the placement in brackets, with promoted pieces marked by a `~`,
+    /// and for variants won by checks, the checks each side has left to give (e.g. "3+3") or, as lichess writes
+    /// them, has given (e.g. "+1+0") may follow the en passant square.
+    pub fn from_fen(fen: &str) -> Result<Self, ParseFenError> {
+        Self::from_variant_fen(fen, &Standard)
+    }
+
+    /// Parses a position of a variant, which sets the board size and any fairy pieces.
+    pub fn from_variant_fen(fen: &str, rules: &'static dyn Rules) -> Result<Self, ParseFenError> {
+        let mut fields = fen.split_whitespace().peekable();
+
+        let placement = fields.next().ok_or(ParseFenError::Fields)?;
+        let (placement, hands) = match placement.split_once('[') {
+            Some((placement, hand)) => (placement, Self::hands(rules, hand.strip_suffix(']').ok_or(ParseFenError::Placement)?)?),
+            None => (placement, Default::default()),
+        };
+        let (pieces, promoted) = Self::placement(rules, placement)?;
+
+        let mut field = || fields.next().ok_or(ParseFenError::Fields);
+
+        let side = match field()? {
+            "w" => Side::White,
+            "b" => Side::Black,
+            _ => return Err(ParseFenError::Side),
+        };
+
+        let castling = Self::castling(&pieces, rules.size(), field()?)?;
+
+        let en_passant = match field()? {
+            "-" => None,
+            pos => Some(pos.parse().map_err(ParseFenError::EnPassant)?),
+        };
+
+        let checks = match fields.next_if(|field| field.contains('+')) {
+            Some(field) => Self::checks(rules, field)?,
+            None => [0; 2],
+        };
+
+        let mut counter = |default: u32| fields.next().map(|n| n.parse::<u32>().map_err(|_| ParseFenError::Counter)).unwrap_or(Ok(default));
+        let halfmoves = counter(0)?;
+        let fullmoves = counter(1)?;
+
+        if fields.next().is_some() {
+            return Err(ParseFenError::Fields);
+        }
+
+        let mut board = Self {
+            pieces,
+            state: State {
+                turn: Turn { side, no: fullmoves.saturating_sub(1) as usize },
+                castling,
+                en_passant,
+                halfmoves,
+                checks,
+                hands,
+                promoted,
+                ..Default::default()
+            },
+            rules,
+            ..Default::default()
+        };
+        board.refresh();
+        Ok(board)
+    }
+
+    /// The position in X-FEN, which is plain FEN for any position reachable in standard chess.
+    pub fn fen(&self) -> String {
+        self.write_fen(false)
+    }
+
+    /// The position in Shredder-FEN, with castling rights given by rook file.
+    pub fn shredder_fen(&self) -> String {
+        self.write_fen(true)
+    }
+
+    /// A hash of the position for looking it up: the pieces, hands, side to move, castling rights and en passant
+    /// square if a pawn can take on it, but not the move counters. It is the same from run to run, so it can be
+    /// stored.
+    pub fn position_hash(&self) -> u64 {
+        let capturable = self.state.en_passant.is_some_and(|pos| self.moves().any(|mov| {
+            mov.to == pos && self.pieces.at(&mov.from).is_some_and(|piece| piece.kind == Piece::Pawn)
+        }));
+        let fen = self.write_fen(true);
+        let fields = fen.split(' ').take(4).enumerate().map(|(i, field)| if i == 3 && !capturable { "-" } else { field });
+        // FNV-1a over the variant and the fields.
+        let mut hash = 0xcbf2_9ce4_8422_2325_u64;
+        for byte in self.rules.name().bytes().chain(fields.flat_map(|field| field.bytes().chain([b' ']))) {
+            hash = (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3);
+        }
+        hash
+    }
+
+    fn write_fen(&self, shredder: bool) -> String {
+        let mut fen = String::new();
+        let size = self.size();
+        for y in (0..size.height).rev() {
+            let mut empty = 0;
+            for x in 0..size.width {
+                match self.pieces.at(&Pos { x, y }) {
+                    Some(piece) => {
+                        if empty != 0 {
+                            write!(fen, "{empty}").unwrap();
+                            empty = 0;
+                        }
+                        fen.push(piece.letter());
+                        if self.state.promoted.contains(&Pos { x, y }) {
+                            fen.push('~');
+                        }
+                    },
+                    None => empty += 1,
+                }
+            }
+            if empty != 0 {
+                write!(fen, "{empty}").unwrap();
+            }
+            if y != 0 {
+                fen.push('/');
+            }
+        }
+
+        if self.rules.drops() {
+            fen.push('[');
+            for side in Side::sides() {
+                fen.extend(self.state.hands[side as usize].iter().map(|kind| BoardPiece { kind: *kind, side }.letter()));
+            }
+            fen.push(']');
+        }
+
+        fen.push_str(match self.state.turn.side {
+            Side::White => " w ",
+            Side::Black => " b ",
+        });
+
+        let castling = fen.len();
+        for side in Side::sides() {
+            for king_side in [true, false] {
+                if let Some(x) = self.state.castling.get(side, king_side) {
+                    let letter = match !shredder && self.outermost_rook(side, king_side) == Some(x) {
+                        true => if king_side { 'K' } else { 'Q' },
+                        false => (b'A' + x as u8) as char,
+                    };
+                    fen.push(match side {
+                        Side::White => letter,
+                        Side::Black => letter.to_ascii_lowercase(),
+                    });
+                }
+            }
+        }
+        if fen.len() == castling {
+            fen.push('-');
+        }
+
+        match self.state.en_passant {
+            Some(pos) => write!(fen, " {pos}"),
+            None => write!(fen, " -"),
+        }.unwrap();
+
+        if let Some(checks) = self.rules.checks() {
+            write!(fen, " {}+{}", checks.saturating_sub(self.state.checks[0]), checks.saturating_sub(self.state.checks[1])).unwrap();
+        }
+
+        write!(fen, " {} {}", self.state.halfmoves, self.state.turn.no + 1).unwrap();
+        fen
+    }
+
+    /// The checks each side has given, from the checks left as in "2+3" or given as in "+1+0". Only variants won by
+    /// checks have them.
+    fn checks(rules: &dyn Rules, field: &str) -> Result<[u32; 2], ParseFenError> {
+        let limit = rules.checks().ok_or(ParseFenError::Counter)?;
+        let (given, counts) = match field.strip_prefix('+') {
+            Some(counts) => (true, counts),
+            None => (false, field),
+        };
+        let counts = counts.split('+').map(|n| n.parse::<u32>().map_err(|_| ParseFenError::Counter)).collect::<Result<Vec<_>, _>>()?;
+        match *counts.as_slice() {
+            [white, black] if given => Ok([white, black]),
+            [white, black] if white <= limit && black <= limit => Ok([limit - white, limit - black]),
+            _ => Err(ParseFenError::Counter),
+        }
+    }
+
+    fn hands(rules: &dyn Rules, hand: &str) -> Result<[Vec<Piece>; 2], ParseFenError> {
+        let mut hands: [Vec<Piece>; 2] = Default::default();
+        for c in hand.chars() {
+            let side = if c.is_ascii_uppercase() { Side::White } else { Side::Black };
+            hands[side as usize].push(rules.piece(c).ok_or(ParseFenError::Placement)?);
+        }
+        Ok(hands)
+    }
+
+    fn placement(rules: &dyn Rules, placement: &str) -> Result<(Pieces, Vec<Pos>), ParseFenError> {
+        let size = rules.size();
+        let ranks = placement.split('/').collect::<Vec<_>>();
+        if ranks.len() != size.height as usize {
+            return Err(ParseFenError::Placement);
+        }
+        let mut pieces: Vec<(Pos, BoardPiece)> = Vec::new();
+        let mut promoted = Vec::new();
+        for (rank, y) in ranks.into_iter().zip((0..size.height).rev()) {
+            let mut x: PosInt = 0;
+            let mut chars = rank.chars().peekable();
+            while let Some(c) = chars.next() {
+                match c.to_digit(10) {
+                    Some(digit @ 1..=9) => {
+                        let mut empty = digit as PosInt;
+                        while let Some(digit) = chars.next_if(char::is_ascii_digit).and_then(|c| c.to_digit(10)) {
+                            empty = empty.saturating_mul(10).saturating_add(digit as PosInt);
+                        }
+                        x = x.saturating_add(empty);
+                    },
+                    None if c == '~' => promoted.push(pieces.last().map(|(pos, ..)| *pos).filter(|pos| pos.y == y && pos.x == x - 1).ok_or(ParseFenError::Placement)?),
+                    Some(..) => return Err(ParseFenError::Placement),
+                    None => {
+                        let kind = rules.piece(c).ok_or(ParseFenError::Placement)?;
+                        let side = if c.is_ascii_uppercase() { Side::White } else { Side::Black };
+                        pieces.push((Pos { x, y }, BoardPiece { kind, side }));
+                        x += 1;
+                    },
+                }
+                if x > size.width {
+                    return Err(ParseFenError::Placement);
+                }
+            }
+            if x != size.width {
+                return Err(ParseFenError::Placement);
+            }
+        }
+        Ok((pieces.into_iter().collect(), promoted))
+    }
+
+    pub(super) fn castling(pieces: &Pieces, size: Size, castling: &str) -> Result<CastlingRights, ParseFenError> {
+        let mut rights = CastlingRights::NONE;
+        if castling == "-" {
+            return Ok(rights);
+        }
+        for c in castling.chars() {
+            let side = if c.is_ascii_uppercase() { Side::White } else { Side::Black };
+            let king = Self::back_rank_king(pieces, size, side).ok_or(ParseFenError::Castling)?;
+            let (king_side, x) = match c.to_ascii_uppercase() {
+                'K' => (true, Self::back_rank_rooks(pieces, size, side).filter(|x| *x > king).max()),
+                'Q' => (false, Self::back_rank_rooks(pieces, size, side).filter(|x| *x < king).min()),
+                file @ 'A'..='Z' if (file as PosInt - 'A' as PosInt) < size.width => {
+                    let x = file as PosInt - 'A' as PosInt;
+                    (x > king, Self::back_rank_rooks(pieces, size, side).find(|rook| *rook == x))
+                },
+                _ => return Err(ParseFenError::Castling),
+            };
+            rights.set(side, king_side, Some(x.ok_or(ParseFenError::Castling)?));
+        }
+        Ok(rights)
+    }
+
+    fn outermost_rook(&self, side: Side, king_side: bool) -> Option<PosInt> {
+        let king = Self::back_rank_king(&self.pieces, self.size(), side)?;
+        let mut rooks = Self::back_rank_rooks(&self.pieces, self.size(), side);
+        match king_side {
+            true => rooks.filter(|x| *x > king).max(),
+            false => rooks.find(|x| *x < king),
+        }
+    }
+
+    fn back_rank_king(pieces: &Pieces, size: Size, side: Side) -> Option<PosInt> {
+        (0..size.width).find(|x| pieces.at(&Pos { x: *x, y: size.origin(side) }).is_some_and(|piece| piece.is_king(side)))
+    }
+
+    fn back_rank_rooks(pieces: &Pieces, size: Size, side: Side) -> impl Iterator<Item = PosInt> + '_ {
+        (0..size.width).filter(move |x| pieces.at(&Pos { x: *x, y: size.origin(side) }).is_some_and(|piece| piece.side == side && piece.kind == Piece::Rook))
+    }
+
+}
+
+impl BoardPiece {
+
+    /// The FEN letter of the piece, upper case for white.
+    pub const fn letter(&self) -> char {
+        match self.side {
+            Side::White => self.kind.letter(),
+            Side::Black => self.kind.letter().to_ascii_lowercase(),
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Board, Move, Pieces, Pos};
+
+    fn mov(s: &str) -> Move {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn round_trip() {
+        assert_eq!(Board::default().fen(), Board::START_FEN);
+        for fen in [
+            Board::START_FEN,
+            "r3k2r/p1ppqpb1/bn2pnp1/3PN3/1p2P3/2N2Q1p/PPPBBPPP/R3K2R w KQkq - 0 1",
+            "8/2p5/3p4/KP5r/1R3p1k/8/4P1P1/8 w - - 0 1",
+            "rnbqkbnr/pp1ppppp/8/2p5/4P3/8/PPPP1PPP/RNBQKBNR w KQkq c6 0 2",
+        ] {
+            assert_eq!(Board::from_fen(fen).unwrap().fen(), fen);
+        }
+        assert!(Board::from_fen("rnbqkbnr/pppppppp/9/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 0 1").is_err());
+        assert!(Board::from_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq").is_err());
+    }
+
+    #[test]
+    fn moves() {
+        let mut board = Board::default();
+        for m in ["e2 e4", "c7 c5", "g1 f3"] {
+            board.move_piece(mov(m)).unwrap();
+        }
+        assert_eq!(board.fen(), "rnbqkbnr/pp1ppppp/8/2p5/4P3/5N2/PPPP1PPP/RNBQKB1R b KQkq - 1 2");
+    }
+
+    #[test]
+    fn position_hash() {
+        let mut board = Board::default();
+        let start = board.position_hash();
+        for m in ["g1 f3", "g8 f6", "f3 g1", "f6 g8"] {
+            board.move_piece(m.parse().unwrap()).unwrap();
+        }
+        assert_eq!(board.position_hash(), start);
+        board.move_piece("e2 e4".parse().unwrap()).unwrap();
+        assert_ne!(board.position_hash(), start);
+        assert_ne!(Board::new(crate::variant("atomic").unwrap()).position_hash(), start);
+    }
+
+    #[test]
+    fn chess960() {
+        assert_eq!(Pieces::chess960(518), Some(Pieces::STANDARD));
+        assert_eq!(Board::chess960(0).unwrap().fen(), "bbqnnrkr/pppppppp/8/8/8/8/PPPPPPPP/BBQNNRKR w KQkq - 0 1");
+        assert_eq!(Board::chess960(959).unwrap().fen(), "rkrnnqbb/pppppppp/8/8/8/8/PPPPPPPP/RKRNNQBB w KQkq - 0 1");
+        assert!(Board::chess960(960).is_none());
+        assert!((0..960).all(|i| {
+            let rank = Pieces::chess960(i).unwrap();
+            let bishops = rank.iter().enumerate().filter(|(.., piece)| **piece == crate::Piece::Bishop).map(|(x, ..)| x % 2).sum::<usize>();
+            let king = rank.iter().position(|piece| *piece == crate::Piece::King).unwrap();
+            bishops == 1 && rank[..king].contains(&crate::Piece::Rook) && rank[king..].contains(&crate::Piece::Rook)
+        }));
+    }
+
+    #[test]
+    fn castling() {
+        let mut board = Board::from_fen("1r2k1r1/pppppppp/8/8/8/8/PPPPPPPP/1R2K1R1 w GBgb - 0 1").unwrap();
+        assert_eq!(board.shredder_fen(), "1r2k1r1/pppppppp/8/8/8/8/PPPPPPPP/1R2K1R1 w GBgb - 0 1");
+        assert_eq!(board.fen(), "1r2k1r1/pppppppp/8/8/8/8/PPPPPPPP/1R2K1R1 w KQkq - 0 1");
+        board.move_piece(mov("e1 g1")).unwrap();
+        assert_eq!(board.fen(), "1r2k1r1/pppppppp/8/8/8/8/PPPPPPPP/1R3RK1 b kq - 1 1");
+        board.move_piece(mov("e8 b8")).unwrap();
+        assert_eq!(board.fen(), "2kr2r1/pppppppp/8/8/8/8/PPPPPPPP/1R3RK1 w - - 2 2");
+
+        let mut board = Board::from_fen("4k3/8/8/8/8/8/8/RK5R w HA - 0 1").unwrap();
+        assert!(board.move_piece(mov("b1 c1")).is_ok());
+        assert_eq!(board.pieces.at(&Pos { x: 2, y: 0 }).map(|piece| piece.kind), Some(crate::Piece::King));
+
+        let mut board = Board::from_fen("4k3/8/8/8/8/8/8/RK5R w HA - 0 1").unwrap();
+        board.move_piece(mov("b1 a1")).unwrap();
+        assert_eq!(board.fen(), "4k3/8/8/8/8/8/8/2KR3R b - - 1 1");
+
+        // The castling rook shields the king from a1 until it moves.
+        let mut board = Board::from_fen("4k3/8/8/8/8/8/8/rRK5 w B - 0 1").unwrap();
+        assert_eq!(board.move_piece(mov("c1 b1")), Err(crate::MoveError::CastlingCheck));
+    }
+}
@@ -1,42 +1,56 @@
-// use alloc::vec::Vec;
+use alloc::vec::Vec;
 
-use crate::{BoardPiece, Move, Pos, Side};
+use crate::{BoardPiece, Move, Piece, Pos, Side};
 
 
-#[derive(Default)]
+#[derive(Default, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BoardHistory {
     moves: Vec<PreviousMove>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct PreviousMove {
     pub mov: Move,
     pub taken: Option<BoardPiece>,
+    /// The piece dropped from the hand, for which `mov` starts and ends on the same square.
+    pub dropped: Option<Piece>,
 }
 
 impl BoardHistory {
     pub fn add(&mut self, mov: Move, taken: Option<BoardPiece>) {
-        self.moves.push(PreviousMove { mov, taken });
+        self.moves.push(PreviousMove { mov, taken, dropped: None });
+    }
+
+    pub fn add_drop(&mut self, piece: Piece, pos: Pos) {
+        self.moves.push(PreviousMove { mov: Move::new(pos, pos), taken: None, dropped: Some(piece) });
     }
 
     // pub fn undo(&mut self) -> Option<PreviousMove> {
     //     self.moves.pop().map(f)
     // }
 
+    /// The moves and drops played, oldest first.
+    pub fn moves(&self) -> &[PreviousMove] {
+        &self.moves
+    }
+
     pub fn taken(&self, side: Side) -> impl Iterator<Item = &BoardPiece> {
         self.moves.iter().filter_map(move |piece| piece.taken.as_ref().filter(|piece| piece.side == side))
     }
 
     pub fn of(&self, pos: Pos) -> impl Iterator<Item = Pos> + '_ {
-        use core::sync::atomic::{AtomicI8, Ordering::Relaxed};
-        let x = AtomicI8::new(pos.x);
-        let y = AtomicI8::new(pos.y);
-        self.moves.iter().rev().filter_map(move |prev| {
-            (prev.mov.to.x == x.load(Relaxed) && prev.mov.to.y == y.load(Relaxed)).then(|| {
-                x.store(prev.mov.from.x, Relaxed);
-                y.store(prev.mov.from.y, Relaxed);
-                prev.mov.from
-            })
-        }).rev().chain(core::iter::once(pos))
+        let mut current = Some(pos);
+        let mut path = self.moves.iter().rev().filter_map(|prev| {
+            if current != Some(prev.mov.to) {
+                return None;
+            }
+            current = prev.dropped.is_none().then_some(prev.mov.from);
+            current
+        }).collect::<Vec<_>>();
+        path.reverse();
+        path.into_iter().chain(core::iter::once(pos))
     }
 
     pub fn reset(&mut self) {
@@ -0,0 +1,301 @@
+use core::fmt::{Display, Formatter, Result as FmtResult};
+
+use core::error::Error;
+
+use alloc::format;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::{Board, Castling, Move, Outcome, ParseMoveError, Piece, Pos, Side};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ParseSanError {
+    Empty,
+    /// No legal move is written this way.
+    Illegal,
+    /// More than one legal move is written this way.
+    Ambiguous,
+}
+
+impl Display for ParseSanError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str(match self {
+            Self::Empty => "Empty move",
+            Self::Illegal => "No legal move matches",
+            Self::Ambiguous => "More than one legal move matches",
+        })
+    }
+}
+
+impl Error for ParseSanError {}
+
+impl Board {
+
+    /// Whether a move castles, and if so on which side (`true` for the king side).
+    pub fn castles(&self, mov: Move) -> Option<bool> {
+        let piece = self.pieces.at(&mov.from).filter(|piece| piece.kind == Piece::King)?;
+        let mov = piece.resolve(self, mov);
+        self.pieces.at(&mov.to).filter(|rook| rook.side == piece.side && rook.kind == Piece::Rook).map(|_| mov.to.x > mov.from.x)
+    }
+
+    /// Whether a move takes a pawn to the last rank, where it is promoted to a queen or `mov.promotion`.
+    pub fn promotes(&self, mov: Move) -> bool {
+        self.pieces.at(&mov.from).is_some_and(|piece| piece.kind == Piece::Pawn && mov.to.y == self.size().origin(piece.side.other()))
+    }
+
+    /// Whether castling has to be written as in Chess960, because a side may castle with its king off the middle
+    /// file (e in standard chess) or with a rook off the corners.
+    pub fn is_chess960(&self) -> bool {
+        let width = self.size().width;
+        Side::sides().into_iter().any(|side| [false, true].into_iter().any(|king_side| {
+            self.state.castling.get(side, king_side).is_some_and(|file| {
+                file != if king_side { width - 1 } else { 0 } || self.king(side).is_none_or(|king| king.x != width / 2)
+            })
+        }))
+    }
+
+    /// A move in UCI notation (e.g. "e2e4", "e7e8q"). Castling is written as the king moving two squares, or as
+    /// the king taking its rook for Chess960.
+    pub fn uci(&self, mov: Move, chess960: bool) -> String {
+        let mut uci = String::new();
+        let to = match self.castles(mov) {
+            Some(king_side) if !chess960 => {
+                let mov = Castling::resolve(self, mov, self.state.turn.side);
+                Pos { x: Castling::destinations(king_side, self.size().width).0, y: mov.to.y }
+            },
+            _ => mov.to,
+        };
+        uci.push_str(&mov.from.to_string());
+        uci.push_str(&to.to_string());
+        if self.promotes(mov) {
+            uci.push(mov.promotion.unwrap_or(Piece::Queen).letter().to_ascii_lowercase());
+        }
+        uci
+    }
+
+    /// Reads a move in UCI notation, which may still be illegal.
+    pub fn parse_uci(&self, uci: &str) -> Result<Move, ParseMoveError> {
+        fn square(s: &str) -> Option<(&str, &str)> {
+            let digits = s.get(1..)?.find(|c: char| !c.is_ascii_digit()).map_or(s.len(), |i| i + 1);
+            Some(s.split_at(digits))
+        }
+        let (from, rest) = square(uci).ok_or(ParseMoveError::Length)?;
+        let (to, promotion) = square(rest).ok_or(ParseMoveError::Length)?;
+        let mov = Move::new(
+            from.parse().map_err(|err| ParseMoveError::Pos(true, err))?,
+            to.parse().map_err(|err| ParseMoveError::Pos(false, err))?,
+        );
+        let mut letters = promotion.chars();
+        match (letters.next(), letters.next()) {
+            (None, ..) => Ok(mov),
+            (Some(letter), None) if self.promotes(mov) => Piece::promotion(letter).map(|kind| mov.promoting(kind)).ok_or(ParseMoveError::Promotion),
+            _ => Err(ParseMoveError::Promotion),
+        }
+    }
+
+    /// A legal move in Standard Algebraic Notation (e.g. "Nf3", "exd5", "O-O", "e8=Q#").
+    pub fn san(&self, mov: Move) -> String {
+        let mut san = self.san_move(mov);
+        let mut after = self.clone();
+        if after.move_piece(mov).is_ok() {
+            match after.state.outcome() {
+                Some(Outcome::Win(.., "checkmate")) => san.push('#'),
+                _ if after.state.check().is_some() => san.push('+'),
+                _ => (),
+            }
+        }
+        san
+    }
+
+    /// A drop from the hand in SAN (e.g. "N@f7+"), with pawns written as "P@e4".
+    pub fn san_drop(&self, kind: Piece, pos: Pos) -> String {
+        let mut san = format!("{}@{pos}", kind.letter());
+        let mut after = self.clone();
+        if after.drop_piece(kind, pos).is_ok() {
+            match after.state.outcome() {
+                Some(Outcome::Win(.., "checkmate")) => san.push('#'),
+                _ if after.state.check().is_some() => san.push('+'),
+                _ => (),
+            }
+        }
+        san
+    }
+
+    /// The SAN of a move, without marking check.
+    fn san_move(&self, mov: Move) -> String {
+        self.san_with(mov, None)
+    }
+
+    /// The SAN of a move with the given part of the square moved from for pieces, or only as much as is needed
+    /// to tell it apart from other moves if `None`.
+    fn san_with(&self, mov: Move, from: Option<&str>) -> String {
+        let Some(piece) = self.pieces.at(&mov.from) else {
+            return mov.to_string();
+        };
+        match self.castles(mov) {
+            Some(true) => return "O-O".to_string(),
+            Some(false) => return "O-O-O".to_string(),
+            None => (),
+        }
+
+        let mut san = String::new();
+        let capture = self.capture(mov).is_some();
+        match piece.kind {
+            Piece::Pawn => if capture {
+                san.push_str(&mov.from.to_string()[..1]);
+            },
+            kind => {
+                san.push(kind.letter());
+                let others = match from {
+                    Some(from) => {
+                        san.push_str(from);
+                        Vec::new()
+                    },
+                    None => self.moves().filter(|other| {
+                        other.to == mov.to && other.from != mov.from && self.pieces.at(&other.from).is_some_and(|other| other.kind == kind)
+                    }).collect(),
+                };
+                if !others.is_empty() {
+                    let from = mov.from.to_string();
+                    let (file, rank) = from.split_at(1);
+                    if others.iter().all(|other| other.from.x != mov.from.x) {
+                        san.push_str(file);
+                    } else if others.iter().all(|other| other.from.y != mov.from.y) {
+                        san.push_str(rank);
+                    } else {
+                        san.push_str(&from);
+                    }
+                }
+            },
+        }
+        if capture {
+            san.push('x');
+        }
+        san.push_str(&mov.to.to_string());
+        if self.promotes(mov) {
+            san.push('=');
+            san.push(mov.promotion.unwrap_or(Piece::Queen).letter());
+        }
+        san
+    }
+
+    /// Reads a move in SAN, checking that it is legal. Check marks, annotations, a missing capture or
+    /// promotion mark and more of the square moved from than needed are allowed.
+    pub fn parse_san(&self, san: &str) -> Result<Move, ParseSanError> {
+        fn loose(san: &str) -> String {
+            san.chars().filter(|c| !matches!(c, 'x' | '=' | '-' | ':')).collect()
+        }
+        /// The square a loosely written move ends on, after any promotion letter.
+        fn destination(san: &str) -> Option<Pos> {
+            let san = san.trim_end_matches(|c: char| c.is_ascii_uppercase());
+            let rank = san.trim_end_matches(|c: char| c.is_ascii_digit()).len();
+            san.get(rank.checked_sub(1)?..)?.parse().ok()
+        }
+        let san = san.trim().trim_end_matches(['+', '#', '!', '?']);
+        if san.is_empty() {
+            return Err(ParseSanError::Empty);
+        }
+        let san = match san.starts_with("0-0") {
+            true => san.replace('0', "O"),
+            false => san.to_string(),
+        };
+        let san = loose(&san);
+        let forms = |mov: &Move| {
+            let from = mov.from.to_string();
+            let (file, rank) = from.split_at(1);
+            let forms = [None, Some(""), Some(file), Some(rank), Some(from.as_str())];
+            forms.iter().any(|from| loose(&self.san_with(*mov, *from)) == san)
+        };
+        let to = destination(&san);
+        let mut matches = self.moves().filter(|mov| san.starts_with("OO") || Some(mov.to) == to).filter(forms);
+        match (matches.next(), matches.next()) {
+            (Some(mov), None) => Ok(mov),
+            (Some(..), Some(..)) => Err(ParseSanError::Ambiguous),
+            (None, ..) => Err(ParseSanError::Illegal),
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Board, Move, MoveError, ParseSanError, Piece, Pos, Rules, Size};
+
+    fn pos(s: &str) -> Pos {
+        s.parse().unwrap()
+    }
+
+    fn play(board: &mut Board, sans: &[&str]) {
+        for san in sans {
+            let mov = board.parse_san(san).unwrap_or_else(|err| panic!("Could not read {san}: {err}"));
+            assert_eq!(board.san(mov), *san);
+            board.move_piece(mov).unwrap();
+        }
+    }
+
+    #[test]
+    fn san() {
+        let mut board = Board::default();
+        play(&mut board, &["e4", "e5", "Nf3", "Nc6", "Bb5", "a6", "Bxc6", "dxc6", "O-O", "Bg4", "h3", "Qd6", "hxg4"]);
+        assert_eq!(board.fen(), "r3kbnr/1pp2ppp/p1pq4/4p3/4P1P1/5N2/PPPP1PP1/RNBQ1RK1 b kq - 0 7");
+        assert_eq!(board.parse_san("O-O-O"), board.parse_san("0-0-0"));
+        assert_eq!(board.parse_san("Ke2"), Err(ParseSanError::Illegal));
+
+        let board = Board::from_fen("4k3/8/8/3p4/4P3/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(board.parse_san("ed5"), board.parse_san("exd5"));
+        assert!(board.parse_san("ed5").is_ok());
+
+        let board = Board::from_fen("4k3/8/8/8/8/8/4K3/R6R w - - 0 1").unwrap();
+        assert_eq!(board.parse_san("Rd1"), Err(ParseSanError::Ambiguous));
+        assert_eq!(board.san(board.parse_san("Rad1").unwrap()), "Rad1");
+
+        let mut board = Board::from_fen("7k/P7/6K1/8/8/8/8/8 w - - 0 1").unwrap();
+        play(&mut board, &["a8=Q#"]);
+
+        let mut board = Board::from_fen("2r1k3/1P6/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        assert_eq!(board.parse_san("bxc8=N").map(|mov| mov.promotion), Ok(Some(Piece::Knight)));
+        assert_eq!(board.parse_san("b8=Q"), Ok(Move::new(pos("b7"), pos("b8"))));
+        play(&mut board, &["bxc8=R+"]);
+        assert_eq!(board.pieces.at(&pos("c8")).map(|piece| piece.kind), Some(Piece::Rook));
+
+        // Squares are read whole, so "a1" is not found in "a10".
+        struct Tall;
+        impl Rules for Tall {
+            fn name(&self) -> &'static str {
+                "tall"
+            }
+
+            fn size(&self) -> Size {
+                Size { width: 8, height: 10 }
+            }
+        }
+        let board = Board::from_variant_fen("4k3/8/8/8/8/R7/8/8/8/4K3 w - - 0 1", &Tall).unwrap();
+        assert_eq!(board.parse_san("Ra10").map(|mov| mov.to), Ok(pos("a10")));
+        assert_eq!(board.parse_san("Ra1").map(|mov| mov.to), Ok(pos("a1")));
+
+        let mut board = Board::from_fen("3k4/8/8/8/8/8/8/R3K2R w KQ - 0 1").unwrap();
+        play(&mut board, &["O-O-O+"]);
+    }
+
+    #[test]
+    fn uci() {
+        let board = Board::from_fen("4k3/P7/8/8/8/8/8/R3K2R w KQ - 0 1").unwrap();
+        let castle = board.parse_uci("e1g1").unwrap();
+        assert_eq!(board.uci(castle, false), "e1g1");
+        assert_eq!(board.uci(board.parse_uci("e1h1").unwrap(), true), "e1h1");
+        assert!(!board.is_chess960() && Board::chess960(0).unwrap().is_chess960());
+        assert!(Board::from_fen("4k3/8/8/8/8/8/8/RK5R w HA - 0 1").unwrap().is_chess960());
+        assert_eq!(board.uci(board.parse_uci("a7a8q").unwrap(), false), "a7a8q");
+        assert_eq!(board.parse_uci("a7a8q"), board.parse_uci("a7a8"));
+        let knight = board.parse_uci("a7a8n").unwrap();
+        assert_eq!((knight.promotion, board.uci(knight, false), board.san(knight)), (Some(Piece::Knight), "a7a8n".to_string(), "a8=N".to_string()));
+        assert_eq!(knight.to_string().parse::<Move>(), Ok(knight));
+        let mut after = board.clone();
+        after.move_piece(knight).unwrap();
+        assert_eq!(after.fen(), "N3k3/8/8/8/8/8/8/R3K2R b KQ - 0 1");
+        assert!(board.parse_uci("a7a8k").is_err());
+        assert_eq!(board.clone().move_piece(Move { promotion: Some(Piece::Knight), ..board.parse_uci("e1e2").unwrap() }), Err(MoveError::Promotion));
+        assert!(board.parse_uci("e2").is_err());
+        assert_eq!(board.parse_uci("a1a10").map(|mov| mov.to.y), Ok(9));
+    }
+}
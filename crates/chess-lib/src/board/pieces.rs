@@ -1,12 +1,18 @@
 // use alloc::vec::Vec;
-use std::collections::HashMap;
+use alloc::vec::Vec;
+
+use hashbrown::HashMap;
 
 use crate::piece::{BoardPiece, Piece};
-use crate::{Move, Pos, PosInt, Side};
+use crate::{Move, Pos, PosInt, Side, Size};
 
 
+#[derive(Clone)]
 pub struct Pieces(HashMap<Pos, BoardPiece>, Vec<PieceUpdate>);
 
+/// A change to a square: a piece put on it or taken off, or the piece on it becoming another kind.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum PieceUpdate {
     Update(Pos, Option<BoardPiece>),
     Modify(Pos, Piece),
@@ -20,7 +26,7 @@ impl Pieces {
 
     pub fn iter_with_move<'a>(&'a self, mov: &'a Move) -> impl Iterator<Item = (&'a Pos, &'a BoardPiece)> + 'a {
         let copy = self.at(&mov.from).expect("Could not get piece to copy for iter_with_move!");
-        self.0.iter().filter(|(pos, ..)| *pos != &mov.from && *pos != &mov.to).chain(std::iter::once((&mov.to, copy)))
+        self.0.iter().filter(|(pos, ..)| *pos != &mov.from && *pos != &mov.to).chain(core::iter::once((&mov.to, copy)))
     }
     
     pub fn of(&self, side: Side) -> impl Iterator<Item=(&Pos, &BoardPiece)> {
@@ -39,16 +45,31 @@ impl Pieces {
         self.0.remove(pos)
     }
 
+    pub fn insert(&mut self, pos: Pos, piece: BoardPiece) -> Option<BoardPiece> {
+        self.0.insert(pos, piece)
+    }
+
     pub fn move_piece(&mut self, mov: Move) -> Option<BoardPiece> {
         let piece = self.take(&mov.from).expect("Could not get moved piece!");
-        let taken = self.0.insert(mov.to, piece);
-        taken
+        self.0.insert(mov.to, piece)
     }
 
     pub fn events(&mut self) -> impl Iterator<Item = PieceUpdate> + '_ {
         self.1.drain(..)
     }
 
+    /// The updates that turn these pieces into `other`, such as those a move made, ordered by square.
+    pub fn diff(&self, other: &Pieces) -> Vec<PieceUpdate> {
+        let mut squares = self.0.keys().chain(other.0.keys()).copied().collect::<Vec<_>>();
+        squares.sort_by_key(|pos| (pos.y, pos.x));
+        squares.dedup();
+        squares.into_iter().filter_map(|pos| match (self.at(&pos), other.at(&pos)) {
+            (Some(before), Some(after)) if before == after => None,
+            (Some(before), Some(after)) if before.side == after.side => Some(PieceUpdate::Modify(pos, after.kind)),
+            (.., after) => Some(PieceUpdate::Update(pos, after.copied())),
+        }).collect()
+    }
+
     pub fn clear(&mut self) {
         self.0.drain().for_each(|(pos, ..)| {
             self.1.push(PieceUpdate::Update(pos, None));
@@ -56,7 +77,11 @@ impl Pieces {
     }
     
     pub fn fill(&mut self) {
-        self.0.extend(Self::default_board().inspect(|(pos, piece)| self.1.push(PieceUpdate::Update(*pos, Some(*piece)))));
+        self.fill_with(Self::default_board());
+    }
+
+    pub fn fill_with(&mut self, pieces: impl IntoIterator<Item = (Pos, BoardPiece)>) {
+        self.0.extend(pieces.into_iter().inspect(|(pos, piece)| self.1.push(PieceUpdate::Update(*pos, Some(*piece)))));
     }
 
     pub fn reset(&mut self) {
@@ -64,44 +89,95 @@ impl Pieces {
         self.fill();
     }
 
-    fn default_board() -> impl Iterator<Item = (Pos, BoardPiece)> {
+    pub const STANDARD: [Piece; 8] = [Piece::Rook, Piece::Knight, Piece::Bishop, Piece::Queen, Piece::King, Piece::Bishop, Piece::Knight, Piece::Rook];
 
-        fn mirror(x: PosInt, side: Side, kind: Piece) -> impl IntoIterator<Item = (Pos, BoardPiece)> {
-            [(Pos { x, y: side.origin() }, BoardPiece {
-                kind,
-                side,
-            }), (Pos { x: 7 - x, y: side.origin() }, BoardPiece {
-                kind,
-                side,
-            })]
+    /// The back rank of Chess960 start position `index` (0 - 959) in Scharnagl's numbering, where 518 is the standard setup.
+    pub fn chess960(index: u16) -> Option<[Piece; 8]> {
+        if index >= 960 {
+            return None;
         }
 
-        fn side(side: Side) -> impl Iterator<Item = (Pos, BoardPiece)> {
-                (0..8).into_iter().map(move |x| (Pos { x, y: side.offset(1) }, BoardPiece {
+        let mut rank = [None; 8];
+        let mut n = index as usize;
+
+        rank[(n % 4) * 2 + 1] = Some(Piece::Bishop);
+        n /= 4;
+        rank[(n % 4) * 2] = Some(Piece::Bishop);
+        n /= 4;
+
+        fn place(rank: &mut [Option<Piece>; 8], nth: usize, piece: Piece) {
+            let x = (0..8).filter(|x| rank[*x].is_none()).nth(nth).expect("Could not find an empty square for Chess960 setup!");
+            rank[x] = Some(piece);
+        }
+
+        place(&mut rank, n % 6, Piece::Queen);
+        n /= 6;
+
+        const KNIGHTS: [(usize, usize); 10] = [(0, 1), (0, 2), (0, 3), (0, 4), (1, 2), (1, 3), (1, 4), (2, 3), (2, 4), (3, 4)];
+        let (first, second) = KNIGHTS[n];
+        place(&mut rank, second, Piece::Knight);
+        place(&mut rank, first, Piece::Knight);
+
+        for piece in [Piece::Rook, Piece::King, Piece::Rook] {
+            place(&mut rank, 0, piece);
+        }
+
+        Some(rank.map(|piece| piece.expect("Could not fill Chess960 back rank!")))
+    }
+
+    pub fn setup(back_rank: [Piece; 8]) -> impl Iterator<Item = (Pos, BoardPiece)> {
+
+        fn side(side: Side, back_rank: [Piece; 8]) -> impl Iterator<Item = (Pos, BoardPiece)> {
+                (0..8).map(move |x| (Pos { x, y: Size::STANDARD.offset(side, 1) }, BoardPiece {
                     kind: Piece::Pawn,
                     side,
                 }))
-                .chain(mirror(0, side, Piece::Rook))
-                .chain(mirror(1, side, Piece::Knight))
-                .chain(mirror(2, side, Piece::Bishop))
-                .chain(std::iter::once((Pos { x: 3, y: side.origin() }, BoardPiece {
-                    kind: Piece::Queen,
-                    side,
-                })))
-                .chain(std::iter::once((Pos { x: 4, y: side.origin() }, BoardPiece {
-                    kind: Piece::King,
+                .chain(back_rank.into_iter().zip(0..).map(move |(kind, x): (Piece, PosInt)| (Pos { x, y: Size::STANDARD.origin(side) }, BoardPiece {
+                    kind,
                     side,
                 })))
         }
         
-        side(Side::White).chain(side(Side::Black))
+        side(Side::White, back_rank).chain(side(Side::Black, back_rank))
+    }
+
+    fn default_board() -> impl Iterator<Item = (Pos, BoardPiece)> {
+        Self::setup(Self::STANDARD)
     }
     
     
 }
 
+impl FromIterator<(Pos, BoardPiece)> for Pieces {
+    fn from_iter<T: IntoIterator<Item = (Pos, BoardPiece)>>(iter: T) -> Self {
+        Self(iter.into_iter().collect(), Vec::new())
+    }
+}
+
 impl Default for Pieces {
     fn default() -> Self {
-        Self(Self::default_board().collect(), Vec::new())
+        Self::default_board().collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Board, BoardPiece, Piece, PieceUpdate, Side};
+
+    #[test]
+    fn diff() {
+        let mut board = Board::default();
+        for mov in ["e2 e4", "d7 d5"] {
+            board.move_piece(mov.parse().unwrap()).unwrap();
+        }
+        let before = board.pieces.clone();
+        board.move_piece("e4 d5".parse().unwrap()).unwrap();
+        let pawn = BoardPiece { kind: Piece::Pawn, side: Side::White };
+        assert_eq!(before.diff(&board.pieces), [PieceUpdate::Update("e4".parse().unwrap(), None), PieceUpdate::Update("d5".parse().unwrap(), Some(pawn))]);
+        assert!(board.pieces.diff(&board.pieces).is_empty());
+
+        let mut queen = board.pieces.clone();
+        queen.insert("d5".parse().unwrap(), BoardPiece { kind: Piece::Queen, side: Side::White });
+        assert_eq!(board.pieces.diff(&queen), [PieceUpdate::Modify("d5".parse().unwrap(), Piece::Queen)]);
     }
-}
\ No newline at end of file
+}
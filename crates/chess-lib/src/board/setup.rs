@@ -0,0 +1,221 @@
+use core::fmt::{Display, Formatter, Result as FmtResult};
+
+use core::error::Error;
+
+use crate::{Board, BoardPiece, CastlingRights, ParseFenError, Piece, Pos, Rules, Side, Turn};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SetupError {
+    /// A piece was placed off the board.
+    OffBoard(Pos),
+    /// A side does not have exactly one king.
+    Kings(Side),
+    /// A pawn is on a back rank it can not be on.
+    PawnRank(Pos),
+    /// The side that just moved is in check.
+    Check(Side),
+    /// A side has castling rights without its king and rook on their squares.
+    Castling(Side),
+    /// The en passant square does not follow a double move.
+    EnPassant(Pos),
+}
+
+impl Display for SetupError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::OffBoard(pos) => write!(f, "Piece at {pos} is off the board"),
+            Self::Kings(side) => write!(f, "{side:?} must have exactly one king"),
+            Self::PawnRank(pos) => write!(f, "Pawn at {pos} is on a back rank"),
+            Self::Check(side) => write!(f, "{side:?} is in check but not to move"),
+            Self::Castling(side) => write!(f, "{side:?} can not castle without its king and rook"),
+            Self::EnPassant(pos) => write!(f, "No pawn can be taken en passant on {pos}"),
+        }
+    }
+}
+
+impl Error for SetupError {}
+
+/// Sets up a position piece by piece, which is checked to be playable once built.
+#[derive(Clone)]
+pub struct Setup {
+    board: Board,
+}
+
+impl Setup {
+
+    /// An empty board of a variant, with white to move.
+    pub fn new(rules: &'static dyn Rules) -> Self {
+        let mut board = Board::new(rules);
+        board.pieces.clear();
+        board.state.reset();
+        board.state.castling = CastlingRights::NONE;
+        Self { board }
+    }
+
+    /// The position so far, which may not be valid yet.
+    pub fn board(&self) -> &Board {
+        &self.board
+    }
+
+    pub fn place(&mut self, pos: Pos, piece: BoardPiece) -> &mut Self {
+        self.board.pieces.insert(pos, piece);
+        self.board.state.promoted.retain(|promoted| *promoted != pos);
+        self
+    }
+
+    pub fn remove(&mut self, pos: Pos) -> &mut Self {
+        self.board.pieces.take(&pos);
+        self.board.state.promoted.retain(|promoted| *promoted != pos);
+        self
+    }
+
+    pub fn clear(&mut self) -> &mut Self {
+        self.board.pieces.clear();
+        self.board.state.promoted.clear();
+        self
+    }
+
+    pub fn turn(&mut self, side: Side) -> &mut Self {
+        self.board.state.turn.side = side;
+        self
+    }
+
+    pub fn castling(&mut self, castling: CastlingRights) -> &mut Self {
+        self.board.state.castling = castling;
+        self
+    }
+
+    /// Sets the castling rights from their FEN field, for the pieces placed so far.
+    pub fn castling_fen(&mut self, castling: &str) -> Result<&mut Self, ParseFenError> {
+        self.board.state.castling = Board::castling(&self.board.pieces, self.board.size(), castling)?;
+        Ok(self)
+    }
+
+    pub fn en_passant(&mut self, en_passant: Option<Pos>) -> &mut Self {
+        self.board.state.en_passant = en_passant;
+        self
+    }
+
+    /// Sets the halfmove clock and the number of the move to play, starting at 1.
+    pub fn counters(&mut self, halfmoves: u32, fullmoves: usize) -> &mut Self {
+        self.board.state.halfmoves = halfmoves;
+        self.board.state.turn = Turn { side: self.board.state.turn.side, no: fullmoves.saturating_sub(1) };
+        self
+    }
+
+    /// Checks that the position can be played under the rules of its variant.
+    pub fn validate(&self) -> Result<(), SetupError> {
+        let board = &self.board;
+        let size = board.size();
+        let start = Board::new(board.rules);
+
+        if let Some((pos, ..)) = board.pieces.iter().find(|(pos, ..)| !size.contains(**pos)) {
+            return Err(SetupError::OffBoard(*pos));
+        }
+
+        for side in Side::sides() {
+            let kings = board.pieces.iter().filter(|(.., piece)| piece.is_king(side)).count();
+            if board.rules.royal() && start.king(side).is_some() && kings != 1 {
+                return Err(SetupError::Kings(side));
+            }
+
+            // Pawns may start on their own back rank in some variants, such as horde.
+            let own_rank = start.pieces.of(side).any(|(pos, piece)| piece.kind == Piece::Pawn && pos.y == size.origin(side));
+            let pawn = board.pieces.of(side).find(|(pos, piece)| {
+                piece.kind == Piece::Pawn && (pos.y == size.origin(side.other()) || (!own_rank && pos.y == size.origin(side)))
+            });
+            if let Some((pos, ..)) = pawn {
+                return Err(SetupError::PawnRank(*pos));
+            }
+
+            for king_side in [true, false] {
+                if let Some(x) = board.state.castling.get(side, king_side) {
+                    let y = size.origin(side);
+                    let king = (0..size.width).find(|x| board.pieces.at(&Pos { x: *x, y }).is_some_and(|piece| piece.is_king(side)));
+                    let rook = board.pieces.at(&Pos { x, y }).is_some_and(|piece| piece.side == side && piece.kind == Piece::Rook);
+                    if !rook || king.is_none_or(|king| (x > king) != king_side) {
+                        return Err(SetupError::Castling(side));
+                    }
+                }
+            }
+        }
+
+        let side = board.state.turn.side;
+        if board.rules.royal() && board.king(side.other()).is_some_and(|king| board.attacked(king, side)) {
+            return Err(SetupError::Check(side.other()));
+        }
+
+        if let Some(pos) = board.state.en_passant {
+            let other = side.other();
+            let pawn = board.pieces.at(&Pos { x: pos.x, y: pos.y + other.forward() }).is_some_and(|piece| piece.side == other && piece.kind == Piece::Pawn);
+            let empty = [pos, Pos { x: pos.x, y: pos.y - other.forward() }].iter().all(|pos| board.pieces.at(pos).is_none());
+            if pos.y != size.offset(other, 2) || !pawn || !empty {
+                return Err(SetupError::EnPassant(pos));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// The board, ready to play, if the position is valid.
+    pub fn build(self) -> Result<Board, SetupError> {
+        self.validate()?;
+        let mut board = self.board;
+        board.history.reset();
+        board.refresh();
+        Ok(board)
+    }
+
+}
+
+impl From<Board> for Setup {
+    fn from(board: Board) -> Self {
+        Self { board }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Board, BoardPiece, CastlingRights, Horde, Piece, Pos, Side};
+
+    use super::{Setup, SetupError};
+
+    fn pos(s: &str) -> Pos {
+        s.parse().unwrap()
+    }
+
+    fn piece(kind: Piece, side: Side) -> BoardPiece {
+        BoardPiece { kind, side }
+    }
+
+    #[test]
+    fn setup() {
+        let mut setup = Setup::new(&crate::Standard);
+        setup.place(pos("e1"), piece(Piece::King, Side::White)).place(pos("h1"), piece(Piece::Rook, Side::White));
+        assert_eq!(setup.validate(), Err(SetupError::Kings(Side::Black)));
+
+        setup.place(pos("e8"), piece(Piece::King, Side::Black)).place(pos("a8"), piece(Piece::Pawn, Side::White));
+        assert_eq!(setup.validate(), Err(SetupError::PawnRank(pos("a8"))));
+
+        setup.remove(pos("a8")).place(pos("e7"), piece(Piece::Queen, Side::White));
+        assert_eq!(setup.validate(), Err(SetupError::Check(Side::Black)));
+
+        setup.turn(Side::Black);
+        let mut castling = CastlingRights::NONE;
+        castling.set(Side::White, false, Some(0));
+        setup.castling(castling);
+        assert_eq!(setup.validate(), Err(SetupError::Castling(Side::White)));
+
+        castling = CastlingRights::NONE;
+        castling.set(Side::White, true, Some(7));
+        setup.castling(castling).en_passant(Some(pos("d3")));
+        assert_eq!(setup.validate(), Err(SetupError::EnPassant(pos("d3"))));
+
+        setup.place(pos("d4"), piece(Piece::Pawn, Side::White));
+        let board = setup.build().unwrap();
+        assert_eq!(board.fen(), "4k3/4Q3/8/8/3P4/8/8/4K2R b K d3 0 1");
+        assert!(board.state.check().is_some());
+
+        assert!(Setup::from(Board::new(&Horde)).build().is_ok());
+    }
+}
@@ -0,0 +1,400 @@
+use core::fmt::{Display, Formatter, Result as FmtResult};
+use core::str::FromStr;
+use core::time::Duration;
+
+use core::error::Error;
+#[cfg(target_has_atomic = "64")]
+use core::sync::atomic::{AtomicU64, Ordering};
+
+use alloc::sync::Arc;
+use alloc::vec;
+use alloc::vec::Vec;
+
+#[cfg(feature = "std")]
+use std::time::Instant;
+
+use crate::{Side, Turn};
+
+/// Monotonic time, measured from an arbitrary fixed point.
+pub trait TimeSource: Send + Sync {
+    fn now(&self) -> Duration;
+}
+
+/// Real time, backed by [`Instant`].
+#[cfg(feature = "std")]
+pub struct MonotonicTime(Instant);
+
+#[cfg(feature = "std")]
+impl Default for MonotonicTime {
+    fn default() -> Self {
+        Self(Instant::now())
+    }
+}
+
+#[cfg(feature = "std")]
+impl TimeSource for MonotonicTime {
+    fn now(&self) -> Duration {
+        self.0.elapsed()
+    }
+}
+
+/// Time that only moves when told to, for tests and replays.
+#[cfg(target_has_atomic = "64")]
+#[derive(Default, Clone)]
+pub struct ManualTime(Arc<AtomicU64>);
+
+#[cfg(target_has_atomic = "64")]
+impl ManualTime {
+    pub fn advance(&self, by: Duration) {
+        self.0.fetch_add(by.as_millis() as u64, Ordering::Relaxed);
+    }
+}
+
+#[cfg(target_has_atomic = "64")]
+impl TimeSource for ManualTime {
+    fn now(&self) -> Duration {
+        Duration::from_millis(self.0.load(Ordering::Relaxed))
+    }
+}
+
+#[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Bonus {
+    #[default]
+    None,
+    /// Fischer increment, added after every move.
+    Increment(Duration),
+    /// Simple (US) delay, the clock only starts counting once the delay has passed.
+    Delay(Duration),
+    /// Bronstein delay, the time used is given back up to the delay.
+    Bronstein(Duration),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Stage {
+    /// Moves to be played in this stage, or `None` for the rest of the game.
+    pub moves: Option<u32>,
+    pub time: Duration,
+    pub bonus: Bonus,
+}
+
+/// A list of stages as in the PGN `TimeControl` tag, e.g. `40/5400+30:1800+30`.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub struct TimeControl {
+    stages: Vec<Stage>,
+}
+
+impl TimeControl {
+
+    pub fn new(stages: Vec<Stage>) -> Option<Self> {
+        (!stages.is_empty()).then_some(Self { stages })
+    }
+
+    pub fn sudden_death(time: Duration, bonus: Bonus) -> Self {
+        Self { stages: vec![Stage { moves: None, time, bonus }] }
+    }
+
+    pub fn stages(&self) -> &[Stage] {
+        &self.stages
+    }
+
+    /// The last stage repeats if it is itself limited to a number of moves.
+    pub fn stage(&self, index: usize) -> Option<&Stage> {
+        match self.stages.get(index) {
+            Some(stage) => Some(stage),
+            None => self.stages.last().filter(|stage| stage.moves.is_some()),
+        }
+    }
+
+}
+
+impl Display for Stage {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        if let Some(moves) = self.moves {
+            write!(f, "{moves}/")?;
+        }
+        write!(f, "{}", self.time.as_secs())?;
+        match self.bonus {
+            Bonus::None => Ok(()),
+            Bonus::Increment(time) => write!(f, "+{}", time.as_secs()),
+            Bonus::Delay(time) => write!(f, "d{}", time.as_secs()),
+            Bonus::Bronstein(time) => write!(f, "b{}", time.as_secs()),
+        }
+    }
+}
+
+impl Display for TimeControl {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        for (i, stage) in self.stages.iter().enumerate() {
+            if i != 0 {
+                write!(f, ":")?;
+            }
+            stage.fmt(f)?;
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+pub enum ParseTimeControlError {
+    Empty,
+    Moves,
+    Time,
+    Bonus,
+}
+
+impl Display for ParseTimeControlError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        write!(f, "Invalid {}", match self {
+            Self::Empty => "(empty) time control",
+            Self::Moves => "move count",
+            Self::Time => "time",
+            Self::Bonus => "increment / delay",
+        })
+    }
+}
+
+impl Error for ParseTimeControlError {}
+
+impl FromStr for Stage {
+    type Err = ParseTimeControlError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let (moves, rest) = match s.split_once('/') {
+            Some((moves, rest)) => (Some(moves.parse().map_err(|_| ParseTimeControlError::Moves)?), rest),
+            None => (None, s),
+        };
+        let seconds = |s: &str, err| s.parse().map(Duration::from_secs).map_err(|_| err);
+        let (time, bonus) = match rest.find(['+', 'd', 'b']) {
+            Some(i) => {
+                let bonus = seconds(&rest[i + 1..], ParseTimeControlError::Bonus)?;
+                (&rest[..i], match &rest[i..=i] {
+                    "+" => Bonus::Increment(bonus),
+                    "d" => Bonus::Delay(bonus),
+                    _ => Bonus::Bronstein(bonus),
+                })
+            },
+            None => (rest, Bonus::None),
+        };
+        Ok(Self { moves, time: seconds(time, ParseTimeControlError::Time)?, bonus })
+    }
+}
+
+impl FromStr for TimeControl {
+    type Err = ParseTimeControlError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let stages = s.trim().split(':').filter(|s| !s.is_empty()).map(str::parse).collect::<Result<Vec<_>, _>>()?;
+        Self::new(stages).ok_or(ParseTimeControlError::Empty)
+    }
+}
+
+#[derive(Clone)]
+pub struct Clock {
+    control: TimeControl,
+    source: Arc<dyn TimeSource>,
+    pub(crate) remaining: [Duration; 2],
+    pub(crate) moves: [u32; 2],
+    pub(crate) stages: [usize; 2],
+    /// The side whose clock is running, and when it was started.
+    running: Option<(Side, Duration)>,
+}
+
+impl Clock {
+
+    /// A clock running on real time, see [`Clock::with_source`] without `std`.
+    #[cfg(feature = "std")]
+    pub fn new(control: TimeControl) -> Self {
+        Self::with_source(control, Arc::new(MonotonicTime::default()))
+    }
+
+    pub fn with_source(control: TimeControl, source: Arc<dyn TimeSource>) -> Self {
+        let time = control.stages[0].time;
+        Self {
+            control,
+            source,
+            remaining: [time; 2],
+            moves: [0; 2],
+            stages: [0; 2],
+            running: None,
+        }
+    }
+
+    pub fn control(&self) -> &TimeControl {
+        &self.control
+    }
+
+    pub fn running(&self) -> Option<Side> {
+        self.running.map(|(side, ..)| side)
+    }
+
+    /// Moves made by a side so far.
+    pub fn moves(&self, side: Side) -> u32 {
+        self.moves[side as usize]
+    }
+
+    pub fn remaining(&self, side: Side) -> Duration {
+        match self.running {
+            Some((running, started)) if running == side => self.remaining[side as usize].saturating_sub(self.charge(side, self.source.now() - started)),
+            _ => self.remaining[side as usize],
+        }
+    }
+
+    pub fn flagged(&self) -> Option<Side> {
+        self.running().filter(|side| self.remaining(*side).is_zero())
+    }
+
+    pub fn start(&mut self, side: Side) {
+        self.running = Some((side, self.source.now()));
+    }
+
+    pub fn stop(&mut self) {
+        if let Some(side) = self.running() {
+            self.remaining[side as usize] = self.remaining(side);
+            self.running = None;
+        }
+    }
+
+    /// Ends the running side's move, then starts the clock of the side to move in `turn`.
+    /// The clock starts with the first switch if it was not already running.
+    pub fn switch(&mut self, turn: Turn) {
+        if let Some((side, started)) = self.running {
+            let used = self.source.now() - started;
+            let stage = self.current(side);
+            let index = side as usize;
+            self.remaining[index] = self.remaining[index].saturating_sub(self.charge(side, used));
+            if !self.remaining[index].is_zero() {
+                self.remaining[index] += match stage.bonus {
+                    Bonus::Increment(time) => time,
+                    Bonus::Bronstein(time) => time.min(used),
+                    Bonus::None | Bonus::Delay(..) => Duration::ZERO,
+                };
+            }
+            self.moves[index] += 1;
+            if stage.moves.is_some_and(|moves| self.moves_in_stage(side) == moves) {
+                self.stages[index] += 1;
+                self.remaining[index] += self.current(side).time;
+            }
+        }
+        self.start(turn.side);
+    }
+
+    /// A suggested budget for the side's next move, for engine time management.
+    pub fn allocation(&self, side: Side) -> Duration {
+        let stage = self.current(side);
+        let moves_to_go = stage.moves.map(|moves| moves - self.moves_in_stage(side)).unwrap_or(30).max(1);
+        let remaining = self.remaining(side);
+        let bonus = match stage.bonus {
+            Bonus::None => Duration::ZERO,
+            Bonus::Increment(time) | Bonus::Delay(time) | Bonus::Bronstein(time) => time,
+        };
+        (remaining / moves_to_go + bonus * 3 / 4).min(remaining / 2)
+    }
+
+    pub fn reset(&mut self) {
+        *self = Self::with_source(self.control.clone(), self.source.clone());
+    }
+
+    fn current(&self, side: Side) -> Stage {
+        *self.control.stage(self.stages[side as usize]).unwrap_or(&self.control.stages[self.control.stages.len() - 1])
+    }
+
+    fn moves_in_stage(&self, side: Side) -> u32 {
+        let index = self.stages[side as usize];
+        let passed = (0..index).filter_map(|i| self.control.stage(i)).filter_map(|stage| stage.moves).sum::<u32>();
+        self.moves(side) - passed
+    }
+
+    /// Time actually taken off the clock after thinking for `used`.
+    fn charge(&self, side: Side, used: Duration) -> Duration {
+        match self.current(side).bonus {
+            Bonus::Delay(delay) => used.saturating_sub(delay),
+            _ => used,
+        }
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{Board, Move, MoveError};
+
+    fn clock(control: &str) -> (Clock, ManualTime) {
+        let time = ManualTime::default();
+        (Clock::with_source(control.parse().unwrap(), Arc::new(time.clone())), time)
+    }
+
+    fn turn(side: Side) -> Turn {
+        Turn { side, no: 0 }
+    }
+
+    #[test]
+    fn parse() {
+        for control in ["300", "180+2", "40/5400+30:1800+30", "600d5", "900b10", "40/7200"] {
+            assert_eq!(control.parse::<TimeControl>().unwrap().to_string(), control);
+        }
+        assert!("".parse::<TimeControl>().is_err());
+        assert!("40/x".parse::<TimeControl>().is_err());
+        assert!("300+".parse::<TimeControl>().is_err());
+    }
+
+    #[test]
+    fn increment() {
+        let (mut clock, time) = clock("60+2");
+        clock.start(Side::White);
+        time.advance(Duration::from_secs(10));
+        assert_eq!(clock.remaining(Side::White), Duration::from_secs(50));
+        clock.switch(turn(Side::Black));
+        assert_eq!(clock.remaining(Side::White), Duration::from_secs(52));
+        assert_eq!(clock.running(), Some(Side::Black));
+    }
+
+    #[test]
+    fn delay() {
+        let (mut clock, time) = clock("60d5");
+        clock.start(Side::White);
+        time.advance(Duration::from_secs(3));
+        assert_eq!(clock.remaining(Side::White), Duration::from_secs(60));
+        time.advance(Duration::from_secs(4));
+        clock.switch(turn(Side::Black));
+        assert_eq!(clock.remaining(Side::White), Duration::from_secs(58));
+
+        let (mut clock, time) = self::clock("60b5");
+        clock.start(Side::White);
+        time.advance(Duration::from_secs(3));
+        clock.switch(turn(Side::Black));
+        assert_eq!(clock.remaining(Side::White), Duration::from_secs(60));
+        time.advance(Duration::from_secs(8));
+        clock.switch(turn(Side::White));
+        assert_eq!(clock.remaining(Side::Black), Duration::from_secs(57));
+    }
+
+    #[test]
+    fn stages() {
+        let (mut clock, time) = clock("2/60:30+1");
+        clock.start(Side::White);
+        for side in [Side::Black, Side::White, Side::Black] {
+            time.advance(Duration::from_secs(10));
+            clock.switch(turn(side));
+        }
+        assert_eq!(clock.remaining(Side::White), Duration::from_secs(70));
+        assert_eq!(clock.remaining(Side::Black), Duration::from_secs(50));
+        time.advance(Duration::from_secs(10));
+        clock.switch(turn(Side::White));
+        assert_eq!(clock.remaining(Side::Black), Duration::from_secs(70));
+        time.advance(Duration::from_secs(10));
+        clock.switch(turn(Side::Black));
+        assert_eq!(clock.remaining(Side::White), Duration::from_secs(61));
+    }
+
+    #[test]
+    fn flag_fall() {
+        let (clock, time) = clock("10");
+        let mut board = Board { clock: Some(clock), ..Default::default() };
+        board.move_piece(Move::new("e2".parse().unwrap(), "e4".parse().unwrap())).unwrap();
+        assert_eq!(board.clock.as_ref().unwrap().running(), Some(Side::Black));
+        time.advance(Duration::from_secs(11));
+        assert!(matches!(board.move_piece(Move::new("e7".parse().unwrap(), "e5".parse().unwrap())), Err(MoveError::Timeout)));
+        assert_eq!(board.state.flagged(), Some(Side::Black));
+    }
+}
@@ -0,0 +1,282 @@
+use core::fmt::{Display, Formatter, Result as FmtResult};
+
+use std::fs::OpenOptions;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use hashbrown::{HashMap, HashSet};
+
+use crate::{Board, Game, MoveError, Pgn, Side};
+
+#[derive(Debug)]
+pub enum DatabaseError {
+    Io(std::io::Error),
+}
+
+impl Display for DatabaseError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Io(err) => write!(f, "Could not read or write the database: {err}"),
+        }
+    }
+}
+
+impl core::error::Error for DatabaseError {}
+
+/// What came of importing games, or of reading them when opening a database.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Import {
+    pub added: usize,
+    /// Games already in the database, with the same players, date and moves.
+    pub duplicates: usize,
+    /// Games that could not be read or replayed, which are left out.
+    pub failed: usize,
+}
+
+impl Import {
+    fn count(&mut self, added: Option<bool>) {
+        match added {
+            Some(true) => self.added += 1,
+            Some(false) => self.duplicates += 1,
+            None => self.failed += 1,
+        }
+    }
+}
+
+/// What to look for in a [`Database`]. Every criterion given has to match, and one left out matches any game.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Query {
+    /// A player of either side, or of `side` if given, ignoring case.
+    pub player: Option<String>,
+    pub side: Option<Side>,
+    /// "1-0", "0-1", "1/2-1/2" or "*".
+    pub result: Option<String>,
+    /// The start of the ECO code, e.g. "B" or "B90".
+    pub eco: Option<String>,
+    /// Dates in PGN form ("2024.03.17"), both inclusive. Games without a known date do not match a date range.
+    pub from: Option<String>,
+    pub to: Option<String>,
+    /// A position the game went through, by [`Board::position_hash`].
+    pub position: Option<u64>,
+    /// A material balance the game went through, by [`material`].
+    pub material: Option<String>,
+}
+
+/// A collection of games kept in a PGN file, with the positions and material balances of each game indexed so
+/// they can be searched. Only the games are saved: the index is rebuilt when the file is opened, which replays
+/// every game in it.
+#[derive(Default)]
+pub struct Database {
+    path: Option<PathBuf>,
+    games: Vec<Pgn>,
+    /// A key for each game from its players, date and moves, to find the same game imported twice.
+    keys: HashSet<u64>,
+    /// The ids of the games that went through each position and each material balance, in order.
+    positions: HashMap<u64, Vec<usize>>,
+    materials: HashMap<String, Vec<usize>>,
+}
+
+impl Database {
+
+    /// A database kept in memory only.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Opens the database kept in the PGN file at `path`, which is created when games are first imported, with what
+    /// came of reading its games. Those that cannot be read or replayed are left out.
+    pub fn open(path: &Path) -> Result<(Self, Import), DatabaseError> {
+        let mut database = Self { path: Some(path.to_path_buf()), ..Self::default() };
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(err) if err.kind() == std::io::ErrorKind::NotFound => String::new(),
+            Err(err) => return Err(DatabaseError::Io(err)),
+        };
+        let mut import = Import::default();
+        for text in Pgn::split(&text) {
+            let game = text.parse::<Pgn>().and_then(|pgn| pgn.game().map(|game| (pgn, game)));
+            import.count(game.ok().and_then(|(pgn, game)| database.add(pgn, &game).ok()));
+        }
+        Ok((database, import))
+    }
+
+    /// Imports the games of a PGN file, leaving out those already in the database.
+    pub fn import_file(&mut self, path: &Path) -> Result<Import, DatabaseError> {
+        self.import(&std::fs::read_to_string(path).map_err(DatabaseError::Io)?)
+    }
+
+    /// Imports games in PGN, leaving out those already in the database, and adds them to its file if it has one.
+    pub fn import(&mut self, text: &str) -> Result<Import, DatabaseError> {
+        let mut import = Import::default();
+        let mut added = String::new();
+        for text in Pgn::split(text) {
+            let Ok((pgn, game)) = text.parse::<Pgn>().and_then(|pgn| pgn.game().map(|game| (pgn, game))) else {
+                import.count(None);
+                continue;
+            };
+            // Stored as written by chess-lib, with the tags it was imported with, so that the file reads back the same.
+            let written = game.pgn().ok().map(|mut written| {
+                pgn.tags.iter().for_each(|(name, value)| written.set(name, value));
+                written
+            });
+            let result = written.and_then(|written| self.add(written, &game).ok());
+            if result == Some(true) {
+                added += &alloc::format!("{}\n", self.games[self.games.len() - 1]);
+            }
+            import.count(result);
+        }
+        if let Some(path) = self.path.as_ref().filter(|_| !added.is_empty()) {
+            let mut file = OpenOptions::new().create(true).append(true).open(path).map_err(DatabaseError::Io)?;
+            file.write_all(added.as_bytes()).map_err(DatabaseError::Io)?;
+        }
+        Ok(import)
+    }
+
+    /// Adds a game and indexes it, unless the same game is already in the database.
+    fn add(&mut self, pgn: Pgn, game: &Game) -> Result<bool, MoveError> {
+        // FNV-1a over the players, date and moves.
+        let mut key = 0xcbf2_9ce4_8422_2325_u64;
+        let fields = ["White", "Black", "Date"].map(|name| pgn.get(name).unwrap_or_default());
+        for byte in fields.iter().copied().chain(pgn.moves.iter().map(String::as_str)).flat_map(|field| field.bytes().chain([0])) {
+            key = (key ^ byte as u64).wrapping_mul(0x0100_0000_01b3);
+        }
+        if self.keys.contains(&key) {
+            return Ok(false);
+        }
+
+        let replay = game.replay()?;
+        let id = self.games.len();
+        for position in replay.positions().iter().map(Board::position_hash).collect::<HashSet<_>>() {
+            self.positions.entry(position).or_default().push(id);
+        }
+        for material in replay.positions().iter().map(self::material).collect::<HashSet<_>>() {
+            self.materials.entry(material).or_default().push(id);
+        }
+        self.keys.insert(key);
+        self.games.push(pgn);
+        Ok(true)
+    }
+
+    pub fn len(&self) -> usize {
+        self.games.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.games.is_empty()
+    }
+
+    /// The game with an id, which is its number in the database from 0.
+    pub fn get(&self, id: usize) -> Option<&Pgn> {
+        self.games.get(id)
+    }
+
+    /// The ids of the games that match the query, in the order they were added. A position or material balance
+    /// narrows the games down through the index first.
+    pub fn search(&self, query: &Query) -> Vec<usize> {
+        let indexed = [
+            query.position.map(|position| self.positions.get(&position)),
+            query.material.as_ref().map(|material| self.materials.get(material)),
+        ];
+        let ids = match indexed.into_iter().flatten().min_by_key(|ids| ids.map_or(0, Vec::len)) {
+            Some(ids) => ids.cloned().unwrap_or_default(),
+            None => (0..self.games.len()).collect(),
+        };
+        ids.into_iter().filter(|id| self.matches(*id, query)).collect()
+    }
+
+    fn matches(&self, id: usize, query: &Query) -> bool {
+        let tag = |name: &str| self.games[id].get(name).unwrap_or_default();
+        let indexed = |ids: Option<&Vec<usize>>| ids.is_some_and(|ids| ids.binary_search(&id).is_ok());
+        let player = query.player.as_ref().is_none_or(|player| {
+            let sides = match query.side {
+                Some(Side::White) => &["White"][..],
+                Some(Side::Black) => &["Black"][..],
+                None => &["White", "Black"][..],
+            };
+            sides.iter().any(|side| tag(side).eq_ignore_ascii_case(player))
+        });
+        let date = tag("Date");
+        let known = !date.is_empty() && !date.starts_with('?');
+        player
+            && query.result.as_ref().is_none_or(|result| tag("Result") == result)
+            && query.eco.as_ref().is_none_or(|eco| tag("ECO").starts_with(eco.as_str()))
+            && query.from.as_ref().is_none_or(|from| known && date >= from.as_str())
+            && query.to.as_ref().is_none_or(|to| known && date <= to.as_str())
+            && query.position.is_none_or(|position| indexed(self.positions.get(&position)))
+            && query.material.as_ref().is_none_or(|material| indexed(self.materials.get(material)))
+    }
+
+}
+
+/// The material on the board and in hand as white's pieces, "v" and black's, each the king first and then the
+/// most valuable, e.g. "KRPPvKR" for a rook and two pawns against a rook.
+pub fn material(board: &Board) -> String {
+    let mut material = String::new();
+    for side in Side::sides() {
+        let mut pieces = board.pieces.iter().filter(|(.., piece)| piece.side == side).map(|(.., piece)| piece.kind)
+            .chain(board.state.hands[side as usize].iter().copied())
+            .collect::<Vec<_>>();
+        pieces.sort_by_key(|kind| (*kind != crate::Piece::King, core::cmp::Reverse(kind.value()), crate::BoardPiece { kind: *kind, side: Side::White }.letter()));
+        if side == Side::Black {
+            material.push('v');
+        }
+        material.extend(pieces.iter().map(|kind| crate::BoardPiece { kind: *kind, side: Side::White }.letter()));
+    }
+    material
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use crate::{Board, Side};
+
+    use super::{Database, Import, Query};
+
+    const GAMES: &str = "[White \"Ann\"]\n[Black \"Bob\"]\n[Date \"2024.03.17\"]\n[ECO \"C60\"]\n[Result \"1-0\"]\n\n1. e4 e5 2. Nf3 Nc6 3. Bb5 1-0\n\n\
+        [White \"Bob\"]\n[Black \"Ann\"]\n[Date \"2024.05.01\"]\n[ECO \"B20\"]\n[Result \"1/2-1/2\"]\n\n1. e4 c5 2. Nf3 d6 3. d4 cxd4 4. Nxd4 1/2-1/2\n\n\
+        [White \"Cas\"]\n[Black \"Ann\"]\n[Date \"????.??.??\"]\n[Result \"0-1\"]\n\n1. Nf3 Nc6 2. e4 e5 0-1\n\n\
+        [White \"Ann\"]\n[Black \"Cas\"]\n[Result \"*\"]\n\n1. e4 e5 2. Ke3 *\n";
+
+    #[test]
+    fn database() {
+        let path = std::env::temp_dir().join(alloc::format!("chess-lib-database-{}.pgn", std::process::id()));
+        let (mut database, import) = Database::open(&path).unwrap();
+        assert_eq!(import, Import::default());
+        assert!(database.is_empty());
+        assert_eq!(database.import(GAMES).unwrap(), Import { added: 3, duplicates: 0, failed: 1 });
+        assert_eq!(database.import(GAMES).unwrap(), Import { added: 0, duplicates: 3, failed: 1 });
+
+        // A game that cannot be replayed is left out when the file is read back, without failing the others.
+        let mut file = std::fs::OpenOptions::new().append(true).open(&path).unwrap();
+        std::io::Write::write_all(&mut file, b"[White \"Dan\"]\n\n1. e4 e5 2. Ke3 *\n").unwrap();
+        let reopened = Database::open(&path);
+        std::fs::remove_file(&path).unwrap();
+        let (database, import) = reopened.unwrap();
+        assert_eq!(import, Import { added: 3, duplicates: 0, failed: 1 });
+        assert_eq!(database.len(), 3);
+        assert_eq!(database.get(1).and_then(|pgn| pgn.get("ECO")), Some("B20"));
+
+        let search = |query: Query| database.search(&query);
+        assert_eq!(search(Query::default()), [0, 1, 2]);
+        assert_eq!(search(Query { player: Some("ann".to_string()), side: Some(Side::Black), ..Default::default() }), [1, 2]);
+        assert_eq!(search(Query { result: Some("1-0".to_string()), ..Default::default() }), [0]);
+        assert_eq!(search(Query { eco: Some("B".to_string()), ..Default::default() }), [1]);
+        assert_eq!(search(Query { from: Some("2024.04".to_string()), ..Default::default() }), [1]);
+        assert_eq!(search(Query { to: Some("2024.12.31".to_string()), ..Default::default() }), [0, 1]);
+
+        // The Ruy Lopez position after 2... Nc6 is reached in two move orders.
+        let mut board = Board::default();
+        for mov in ["e2 e4", "e7 e5", "g1 f3", "b8 c6"] {
+            board.move_piece(mov.parse().unwrap()).unwrap();
+        }
+        assert_eq!(search(Query { position: Some(board.position_hash()), ..Default::default() }), [0, 2]);
+        assert_eq!(super::material(&board), "KQRRBBNNPPPPPPPPvKQRRBBNNPPPPPPPP");
+        assert_eq!(search(Query { material: Some("KQRRBBNNPPPPPPPvKQRRBBNNPPPPPPP".to_string()), ..Default::default() }), [1]);
+        assert_eq!(search(Query { position: Some(board.position_hash()), player: Some("cas".to_string()), ..Default::default() }), [2]);
+        assert_eq!(search(Query { position: Some(board.position_hash()), material: Some("KvK".to_string()), ..Default::default() }), Vec::<usize>::new());
+    }
+}
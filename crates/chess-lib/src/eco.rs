@@ -0,0 +1,143 @@
+use core::fmt::{Display, Formatter, Result as FmtResult};
+
+use core::error::Error;
+
+use alloc::boxed::Box;
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use hashbrown::HashMap;
+use once_cell::race::OnceBox;
+
+use crate::{Board, Game, ParseSanError};
+
+/// The openings chess-lib knows by default: the main lines of common openings, in the layout of the lichess
+/// opening tables. A full table can be read with [`Eco::parse`].
+const TABLE: &str = include_str!("eco.tsv");
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseEcoError {
+    /// A line without a code, name and moves, with its line number.
+    Line(usize),
+    /// A move that could not be played, with its line number.
+    Move(usize, String, ParseSanError),
+}
+
+impl Display for ParseEcoError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Line(line) => write!(f, "Expected a code, name and moves on line {line}"),
+            Self::Move(line, san, err) => write!(f, "Invalid move {san} on line {line} with error {err}"),
+        }
+    }
+}
+
+impl Error for ParseEcoError {}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Opening {
+    pub eco: String,
+    pub name: String,
+    /// The moves of its main line, in SAN.
+    pub moves: Vec<String>,
+}
+
+/// A table of openings by the position at the end of their lines, so that an opening is found whatever order its
+/// moves were played in.
+#[derive(Debug, Clone, Default)]
+pub struct Eco {
+    openings: Vec<Opening>,
+    positions: HashMap<u64, usize>,
+}
+
+impl Eco {
+
+    /// The built-in table, read the first time it is needed, with or without `std`.
+    pub fn builtin() -> &'static Self {
+        static BUILTIN: OnceBox<Eco> = OnceBox::new();
+        BUILTIN.get_or_init(|| Box::new(Self::parse(TABLE).expect("Could not read the built-in ECO table!")))
+    }
+
+    /// Reads a table with a code, a name and the moves in SAN (move numbers optional) on each line, separated by
+    /// tabs. A header line starting with "eco" is skipped. Where two openings end in the same position the later
+    /// one is kept.
+    pub fn parse(table: &str) -> Result<Self, ParseEcoError> {
+        let mut eco = Self::default();
+        for (i, line) in table.lines().enumerate().filter(|(.., line)| !line.trim().is_empty() && !line.starts_with("eco\t")) {
+            let (code, name, pgn) = match *line.split('\t').collect::<Vec<_>>().as_slice() {
+                [code, name, pgn, ..] => (code.trim(), name.trim(), pgn),
+                _ => return Err(ParseEcoError::Line(i + 1)),
+            };
+            let mut board = Board::default();
+            let mut moves = Vec::new();
+            for san in pgn.split_whitespace().map(|word| word.rsplit_once('.').map_or(word, |(.., san)| san)).filter(|san| !san.is_empty()) {
+                let played = board.parse_san(san).and_then(|mov| board.move_piece(mov).map_err(|_| ParseSanError::Illegal));
+                played.map_err(|err| ParseEcoError::Move(i + 1, san.to_string(), err))?;
+                moves.push(san.to_string());
+            }
+            eco.positions.insert(board.position_hash(), eco.openings.len());
+            eco.openings.push(Opening { eco: code.to_string(), name: name.to_string(), moves });
+        }
+        Ok(eco)
+    }
+
+    pub fn openings(&self) -> &[Opening] {
+        &self.openings
+    }
+
+    /// The opening whose line ends in exactly this position.
+    pub fn classify(&self, board: &Board) -> Option<&Opening> {
+        self.positions.get(&board.position_hash()).map(|index| &self.openings[*index])
+    }
+
+    /// The opening of the last position of a standard game that is in the table, as the game usually leaves the
+    /// known lines at some point.
+    pub fn classify_game(&self, game: &Game) -> Option<&Opening> {
+        if game.start().rules.name() != "standard" {
+            return None;
+        }
+        let replay = game.replay().ok()?;
+        replay.positions().iter().rev().find_map(|board| self.classify(board))
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Board, Game};
+
+    use super::{Eco, ParseEcoError};
+
+    #[test]
+    fn eco() {
+        let eco = Eco::builtin();
+        assert!(eco.openings().len() > 50);
+        assert_eq!(eco.classify(&Board::default()), None);
+
+        // The Berlin, named after the game has left it.
+        let mut game = Game::new(Board::default());
+        for mov in ["e2 e4", "e7 e5", "g1 f3", "b8 c6", "f1 b5", "g8 f6", "e1 h1", "f6 e4"] {
+            game.board.move_piece(mov.parse().unwrap()).unwrap();
+        }
+        let opening = eco.classify_game(&game).unwrap();
+        assert_eq!((opening.eco.as_str(), opening.name.as_str()), ("C65", "Ruy Lopez: Berlin Defense"));
+        let mut game = Game::new(Board::default());
+        for mov in ["d2 d4", "g8 f6", "c2 c4", "e7 e6", "b1 c3", "f8 b4"] {
+            game.board.move_piece(mov.parse().unwrap()).unwrap();
+        }
+        assert_eq!(eco.classify_game(&game).map(|opening| opening.eco.as_str()), Some("E20"));
+        let pgn = game.pgn().unwrap();
+        assert_eq!((pgn.get("ECO"), pgn.get("Opening")), (Some("E20"), Some("Nimzo-Indian Defense")));
+
+        // Transposed: 1. c4 e6 2. Nc3 Nf6 3. d4 Bb4.
+        let mut game = Game::new(Board::default());
+        for mov in ["c2 c4", "e7 e6", "b1 c3", "g8 f6", "d2 d4", "f8 b4"] {
+            game.board.move_piece(mov.parse().unwrap()).unwrap();
+        }
+        assert_eq!(eco.classify(&game.board).map(|opening| opening.name.as_str()), Some("Nimzo-Indian Defense"));
+
+        let eco = Eco::parse("A00\tTest\t1. e4 e5\nB00\tBroken\t1. e5").map(|eco| eco.openings().len());
+        assert!(matches!(eco, Err(ParseEcoError::Move(2, ..))));
+        assert!(matches!(Eco::parse("A00 Test"), Err(ParseEcoError::Line(1))));
+    }
+}
@@ -0,0 +1,369 @@
+use core::fmt::{Display, Formatter, Result as FmtResult};
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::time::Duration;
+
+use alloc::sync::Arc;
+use alloc::vec::Vec;
+
+use crate::{Board, Move, Outcome, Piece, Pos, TimeSource};
+
+/// A score for mate on the board; mates further away score less.
+const MATE: i32 = 1_000_000;
+
+/// How deep a search goes when nothing else limits it.
+const MAX_DEPTH: u32 = 64;
+
+/// How good a position is for the side to move.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Score {
+    /// Centipawns.
+    Cp(i32),
+    /// Mate in so many moves, negative when the side to move gets mated.
+    Mate(i32),
+}
+
+impl Score {
+
+    fn from_value(value: i32) -> Self {
+        match MATE - value.abs() {
+            plies if plies < MAX_DEPTH as i32 * 2 => Self::Mate(value.signum() * (plies + 1) / 2),
+            _ => Self::Cp(value),
+        }
+    }
+
+    /// The score in centipawns, with mates as far beyond any material as they are close.
+    pub fn value(&self) -> i32 {
+        match *self {
+            Self::Cp(cp) => cp,
+            Self::Mate(moves) => moves.signum() * (MATE - moves.abs() * 2),
+        }
+    }
+
+}
+
+impl Display for Score {
+    /// As in UCI, e.g. "cp 35" or "mate -2".
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Cp(cp) => write!(f, "cp {cp}"),
+            Self::Mate(moves) => write!(f, "mate {moves}"),
+        }
+    }
+}
+
+/// What a search may spend. It stops at whichever limit it reaches first, but always finishes depth 1.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Limits {
+    pub depth: Option<u32>,
+    pub nodes: Option<u64>,
+    /// Needs the engine to have a time source, which it has by default with `std`.
+    pub time: Option<Duration>,
+}
+
+/// The outcome of a search to some depth.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Search {
+    /// `None` when the game is already over.
+    pub best: Option<Move>,
+    pub score: Score,
+    pub depth: u32,
+    pub nodes: u64,
+    /// The line the engine expects, starting with the best move.
+    pub pv: Vec<Move>,
+}
+
+/// The built-in engine: an iterative deepening alpha-beta search over material and piece placement.
+/// It searches moves only, not drops.
+#[derive(Clone)]
+pub struct Engine {
+    /// Whether captures are played out at the end of the search, so that it does not stop halfway through
+    /// an exchange.
+    pub quiescence: bool,
+    /// Centipawns for each step a minor piece or pawn stands closer to the centre.
+    pub center: i32,
+    source: Option<Arc<dyn TimeSource>>,
+}
+
+impl Default for Engine {
+    fn default() -> Self {
+        #[cfg(feature = "std")]
+        let source = Some(Arc::new(crate::MonotonicTime::default()) as Arc<dyn TimeSource>);
+        #[cfg(not(feature = "std"))]
+        let source = None;
+        Self { quiescence: true, center: 5, source }
+    }
+}
+
+impl Engine {
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// An engine timing its searches with `source`.
+    pub fn with_source(self, source: Arc<dyn TimeSource>) -> Self {
+        Self { source: Some(source), ..self }
+    }
+
+    pub fn search(&self, board: &Board, limits: Limits) -> Search {
+        self.search_with(board, limits, |_| ())
+    }
+
+    /// Searches the position, telling `info` about each depth as it is finished.
+    pub fn search_with(&self, board: &Board, limits: Limits, mut info: impl FnMut(&Search)) -> Search {
+        let searches = self.analyze(board, limits, 1, &AtomicBool::new(false), |searches| info(&searches[0]));
+        searches.into_iter().next().unwrap_or(Search { best: None, score: Score::Cp(0), depth: 0, nodes: 0, pv: Vec::new() })
+    }
+
+    /// Searches the best `lines` moves of the position (MultiPV), each with its own score and line, telling `info`
+    /// about them, best first, each time a depth is finished. Without limits it goes on until all lines are mates
+    /// or `stop` is set, e.g. by another thread. Returns nothing when the game is already over.
+    pub fn analyze(&self, board: &Board, limits: Limits, lines: usize, stop: &AtomicBool, mut info: impl FnMut(&[Search])) -> Vec<Search> {
+        let board = Board { clock: None, ..board.clone() };
+        let mut searcher = Searcher {
+            engine: self,
+            limits,
+            start: self.now(),
+            nodes: 0,
+            stop,
+            excluded: Vec::new(),
+            stoppable: false,
+            stopped: false,
+        };
+        let mut searches = Vec::<Search>::new();
+        if board.state.outcome().is_some() {
+            return searches;
+        }
+        let lines = lines.clamp(1, board.moves().filter(|mov| board.clone().move_piece(*mov).is_ok()).count().max(1));
+        for depth in 1..=limits.depth.unwrap_or(MAX_DEPTH).clamp(1, MAX_DEPTH) {
+            searcher.stoppable = depth > 1;
+            searcher.excluded.clear();
+            let mut found = Vec::new();
+            // Each line is the best move left once the moves of the lines before it are taken out.
+            while found.len() < lines && !searcher.stopped {
+                let mut pv = searches.get(found.len()).map(|search| search.pv.clone()).unwrap_or_default();
+                let value = searcher.negamax(&board, depth, 0, -MATE - 1, MATE + 1, &mut pv);
+                let Some(&best) = pv.first().filter(|_| !searcher.stopped) else {
+                    break;
+                };
+                searcher.excluded.push(best);
+                found.push(Search { best: Some(best), score: Score::from_value(value), depth, nodes: 0, pv });
+            }
+            if searcher.stopped || found.is_empty() {
+                break;
+            }
+            found.iter_mut().for_each(|search| search.nodes = searcher.nodes);
+            searches = found;
+            info(&searches);
+            if searches.iter().all(|search| matches!(search.score, Score::Mate(..))) || searcher.spent() {
+                break;
+            }
+        }
+        searches
+    }
+
+    /// The position for the side to move, in centipawns.
+    pub fn evaluate(&self, board: &Board) -> i32 {
+        let side = board.state.turn.side;
+        let size = board.size();
+        let placement = |pos: &Pos, kind: Piece| match kind {
+            Piece::Pawn | Piece::Knight | Piece::Bishop => {
+                let distance = (2 * pos.x - size.width + 1).abs().max((2 * pos.y - size.height + 1).abs()) / 2;
+                self.center * (size.width.max(size.height) / 2 - 1 - distance) as i32
+            },
+            _ => 0,
+        };
+        let pieces = board.pieces.iter().map(|(pos, piece)| {
+            let value = piece.kind.value() + placement(pos, piece.kind);
+            if piece.side == side { value } else { -value }
+        }).sum::<i32>();
+        let hands = board.state.hands[side as usize].iter().map(Piece::value).sum::<i32>()
+            - board.state.hands[side.other() as usize].iter().map(Piece::value).sum::<i32>();
+        pieces + hands
+    }
+
+    fn now(&self) -> Duration {
+        self.source.as_ref().map_or(Duration::ZERO, |source| source.now())
+    }
+
+}
+
+struct Searcher<'a> {
+    engine: &'a Engine,
+    limits: Limits,
+    start: Duration,
+    nodes: u64,
+    stop: &'a AtomicBool,
+    /// Moves left out at the root, as they are already in other lines.
+    excluded: Vec<Move>,
+    /// Whether the search may stop once it is spent, which it may not before it has finished depth 1.
+    stoppable: bool,
+    stopped: bool,
+}
+
+impl Searcher<'_> {
+
+    /// Whether the search has used up its nodes or time, or has been told to stop.
+    fn spent(&self) -> bool {
+        self.stop.load(Ordering::Relaxed)
+            || self.limits.nodes.is_some_and(|nodes| self.nodes >= nodes)
+            || self.limits.time.is_some_and(|time| self.engine.now().saturating_sub(self.start) >= time)
+    }
+
+    /// The value of the position for the side to move, with `pv` holding the best line found so far on entry
+    /// and the best line found on exit.
+    fn negamax(&mut self, board: &Board, depth: u32, ply: u32, mut alpha: i32, beta: i32, pv: &mut Vec<Move>) -> i32 {
+        self.nodes += 1;
+        if self.stoppable && (self.stopped || self.spent()) {
+            self.stopped = true;
+            return 0;
+        }
+        if let Some(value) = Self::outcome(board, ply) {
+            pv.clear();
+            return value;
+        }
+        if depth == 0 {
+            pv.clear();
+            return self.quiesce(board, alpha, beta);
+        }
+
+        let mut moves = Self::ordered(board);
+        if ply == 0 {
+            moves.retain(|mov| !self.excluded.contains(mov));
+        }
+        if let Some(index) = pv.first().and_then(|first| moves.iter().position(|mov| mov == first)) {
+            let first = moves.remove(index);
+            moves.insert(0, first);
+        }
+        let mut line = pv.get(1..).map(<[Move]>::to_vec).unwrap_or_default();
+        let mut best = -MATE - 1;
+        for (index, mov) in moves.into_iter().enumerate() {
+            let mut child = board.clone();
+            if child.move_piece(mov).is_err() {
+                continue;
+            }
+            if index > 0 {
+                line.clear();
+            }
+            let value = -self.negamax(&child, depth - 1, ply + 1, -beta, -alpha, &mut line);
+            if self.stopped {
+                return 0;
+            }
+            if value > best {
+                best = value;
+                if value > alpha {
+                    alpha = value;
+                    pv.clear();
+                    pv.push(mov);
+                    pv.extend_from_slice(&line);
+                }
+                if value >= beta {
+                    break;
+                }
+            }
+        }
+        best
+    }
+
+    /// Plays out captures until the position is quiet.
+    fn quiesce(&mut self, board: &Board, mut alpha: i32, beta: i32) -> i32 {
+        let standing = self.engine.evaluate(board);
+        if !self.engine.quiescence || standing >= beta {
+            return standing;
+        }
+        alpha = alpha.max(standing);
+        for mov in Self::ordered(board).into_iter().filter(|mov| board.capture(*mov).is_some() && board.see(*mov) >= 0) {
+            self.nodes += 1;
+            let mut child = board.clone();
+            if child.move_piece(mov).is_err() {
+                continue;
+            }
+            let value = match Self::outcome(&child, 1) {
+                Some(value) => -value,
+                None => -self.quiesce(&child, -beta, -alpha),
+            };
+            if value >= beta {
+                return value;
+            }
+            alpha = alpha.max(value);
+        }
+        alpha
+    }
+
+    /// The value of a finished game for the side to move.
+    fn outcome(board: &Board, ply: u32) -> Option<i32> {
+        board.state.outcome().map(|outcome| match outcome {
+            Outcome::Win(side, ..) if side == board.state.turn.side => MATE - ply as i32,
+            Outcome::Win(..) => -(MATE - ply as i32),
+            Outcome::Draw(..) => 0,
+        })
+    }
+
+    /// Legal moves, the most valuable captures by the least valuable pieces first.
+    fn ordered(board: &Board) -> Vec<Move> {
+        let mut moves = board.moves().collect::<Vec<_>>();
+        moves.sort_by_cached_key(|mov| {
+            let taken = board.capture(*mov).map_or(0, |piece| piece.kind.value());
+            let piece = board.pieces.at(&mov.from).map_or(0, |piece| piece.kind.value());
+            (-taken, piece)
+        });
+        moves
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use core::sync::atomic::AtomicBool;
+
+    use crate::{Board, Side};
+
+    use super::{Engine, Limits, Score};
+
+    fn solve(fen: &str, depth: u32) -> super::Search {
+        Engine::new().search(&Board::from_fen(fen).unwrap(), Limits { depth: Some(depth), ..Default::default() })
+    }
+
+    #[test]
+    fn engine() {
+        // Back rank mate, and getting mated.
+        let search = solve("6k1/5ppp/8/8/8/8/8/R5K1 w - - 0 1", 2);
+        assert_eq!((search.best, search.score), (Some("a1 a8".parse().unwrap()), Score::Mate(1)));
+        assert_eq!(solve("3r4/8/8/8/8/p7/P1k4P/K7 w - - 0 1", 2).score, Score::Mate(-1));
+        // Takes the free queen rather than the defended rook.
+        let search = solve("4k3/8/2r5/3p4/8/1q6/8/1R2K1N1 w - - 0 1", 1);
+        assert_eq!(search.best, Some("b1 b3".parse().unwrap()));
+        assert_eq!(search.pv.len(), 1);
+
+        let board = Board::default();
+        assert_eq!(Engine::new().evaluate(&board), 0);
+        let search = Engine::new().search(&board, Limits { nodes: Some(1), ..Default::default() });
+        assert_eq!(search.depth, 1);
+        let mut depths = alloc::vec::Vec::new();
+        let search = Engine::new().search_with(&board, Limits { depth: Some(3), ..Default::default() }, |search| depths.push(search.depth));
+        assert_eq!((depths.as_slice(), search.pv.len()), ([1, 2, 3].as_slice(), 3));
+        assert_eq!(board.state.turn.side, Side::White);
+        assert_eq!((Score::Mate(-2).to_string(), Score::Cp(35).value()), ("mate -2".into(), 35));
+        assert!(Score::Mate(1).value() > Score::Mate(2).value());
+    }
+
+    #[test]
+    fn analyze() {
+        let board = Board::from_fen("4k3/8/2r5/3p4/8/1q6/8/1R2K1N1 w - - 0 1").unwrap();
+        let mut depths = alloc::vec::Vec::new();
+        let lines = Engine::new().analyze(&board, Limits { depth: Some(2), ..Default::default() }, 3, &AtomicBool::new(false), |lines| depths.push((lines[0].depth, lines.len())));
+        assert_eq!(depths, [(1, 3), (2, 3)]);
+        assert_eq!(lines[0].best, Some("b1 b3".parse().unwrap()));
+        assert!(lines.windows(2).all(|pair| pair[0].score.value() >= pair[1].score.value() && pair[0].best != pair[1].best));
+        assert!(lines.iter().all(|line| line.pv.first() == line.best.as_ref() && line.depth == 2));
+
+        // No more lines than there are moves, and none once the game is over.
+        let board = Board::from_fen("7k/8/8/8/8/8/6PP/6QK w - - 0 1").unwrap();
+        let stop = AtomicBool::new(false);
+        assert_eq!(Engine::new().analyze(&board, Limits { depth: Some(1), ..Default::default() }, 100, &stop, |_| ()).len(), board.moves().count());
+        let board = Board::from_fen("R5k1/5ppp/8/8/8/8/8/6K1 b - - 0 1").unwrap();
+        assert!(Engine::new().analyze(&board, Limits::default(), 2, &stop, |_| ()).is_empty());
+        stop.store(true, core::sync::atomic::Ordering::Relaxed);
+        assert_eq!(Engine::new().analyze(&Board::default(), Limits::default(), 2, &stop, |_| ())[0].depth, 1);
+    }
+}
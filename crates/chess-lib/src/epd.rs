@@ -0,0 +1,174 @@
+use core::fmt::{Display, Formatter, Result as FmtResult};
+use core::str::FromStr;
+
+use core::error::Error;
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::{Board, Move, ParseFenError, ParseSanError};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParseEpdError {
+    Fen(ParseFenError),
+    /// An operation without an opcode, or with a string that is not closed.
+    Operation(String),
+}
+
+impl Display for ParseEpdError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Fen(err) => write!(f, "Invalid position with error {err}"),
+            Self::Operation(operation) => write!(f, "Invalid operation {operation}"),
+        }
+    }
+}
+
+impl Error for ParseEpdError {}
+
+/// A position in Extended Position Description: the first four fields of FEN, followed by operations such as
+/// `bm Nf3; id "WAC.001";`, each an opcode with its operands.
+#[derive(Clone)]
+pub struct Epd {
+    pub board: Board,
+    pub operations: Vec<(String, Vec<String>)>,
+}
+
+impl Epd {
+
+    pub fn get(&self, opcode: &str) -> Option<&[String]> {
+        self.operations.iter().find(|(name, ..)| name == opcode).map(|(.., operands)| operands.as_slice())
+    }
+
+    /// Replaces an operation, or adds it at the end.
+    pub fn set(&mut self, opcode: &str, operands: Vec<String>) {
+        match self.operations.iter_mut().find(|(name, ..)| name == opcode) {
+            Some(operation) => operation.1 = operands,
+            None => self.operations.push((opcode.to_string(), operands)),
+        }
+    }
+
+    /// The best moves (`bm`), in SAN.
+    pub fn best_moves(&self) -> Result<Vec<Move>, ParseSanError> {
+        self.moves("bm")
+    }
+
+    /// The moves to avoid (`am`), in SAN.
+    pub fn avoid_moves(&self) -> Result<Vec<Move>, ParseSanError> {
+        self.moves("am")
+    }
+
+    pub fn id(&self) -> Option<&str> {
+        self.string("id")
+    }
+
+    /// The first comment (`c0`).
+    pub fn comment(&self) -> Option<&str> {
+        self.string("c0")
+    }
+
+    /// The depth the position was analysed to (`acd`).
+    pub fn depth(&self) -> Option<u32> {
+        self.string("acd")?.parse().ok()
+    }
+
+    fn string(&self, opcode: &str) -> Option<&str> {
+        self.get(opcode)?.first().map(String::as_str)
+    }
+
+    fn moves(&self, opcode: &str) -> Result<Vec<Move>, ParseSanError> {
+        self.get(opcode).unwrap_or_default().iter().map(|san| self.board.parse_san(san)).collect()
+    }
+
+}
+
+impl Display for Epd {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        let fen = self.board.fen();
+        let fields = fen.split_whitespace().take(4).collect::<Vec<_>>();
+        write!(f, "{}", fields.join(" "))?;
+        for (opcode, operands) in &self.operations {
+            write!(f, " {opcode}")?;
+            for operand in operands {
+                // Strings are quoted, as are any operands that would otherwise be split.
+                let string = opcode == "id" || (opcode.len() == 2 && opcode.starts_with('c') && opcode.ends_with(|c: char| c.is_ascii_digit()));
+                match string || operand.contains([' ', ';']) {
+                    true => write!(f, " \"{operand}\"")?,
+                    false => write!(f, " {operand}")?,
+                }
+            }
+            write!(f, ";")?;
+        }
+        Ok(())
+    }
+}
+
+impl FromStr for Epd {
+    type Err = ParseEpdError;
+
+    /// Reads a position, taking the move counters from the `hmvc` and `fmvn` operations if there are any.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let mut end = 0;
+        for _ in 0..4 {
+            let field = s[end..].trim_start();
+            end = s.len() - field.len() + field.find(char::is_whitespace).unwrap_or(field.len());
+        }
+        let (position, mut rest) = s.split_at(end);
+
+        let mut operations = Vec::new();
+        while !rest.trim().is_empty() {
+            let mut operands = Vec::new();
+            let mut operand = String::new();
+            let mut quoted = false;
+            let mut chars = rest.char_indices();
+            let end = loop {
+                match chars.next() {
+                    Some((.., '"')) => quoted = !quoted,
+                    Some((i, ';')) if !quoted => break i + 1,
+                    Some((.., c)) if c.is_whitespace() && !quoted => operands.extend((!operand.is_empty()).then(|| core::mem::take(&mut operand))),
+                    Some((.., c)) => operand.push(c),
+                    None if quoted => return Err(ParseEpdError::Operation(rest.trim().to_string())),
+                    None => break rest.len(),
+                }
+            };
+            operands.extend((!operand.is_empty()).then_some(operand));
+            if operands.is_empty() {
+                return Err(ParseEpdError::Operation(rest[..end].trim().to_string()));
+            }
+            let opcode = operands.remove(0);
+            operations.push((opcode, operands));
+            rest = &rest[end..];
+        }
+
+        let counter = |opcode: &str| operations.iter().find(|(name, ..)| name == opcode).and_then(|(.., operands)| operands.first());
+        let fen = alloc::format!("{position} {} {}", counter("hmvc").map_or("0", String::as_str), counter("fmvn").map_or("1", String::as_str));
+        let board = Board::from_fen(&fen).map_err(ParseEpdError::Fen)?;
+        Ok(Self { board, operations })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use super::{Epd, ParseEpdError};
+
+    #[test]
+    fn epd() {
+        let epd = "2rr3k/pp3pp1/1nnqbN1p/3pN3/2pP4/2P3Q1/PPB4P/R4RK1 w - - bm Qg6; id \"WAC.001\"; c0 \"mate in three\"; acd 12; fmvn 20;".parse::<Epd>().unwrap();
+        assert_eq!(epd.best_moves(), Ok(alloc::vec!["g3 g6".parse().unwrap()]));
+        assert_eq!(epd.avoid_moves(), Ok(alloc::vec::Vec::new()));
+        assert_eq!((epd.id(), epd.comment(), epd.depth()), (Some("WAC.001"), Some("mate in three"), Some(12)));
+        assert_eq!(epd.board.state.turn.no, 19);
+        assert_eq!(epd.to_string().parse::<Epd>().unwrap().operations, epd.operations);
+
+        let mut epd = "4k3/8/8/8/8/8/8/4K2R w K -".parse::<Epd>().unwrap();
+        assert!(epd.operations.is_empty());
+        epd.set("am", alloc::vec!["Rh8+".to_string(), "Kd1".to_string()]);
+        assert_eq!(epd.avoid_moves().map(|moves| moves.len()), Ok(2));
+        assert_eq!(epd.to_string(), "4k3/8/8/8/8/8/8/4K2R w K - am Rh8+ Kd1;");
+        assert!(matches!("4k3/8/8/8/8/8/8/4K2R w K - id \"open".parse::<Epd>(), Err(ParseEpdError::Operation(..))));
+        assert!(matches!("4k3/8/8/8 w K -".parse::<Epd>(), Err(ParseEpdError::Fen(..))));
+    }
+}
@@ -0,0 +1,145 @@
+use alloc::string::String;
+use alloc::vec::Vec;
+
+use hashbrown::HashMap;
+
+use crate::{Board, Game, MoveError, Outcome, Side};
+
+/// How many games ended each way.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Tally {
+    pub white: u32,
+    pub draws: u32,
+    pub black: u32,
+}
+
+impl Tally {
+
+    pub fn games(&self) -> u32 {
+        self.white + self.draws + self.black
+    }
+
+    /// The percentages of white wins, draws and black wins.
+    pub fn percentages(&self) -> [f64; 3] {
+        let games = self.games().max(1) as f64;
+        [self.white, self.draws, self.black].map(|count| count as f64 * 100.0 / games)
+    }
+
+    fn add(&mut self, outcome: Outcome) {
+        match outcome {
+            Outcome::Win(Side::White, ..) => self.white += 1,
+            Outcome::Win(Side::Black, ..) => self.black += 1,
+            Outcome::Draw(..) => self.draws += 1,
+        }
+    }
+
+}
+
+/// A move played in a position, with how the games that played it ended.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Continuation {
+    pub san: String,
+    pub tally: Tally,
+}
+
+/// The moves played from each position of a collection of games, up to some number of moves into each game,
+/// looked up by [`Board::position_hash`], so that transpositions are found too.
+#[derive(Debug, Clone, Default)]
+pub struct Explorer {
+    positions: HashMap<u64, Vec<Continuation>>,
+    /// Plies of each game that are added.
+    pub plies: usize,
+    games: usize,
+}
+
+impl Explorer {
+
+    /// An explorer of the first `plies` half-moves of the games added.
+    pub fn new(plies: usize) -> Self {
+        Self { plies, ..Default::default() }
+    }
+
+    /// Adds the opening of a finished game; unfinished games have no result to count and are left out.
+    pub fn add(&mut self, game: &Game) -> Result<(), MoveError> {
+        let Some(outcome) = game.outcome() else {
+            return Ok(());
+        };
+        let replay = game.replay()?;
+        for (board, previous) in replay.moves().take(self.plies) {
+            let san = match previous.dropped {
+                Some(kind) => board.san_drop(kind, previous.mov.to),
+                None => board.san(previous.mov),
+            };
+            let continuations = self.positions.entry(board.position_hash()).or_default();
+            match continuations.iter_mut().find(|continuation| continuation.san == san) {
+                Some(continuation) => continuation.tally.add(outcome),
+                None => {
+                    let mut tally = Tally::default();
+                    tally.add(outcome);
+                    continuations.push(Continuation { san, tally });
+                },
+            }
+        }
+        self.games += 1;
+        Ok(())
+    }
+
+    /// The games added.
+    pub fn games(&self) -> usize {
+        self.games
+    }
+
+    /// The moves played in the position, the most played first.
+    pub fn moves(&self, board: &Board) -> Vec<Continuation> {
+        let mut moves = self.positions.get(&board.position_hash()).cloned().unwrap_or_default();
+        moves.sort_by(|a, b| b.tally.games().cmp(&a.tally.games()).then_with(|| a.san.cmp(&b.san)));
+        moves
+    }
+
+    /// How the games that reached the position ended, counting only those that went on to play a move in it.
+    pub fn position(&self, board: &Board) -> Tally {
+        self.positions.get(&board.position_hash()).into_iter().flatten().fold(Tally::default(), |total, continuation| Tally {
+            white: total.white + continuation.tally.white,
+            draws: total.draws + continuation.tally.draws,
+            black: total.black + continuation.tally.black,
+        })
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Board, Pgn};
+
+    use super::{Explorer, Tally};
+
+    #[test]
+    fn explorer() {
+        let mut explorer = Explorer::new(5);
+        for text in [
+            "[Result \"1-0\"]\n\n1. e4 e5 2. Nf3 Nc6 3. Bb5 1-0",
+            "[Result \"0-1\"]\n\n1. Nf3 Nc6 2. e4 e5 3. Bc4 0-1",
+            "[Result \"1/2-1/2\"]\n\n1. e4 c5 1/2-1/2",
+            "[Result \"*\"]\n\n1. d4 *",
+        ] {
+            explorer.add(&text.parse::<Pgn>().unwrap().game().unwrap()).unwrap();
+        }
+        assert_eq!(explorer.games(), 3);
+
+        let board = Board::default();
+        let moves = explorer.moves(&board);
+        assert_eq!(moves.iter().map(|continuation| continuation.san.as_str()).collect::<alloc::vec::Vec<_>>(), ["e4", "Nf3"]);
+        assert_eq!(moves[0].tally, Tally { white: 1, draws: 1, black: 0 });
+        assert_eq!(explorer.position(&board).percentages(), [100.0 / 3.0, 100.0 / 3.0, 100.0 / 3.0]);
+
+        // Both orders reach the same position, and the sixth ply is past the opening.
+        let mut board = Board::default();
+        for mov in ["e2 e4", "e7 e5", "g1 f3", "b8 c6"] {
+            board.move_piece(mov.parse().unwrap()).unwrap();
+        }
+        assert_eq!(explorer.moves(&board).iter().map(|continuation| continuation.san.as_str()).collect::<alloc::vec::Vec<_>>(), ["Bb5", "Bc4"]);
+        assert_eq!(explorer.position(&board), Tally { white: 1, draws: 0, black: 1 });
+        board.move_piece("f1 b5".parse().unwrap()).unwrap();
+        assert!(explorer.moves(&board).is_empty());
+    }
+}
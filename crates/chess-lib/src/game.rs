@@ -0,0 +1,226 @@
+use alloc::collections::BTreeMap;
+use alloc::string::String;
+
+use crate::{Board, Eco, MoveError, Outcome, Pgn, PreviousMove, Replay, Side, TimeControl};
+
+/// A game with its players and other details, kept together with the position it started from.
+/// Moves are made on `board`, whose history and clock make up the rest of the game.
+#[derive(Clone)]
+pub struct Game {
+    pub white: String,
+    pub black: String,
+    pub event: String,
+    pub site: String,
+    /// As in PGN, e.g. "2024.03.17".
+    pub date: String,
+    pub round: String,
+    pub board: Board,
+    start: Board,
+}
+
+impl Game {
+
+    /// A game from the position on `board`, with its clock if it has one.
+    pub fn new(board: Board) -> Self {
+        Self {
+            start: Self::position(&board),
+            white: String::new(),
+            black: String::new(),
+            event: String::new(),
+            site: String::new(),
+            date: String::new(),
+            round: String::new(),
+            board,
+        }
+    }
+
+    /// The position the game started from.
+    pub fn start(&self) -> &Board {
+        &self.start
+    }
+
+    /// The moves and drops played so far.
+    pub fn moves(&self) -> &[PreviousMove] {
+        self.board.history.moves()
+    }
+
+    /// The positions of the game so far, to step through without changing it.
+    pub fn replay(&self) -> Result<Replay, MoveError> {
+        Replay::new(&self.start, &self.board.history)
+    }
+
+    pub fn time_control(&self) -> Option<&TimeControl> {
+        self.board.clock.as_ref().map(|clock| clock.control())
+    }
+
+    pub fn outcome(&self) -> Option<Outcome> {
+        self.board.state.outcome()
+    }
+
+    /// The result as in PGN: "1-0", "0-1", "1/2-1/2" or "*" while the game goes on.
+    pub fn result(&self) -> &'static str {
+        Pgn::result(self.outcome())
+    }
+
+    /// Ends the game with `side` resigning.
+    pub fn resign(&mut self, side: Side) -> Result<(), MoveError> {
+        self.board.end(Outcome::Win(side.other(), "resignation"))
+    }
+
+    /// Ends the game in a draw agreed by the players.
+    pub fn draw(&mut self) -> Result<(), MoveError> {
+        self.board.end(Outcome::Draw("agreement"))
+    }
+
+    /// Starts over from a new position, keeping the players and other details.
+    pub fn restart(&mut self, board: Board) {
+        self.start = Self::position(&board);
+        self.board = board;
+    }
+
+    /// Starts over from the start position of the variant, as [`Board::reset`].
+    pub fn reset(&mut self) {
+        self.board.reset();
+        self.start = Self::position(&self.board);
+    }
+
+    /// The position on a board, without its clock.
+    fn position(board: &Board) -> Board {
+        Board { clock: None, ..board.clone() }
+    }
+
+    /// The game in PGN, with its details, time control and opening as tags.
+    pub fn pgn(&self) -> Result<Pgn, MoveError> {
+        let mut pgn = Pgn::from_history(&self.start, self.moves().iter().copied())?;
+        for (name, value) in [("Event", &self.event), ("Site", &self.site), ("Date", &self.date), ("Round", &self.round), ("White", &self.white), ("Black", &self.black)] {
+            if !value.is_empty() {
+                pgn.set(name, value);
+            }
+        }
+        pgn.set("Result", self.result());
+        if let Some(control) = self.time_control() {
+            pgn.set("TimeControl", &alloc::format!("{control}"));
+        }
+        if let Some(opening) = Eco::builtin().classify_game(self) {
+            pgn.set("ECO", &opening.eco);
+            pgn.set("Opening", &opening.name);
+        }
+        Ok(pgn)
+    }
+
+}
+
+/// Games played at the same time, such as the boards of a simul, addressed by id.
+#[derive(Default, Clone)]
+pub struct GameManager {
+    games: BTreeMap<usize, Game>,
+    next: usize,
+    current: Option<usize>,
+}
+
+impl GameManager {
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a game and switches to it, returning its id. Ids start at 1 and are not reused.
+    pub fn add(&mut self, game: Game) -> usize {
+        self.next += 1;
+        self.games.insert(self.next, game);
+        self.current = Some(self.next);
+        self.next
+    }
+
+    /// Removes a game, leaving no game current if it was.
+    pub fn remove(&mut self, id: usize) -> Option<Game> {
+        if self.current == Some(id) {
+            self.current = None;
+        }
+        self.games.remove(&id)
+    }
+
+    pub fn get(&self, id: usize) -> Option<&Game> {
+        self.games.get(&id)
+    }
+
+    pub fn get_mut(&mut self, id: usize) -> Option<&mut Game> {
+        self.games.get_mut(&id)
+    }
+
+    /// Makes a game current, if there is one with the id.
+    pub fn switch(&mut self, id: usize) -> Option<&mut Game> {
+        let game = self.games.get_mut(&id)?;
+        self.current = Some(id);
+        Some(game)
+    }
+
+    pub fn current_id(&self) -> Option<usize> {
+        self.current
+    }
+
+    pub fn current(&self) -> Option<&Game> {
+        self.games.get(&self.current?)
+    }
+
+    pub fn current_mut(&mut self) -> Option<&mut Game> {
+        self.games.get_mut(&self.current?)
+    }
+
+    /// The games by id, in the order they were added.
+    pub fn iter(&self) -> impl Iterator<Item = (usize, &Game)> {
+        self.games.iter().map(|(id, game)| (*id, game))
+    }
+
+    pub fn len(&self) -> usize {
+        self.games.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.games.is_empty()
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use crate::{Board, Crazyhouse, MoveError, Piece, Side};
+
+    use super::{Game, GameManager};
+
+    #[test]
+    fn games() {
+        let mut games = GameManager::new();
+        let first = games.add(Game::new(Board::default()));
+        let second = games.add(Game::new(Board::new(&Crazyhouse)));
+        assert_eq!((first, second, games.current_id()), (1, 2, Some(2)));
+
+        let game = games.current_mut().unwrap();
+        game.white = "Ann".to_string();
+        for mov in ["e2 e4", "d7 d5", "e4 d5", "d8 d5", "b1 c3"] {
+            game.board.move_piece(mov.parse().unwrap()).unwrap();
+        }
+        game.board.drop_piece(Piece::Pawn, "e4".parse().unwrap()).unwrap();
+        game.resign(Side::White).unwrap();
+        assert_eq!(game.draw(), Err(MoveError::GameOver));
+        let pgn = game.pgn().unwrap();
+        assert_eq!(pgn.get("White"), Some("Ann"));
+        assert_eq!(pgn.get("Variant"), Some("crazyhouse"));
+        assert!(pgn.to_string().ends_with("1. e4 d5 2. exd5 Qxd5 3. Nc3 P@e4 0-1\n"));
+
+        games.switch(first).unwrap().board.move_piece("e2 e4".parse().unwrap()).unwrap();
+        assert_eq!(games.iter().map(|(.., game)| game.moves().len()).collect::<alloc::vec::Vec<_>>(), [1, 6]);
+        assert!(games.switch(3).is_none());
+        assert_eq!(games.current_id(), Some(first));
+
+        let game = games.current_mut().unwrap();
+        game.restart(Board::from_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1").unwrap());
+        assert_eq!(game.pgn().unwrap().get("FEN"), Some("4k3/8/8/8/8/8/8/4K2R w K - 0 1"));
+        game.reset();
+        assert_eq!(game.start().fen(), Board::START_FEN);
+        assert_eq!(games.remove(first).map(|game| game.moves().len()), Some(0));
+        assert!(games.current().is_none() && games.len() == 1);
+    }
+}
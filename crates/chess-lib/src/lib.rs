@@ -1,10 +1,41 @@
-// #![no_std]
-// extern crate alloc;
+//! Chess rules, variants and clocks.
+//!
+//! The `std` feature is on by default. Without it the crate is `no_std` and only needs `alloc`, e.g.
+//! `cargo build -p chess-lib --no-default-features --target thumbv7em-none-eabihf`.
+
+#![cfg_attr(not(any(feature = "std", test)), no_std)]
+extern crate alloc;
 
 mod board;
+mod clock;
+#[cfg(feature = "std")]
+mod database;
+mod eco;
+mod engine;
+mod epd;
+mod explorer;
+mod game;
+mod pgn;
 mod piece;
+mod replay;
+mod review;
+mod rules;
+#[cfg(feature = "serde")]
+mod serialize;
 mod util;
 
 pub use util::*;
 pub use board::*;
-pub use piece::*;
\ No newline at end of file
+pub use clock::*;
+#[cfg(feature = "std")]
+pub use database::{material, Database, DatabaseError, Import, Query};
+pub use eco::{Eco, Opening, ParseEcoError};
+pub use engine::{Engine, Limits, Score, Search};
+pub use epd::{Epd, ParseEpdError};
+pub use explorer::{Continuation, Explorer, Tally};
+pub use game::{Game, GameManager};
+pub use pgn::{Annotation, ParsePgnError, Pgn};
+pub use piece::*;
+pub use replay::Replay;
+pub use review::{Judgement, MoveReview, Review};
+pub use rules::*;
\ No newline at end of file
@@ -0,0 +1,397 @@
+use core::fmt::{Display, Formatter, Result as FmtResult};
+use core::str::FromStr;
+
+use core::error::Error;
+
+use alloc::string::{String, ToString};
+use alloc::vec::Vec;
+
+use crate::{variant, Board, Game, Move, MoveError, Outcome, ParseFenError, ParseSanError, Piece, PreviousMove, Side, Standard};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ParsePgnError {
+    /// A tag pair that is not of the form `[Name "value"]`.
+    Tag(String),
+    Variant(String),
+    Fen(ParseFenError),
+    /// A move that could not be played, with the number of moves before it.
+    Move(usize, String, ParseSanError),
+}
+
+impl Display for ParsePgnError {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        match self {
+            Self::Tag(tag) => write!(f, "Invalid tag pair {tag}"),
+            Self::Variant(name) => write!(f, "Unknown variant {name}"),
+            Self::Fen(err) => write!(f, "Invalid FEN tag with error {err}"),
+            Self::Move(i, san, err) => write!(f, "Invalid move {san} after {i} moves with error {err}"),
+        }
+    }
+}
+
+impl Error for ParsePgnError {}
+
+/// What is written after a move: numeric annotation glyphs such as `$2` for "?", a comment, and another line
+/// that could have been played instead, in SAN.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Annotation {
+    pub nags: Vec<u8>,
+    pub comment: Option<String>,
+    pub variation: Vec<String>,
+}
+
+/// A game in Portable Game Notation, with its moves in SAN.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Pgn {
+    /// Tags in order, starting with the seven tag roster.
+    pub tags: Vec<(String, String)>,
+    pub moves: Vec<String>,
+    /// Annotations by the index of the move they follow.
+    pub annotations: Vec<(usize, Annotation)>,
+    /// The number of the first move and whether black plays it.
+    pub first: (usize, Side),
+}
+
+impl Pgn {
+
+    /// Records `moves` played from `start`, with the result and set up tags filled in.
+    pub fn new(start: &Board, moves: impl IntoIterator<Item = Move>) -> Result<Self, MoveError> {
+        Self::from_history(start, moves.into_iter().map(|mov| PreviousMove { mov, taken: None, dropped: None }))
+    }
+
+    /// Records moves and drops as kept in a [`crate::BoardHistory`].
+    pub fn from_history(start: &Board, moves: impl IntoIterator<Item = PreviousMove>) -> Result<Self, MoveError> {
+        let mut board = start.clone();
+        board.clock = None;
+        let mut sans = Vec::new();
+        for previous in moves {
+            match previous.dropped {
+                Some(kind) => {
+                    sans.push(board.san_drop(kind, previous.mov.to));
+                    board.drop_piece(kind, previous.mov.to)?;
+                },
+                None => {
+                    sans.push(board.san(previous.mov));
+                    board.move_piece(previous.mov)?;
+                },
+            }
+        }
+
+        let mut pgn = Self {
+            tags: ["Event", "Site", "Date", "Round", "White", "Black"].into_iter()
+                .map(|name| (name.to_string(), if name == "Date" { "????.??.??" } else { "?" }.to_string()))
+                .collect(),
+            moves: sans,
+            annotations: Vec::new(),
+            first: (start.state.turn.no + 1, start.state.turn.side),
+        };
+        pgn.set("Result", Self::result(board.state.outcome()));
+        if start.rules.name() != "standard" {
+            pgn.set("Variant", start.rules.name());
+        }
+        let fen = start.fen();
+        if fen != start.rules.start() {
+            pgn.set("SetUp", "1");
+            pgn.set("FEN", &fen);
+        }
+        Ok(pgn)
+    }
+
+    /// Splits the text of several games, as in a PGN file, into the text of each, to be read with [`str::parse`].
+    pub fn split(text: &str) -> Vec<&str> {
+        let mut starts = alloc::vec![0];
+        let mut offset = 0;
+        let mut movetext = false;
+        for line in text.split_inclusive('\n') {
+            let trimmed = line.trim();
+            if trimmed.starts_with('[') && movetext {
+                starts.push(offset);
+                movetext = false;
+            } else if !trimmed.is_empty() && !trimmed.starts_with('[') {
+                movetext = true;
+            }
+            offset += line.len();
+        }
+        starts.push(text.len());
+        starts.windows(2).map(|range| &text[range[0]..range[1]]).filter(|game| !game.trim().is_empty()).collect()
+    }
+
+    /// The result tag for an outcome, "*" if the game has not ended.
+    pub fn result(outcome: Option<Outcome>) -> &'static str {
+        match outcome {
+            Some(Outcome::Win(Side::White, ..)) => "1-0",
+            Some(Outcome::Win(Side::Black, ..)) => "0-1",
+            Some(Outcome::Draw(..)) => "1/2-1/2",
+            None => "*",
+        }
+    }
+
+    /// Plays the moves from the position in the tags, giving the game with its details. A decisive result or
+    /// draw that the moves do not reach is taken as a resignation or an agreed draw.
+    pub fn game(&self) -> Result<Game, ParsePgnError> {
+        let name = self.get("Variant").unwrap_or("standard").replace([' ', '-'], "");
+        let rules = match name.to_ascii_lowercase().as_str() {
+            "chess960" | "fischerandom" | "fromposition" => &Standard,
+            name => variant(name).ok_or_else(|| ParsePgnError::Variant(name.to_string()))?,
+        };
+        let start = match self.get("FEN") {
+            Some(fen) => Board::from_variant_fen(fen, rules).map_err(ParsePgnError::Fen)?,
+            None => Board::new(rules),
+        };
+
+        let mut game = Game::new(start);
+        for (i, san) in self.moves.iter().enumerate() {
+            let board = &mut game.board;
+            let played = match san.split_once('@') {
+                Some((letter, pos)) => {
+                    let kind = match letter {
+                        "" => Some(Piece::Pawn),
+                        letter => letter.chars().next().and_then(|letter| board.rules.piece(letter)),
+                    };
+                    let pos = pos.trim_end_matches(['+', '#']).parse().ok();
+                    kind.zip(pos).ok_or(ParseSanError::Illegal).and_then(|(kind, pos)| board.drop_piece(kind, pos).map_err(|_| ParseSanError::Illegal))
+                },
+                None => board.parse_san(san).and_then(|mov| board.move_piece(mov).map_err(|_| ParseSanError::Illegal)),
+            };
+            played.map_err(|err| ParsePgnError::Move(i, san.clone(), err))?;
+        }
+
+        let tag = |name: &str| self.get(name).filter(|value| !value.starts_with('?')).unwrap_or_default().to_string();
+        (game.white, game.black, game.event, game.site, game.date, game.round) = (tag("White"), tag("Black"), tag("Event"), tag("Site"), tag("Date"), tag("Round"));
+        let outcome = match self.get("Result") {
+            Some("1-0") => Some(Outcome::Win(Side::White, "resignation")),
+            Some("0-1") => Some(Outcome::Win(Side::Black, "resignation")),
+            Some("1/2-1/2") => Some(Outcome::Draw("agreement")),
+            _ => None,
+        };
+        if let Some(outcome) = outcome.filter(|_| game.outcome().is_none()) {
+            game.board.end(outcome).expect("Could not end game!");
+        }
+        Ok(game)
+    }
+
+    pub fn annotation(&self, index: usize) -> Option<&Annotation> {
+        self.annotations.iter().find(|(i, ..)| *i == index).map(|(.., annotation)| annotation)
+    }
+
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.tags.iter().find(|(tag, ..)| tag == name).map(|(.., value)| value.as_str())
+    }
+
+    /// Sets a tag, adding it to the end if it is new.
+    pub fn set(&mut self, name: &str, value: &str) {
+        match self.tags.iter_mut().find(|(tag, ..)| tag == name) {
+            Some((.., old)) => *old = value.to_string(),
+            None => self.tags.push((name.to_string(), value.to_string())),
+        }
+    }
+
+}
+
+impl Display for Pgn {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        for (name, value) in &self.tags {
+            writeln!(f, "[{name} \"{}\"]", value.replace('\\', "\\\\").replace('"', "\\\""))?;
+        }
+        writeln!(f)?;
+
+        // Movetext lines are kept under 80 characters.
+        let mut line = 0;
+        let mut word = |f: &mut Formatter<'_>, word: &str| {
+            if line != 0 && line + 1 + word.len() > 79 {
+                writeln!(f)?;
+                line = 0;
+            } else if line != 0 {
+                f.write_str(" ")?;
+                line += 1;
+            }
+            line += word.len();
+            f.write_str(word)
+        };
+
+        // Comments and variations are wrapped word by word, with their brackets against the first and last words.
+        let mut bracketed = |f: &mut Formatter<'_>, words: &[String], open: &str, close: &str| {
+            for (i, text) in words.iter().enumerate() {
+                let open = if i == 0 { open } else { "" };
+                let close = if i + 1 == words.len() { close } else { "" };
+                word(f, &alloc::format!("{open}{text}{close}"))?;
+            }
+            Ok(())
+        };
+
+        // Black's move numbers are written again after a comment or variation.
+        let mut resume = true;
+        let (mut no, mut side) = self.first;
+        for (i, san) in self.moves.iter().enumerate() {
+            let mut words = Vec::new();
+            match side {
+                Side::White => words.push(no.to_string() + "."),
+                Side::Black if resume => words.push(no.to_string() + "..."),
+                Side::Black => (),
+            }
+            words.push(san.clone());
+            resume = false;
+            if let Some(annotation) = self.annotation(i) {
+                words.extend(annotation.nags.iter().map(|nag| alloc::format!("${nag}")));
+                bracketed(f, &words, "", "")?;
+                words.clear();
+                if let Some(comment) = &annotation.comment {
+                    bracketed(f, &comment.split_whitespace().map(str::to_string).collect::<Vec<_>>(), "{", "}")?;
+                    resume = true;
+                }
+                if !annotation.variation.is_empty() {
+                    let (mut no, mut side) = (no, side);
+                    for (j, san) in annotation.variation.iter().enumerate() {
+                        match side {
+                            Side::White => words.push(no.to_string() + "."),
+                            Side::Black if j == 0 => words.push(no.to_string() + "..."),
+                            Side::Black => (),
+                        }
+                        words.push(san.clone());
+                        if side == Side::Black {
+                            no += 1;
+                        }
+                        side = side.other();
+                    }
+                    bracketed(f, &words, "(", ")")?;
+                    words.clear();
+                    resume = true;
+                }
+            }
+            bracketed(f, &words, "", "")?;
+            if side == Side::Black {
+                no += 1;
+            }
+            side = side.other();
+        }
+        word(f, self.get("Result").unwrap_or("*"))?;
+        writeln!(f)
+    }
+}
+
+impl FromStr for Pgn {
+    type Err = ParsePgnError;
+
+    /// Reads the first game in PGN. Comments, annotations and variations are skipped.
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut pgn = Self { tags: Vec::new(), moves: Vec::new(), annotations: Vec::new(), first: (1, Side::White) };
+        let mut lines = s.lines().map(str::trim).skip_while(|line| line.is_empty()).peekable();
+        while let Some(line) = lines.next_if(|line| line.starts_with('[') || line.is_empty()) {
+            if line.is_empty() {
+                continue;
+            }
+            let tag = line.strip_prefix('[').and_then(|line| line.strip_suffix(']'))
+                .and_then(|line| line.split_once(' '))
+                .and_then(|(name, value)| Some((name, value.trim().strip_prefix('"')?.strip_suffix('"')?)))
+                .ok_or_else(|| ParsePgnError::Tag(line.to_string()))?;
+            pgn.set(tag.0, &tag.1.replace("\\\"", "\"").replace("\\\\", "\\"));
+        }
+
+        let mut depth = 0;
+        let mut comment = false;
+        for line in lines {
+            if line.starts_with('[') && depth == 0 && !comment {
+                break;
+            }
+            let mut rest = line;
+            while !rest.is_empty() {
+                if comment {
+                    match rest.find('}') {
+                        Some(end) => {
+                            comment = false;
+                            rest = &rest[end + 1..];
+                        },
+                        None => break,
+                    }
+                    continue;
+                }
+                rest = rest.trim_start();
+                let end = rest.find(|c: char| c.is_whitespace() || "{}();".contains(c)).unwrap_or(rest.len()).max(1);
+                let (token, after) = rest.split_at(end.min(rest.len()));
+                rest = after;
+                match token {
+                    "{" => comment = true,
+                    ";" => break,
+                    "(" => depth += 1,
+                    ")" => depth -= 1,
+                    _ if depth > 0 => (),
+                    "1-0" | "0-1" | "1/2-1/2" | "*" => {
+                        if pgn.get("Result").is_none() {
+                            pgn.set("Result", token);
+                        }
+                    },
+                    "}" => (),
+                    token if token.starts_with('$') => (),
+                    token => {
+                        // Move numbers may be written against the move, as in "1.e4".
+                        let san = token.rsplit_once('.').map_or(token, |(.., san)| san).trim_end_matches(['!', '?']);
+                        if !san.is_empty() {
+                            pgn.moves.push(san.to_string());
+                        }
+                    },
+                }
+            }
+        }
+
+        if let Some(fen) = pgn.get("FEN") {
+            let fields = fen.split_whitespace().collect::<Vec<_>>();
+            let side = match fields.get(1) {
+                Some(&"b") => Side::Black,
+                _ => Side::White,
+            };
+            pgn.first = (fields.get(5).and_then(|no| no.parse().ok()).unwrap_or(1), side);
+        }
+        Ok(pgn)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use alloc::string::ToString;
+
+    use crate::{Board, Outcome, ParsePgnError, ParseSanError, Pgn, Side};
+
+    #[test]
+    fn write() {
+        let moves = ["e2 e4", "e7 e5", "f1 c4", "b8 c6", "d1 h5", "g8 f6", "h5 f7"].map(|mov| mov.parse().unwrap());
+        let pgn = Pgn::new(&Board::default(), moves).unwrap();
+        assert_eq!(pgn.to_string(), "[Event \"?\"]\n[Site \"?\"]\n[Date \"????.??.??\"]\n[Round \"?\"]\n[White \"?\"]\n[Black \"?\"]\n[Result \"1-0\"]\n\n1. e4 e5 2. Bc4 Nc6 3. Qh5 Nf6 4. Qxf7# 1-0\n");
+
+        let start = Board::from_fen("4k3/8/8/8/8/8/8/4K2R b K - 0 10").unwrap();
+        let pgn = Pgn::new(&start, ["e8 d7".parse().unwrap()]).unwrap();
+        assert_eq!(pgn.get("FEN"), Some("4k3/8/8/8/8/8/8/4K2R b K - 0 10"));
+        assert!(pgn.to_string().ends_with("\n10... Kd7 *\n"));
+    }
+
+    #[test]
+    fn read() {
+        let text = "[Event \"Casual \\\"blitz\\\"\"]\n[White \"Ann\"]\n\n1.e4 {best by test} e5 2. Nf3 (2. f4 exf4) Nc6 $1 3. Bb5!? a6 ; Ruy Lopez\n4. Ba4 Nf6 5. O-O 1-0\n";
+        let pgn = text.parse::<Pgn>().unwrap();
+        assert_eq!(pgn.get("Event"), Some("Casual \"blitz\""));
+        assert_eq!(pgn.moves, ["e4", "e5", "Nf3", "Nc6", "Bb5", "a6", "Ba4", "Nf6", "O-O"]);
+        let game = pgn.game().unwrap();
+        assert_eq!((game.white.as_str(), game.moves().len()), ("Ann", 9));
+        assert_eq!(game.outcome(), Some(Outcome::Win(Side::White, "resignation")));
+        assert_eq!(game.pgn().unwrap().to_string().parse::<Pgn>().unwrap().moves, pgn.moves);
+
+        let both = text.to_string() + "\n" + text;
+        let games = Pgn::split(&both);
+        assert_eq!(games.len(), 2);
+        assert!(games.iter().all(|game| game.parse::<Pgn>().unwrap().moves == pgn.moves));
+
+        let pgn = "[Variant \"Crazyhouse\"]\n[FEN \"k7/8/8/8/8/8/8/K7[N] b - - 0 12\"]\n\n12... Kb8 13. N@c6+ *".parse::<Pgn>().unwrap();
+        assert_eq!(pgn.first, (12, Side::Black));
+        assert_eq!(pgn.game().unwrap().board.fen(), "1k6/8/2N5/8/8/8/8/K7[] b - - 2 13");
+        assert!(pgn.to_string().contains("12... Kb8 13. N@c6+ *"));
+
+        assert_eq!("[Event broken]".parse::<Pgn>(), Err(ParsePgnError::Tag("[Event broken]".to_string())));
+        // Underpromotions are read and written with their piece.
+        let pgn = "[FEN \"8/1P2k3/8/8/8/8/6p1/4K3 w - - 0 1\"]\n\n1. b8=N g1=R+ 2. Kd2 *".parse::<Pgn>().unwrap();
+        let game = pgn.game().unwrap();
+        assert_eq!(game.board.fen(), "1N6/4k3/8/8/8/8/3K4/6r1 b - - 1 2");
+        assert!(game.pgn().unwrap().to_string().ends_with("1. b8=N g1=R+ 2. Kd2 *\n"));
+
+        let pgn = "1. e4 e5 2. Ke3".parse::<Pgn>().unwrap();
+        assert_eq!(pgn.game().err(), Some(ParsePgnError::Move(2, "Ke3".to_string(), ParseSanError::Illegal)));
+    }
+}
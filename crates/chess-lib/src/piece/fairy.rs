@@ -0,0 +1,95 @@
+use core::cmp::Ordering;
+use core::fmt::{Debug, Formatter, Result as FmtResult};
+use core::hash::{Hash, Hasher};
+
+use crate::Pos;
+
+use super::step::{BishopStep, KnightStep, Leaper, PieceStep, QueenStep, RookStep};
+
+/// A piece made up of steps, such as leapers and riders.
+/// Pieces are told apart by their letter, which is upper case and should be unique within a variant.
+pub struct Fairy {
+    pub name: &'static str,
+    pub letter: char,
+    pub steps: &'static [&'static dyn PieceStep],
+    /// The worth of the piece in centipawns, see [`crate::Piece::value`].
+    pub value: i32,
+}
+
+/// Moves as a bishop or a knight.
+pub static ARCHBISHOP: Fairy = Fairy {
+    name: "Archbishop",
+    letter: 'A',
+    steps: &[&BishopStep, &KnightStep],
+    value: 825,
+};
+
+/// Moves as a rook or a knight.
+pub static CHANCELLOR: Fairy = Fairy {
+    name: "Chancellor",
+    letter: 'C',
+    steps: &[&RookStep, &KnightStep],
+    value: 875,
+};
+
+/// Moves as a queen or a knight.
+pub static AMAZON: Fairy = Fairy {
+    name: "Amazon",
+    letter: 'Z',
+    steps: &[&QueenStep, &KnightStep],
+    value: 1200,
+};
+
+const CAMEL_STEPS: [Pos; 8] = [
+    Pos { x: 1, y: 3 },
+    Pos { x: 3, y: 1 },
+    Pos { x: 3, y: -1 },
+    Pos { x: 1, y: -3 },
+    Pos { x: -1, y: -3 },
+    Pos { x: -3, y: -1 },
+    Pos { x: -3, y: 1 },
+    Pos { x: -1, y: 3 },
+];
+
+/// Leaps three squares one way and one the other.
+pub static CAMEL: Fairy = Fairy {
+    name: "Camel",
+    letter: 'L',
+    steps: &[&Leaper(&CAMEL_STEPS)],
+    value: 250,
+};
+
+/// The fairy pieces defined here, which can be found again by their letter.
+pub static FAIRIES: [&Fairy; 4] = [&ARCHBISHOP, &CHANCELLOR, &AMAZON, &CAMEL];
+
+impl Debug for Fairy {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str(self.name)
+    }
+}
+
+impl PartialEq for Fairy {
+    fn eq(&self, other: &Self) -> bool {
+        self.letter == other.letter
+    }
+}
+
+impl Eq for Fairy {}
+
+impl Hash for Fairy {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.letter.hash(state);
+    }
+}
+
+impl PartialOrd for Fairy {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Fairy {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.letter.cmp(&other.letter)
+    }
+}
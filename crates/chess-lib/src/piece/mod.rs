@@ -1,3 +1,4 @@
+mod fairy;
 mod step;
 
 // use alloc::vec::Vec;
@@ -9,9 +10,14 @@ use king::*;
 use step::pawn::*;
 use step::*;
 
-use crate::{Board, Move, Pos, PosInt, Side};
+pub use fairy::*;
+pub use step::{Leaper, PieceStep, Rider};
+pub use step::king::Castling;
+
+use crate::{Board, Move, MoveError, Pos, PosInt, Side};
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub struct BoardPiece {
     pub kind: Piece,
     pub side: Side,
@@ -26,7 +32,7 @@ impl BoardPiece {
     fn with<'a>(step: &'a dyn PieceStep, position: Pos, side: Side, predicate: impl Fn(Pos, PosInt) -> bool + 'a + Copy) -> impl Iterator<Item = Pos> + 'a {
         step.directions().copied().map(move |d| d * side.forward()).flat_map(move |direction| {
             (1..=match step.once() {
-                false => (1..).take_while(move |i| predicate(direction, *i)).count() as PosInt + 1,
+                false => (1..).take_while(move |i| predicate(direction, *i)).count() as PosInt,
                 true => predicate(direction, 1) as PosInt,
             }).map(move |i| position + (direction * i))
         })
@@ -35,12 +41,15 @@ impl BoardPiece {
     fn step_targets<'a>(board: &'a Board, step: &'a &'static dyn PieceStep, position: Pos, side: Side) -> impl Iterator<Item = Pos> + 'a {
         Self::with(*step, position, side, move |direction, num| {
             let mov = position + (direction * num);
-            Self::on(mov) && Self::previous_unoccupied(board, position, direction, num) && step.condition(board, Move { from: position, to: position + (direction * num) }, side)
+            board.size().contains(mov) && Self::previous_unoccupied(board, position, direction, num) && step.condition(board, Move::new(position, position + (direction * num)), side)
         })
     }
 
     fn step_moves<'a>(board: &'a Board, step: &'a &'static dyn PieceStep, position: Pos, side: Side) -> impl Iterator<Item = Pos> + 'a {
-        Self::step_targets(board, step, position, side).filter(move |mov| board.pieces.at(mov).filter(|piece| piece.side == side || piece.kind == Piece::King).is_none() && Self::prevents_check(board, &Move { from: position, to: *mov }, side))
+        Self::step_targets(board, step, position, side).filter(move |to| {
+            let mov = Move::new(position, *to);
+            (step.custom_checks() || board.pieces.at(to).filter(|piece| piece.side == side || (piece.kind == Piece::King && board.rules.royal())).is_none() && board.rules.legal(board, mov, side)) && board.rules.allowed(board, mov, side)
+        })
     }
 
     pub fn targets<'a>(&'a self, board: &'a Board, position: Pos) -> impl Iterator<Item = Pos> + 'a {
@@ -48,24 +57,61 @@ impl BoardPiece {
     }
 
     pub fn can_move(&self, board: &Board, mov: Move) -> Option<&dyn PieceStep> {
-        self.kind.moves().find(move |step| Self::step_moves(board, step, mov.from, self.side).any(|target| target == mov.to)).map(|s| *s)
+        self.kind.moves().find(move |step| Self::step_moves(board, step, mov.from, self.side).any(|target| target == mov.to)).copied()
     }
 
-    pub fn moves<'a>(&'a self, board: &'a Board, position: Pos) -> impl Iterator<Item = Pos> + 'a {
-        self.kind.moves().flat_map(move |step| Self::step_moves(board, step, position, self.side))
+    /// Turns other notations of a move into the form used by the piece's steps, such as castling by
+    /// moving the king two squares instead of onto the rook.
+    pub fn resolve(&self, board: &Board, mov: Move) -> Move {
+        match self.kind {
+            Piece::King => Castling::resolve(board, mov, self.side),
+            _ => mov,
+        }
     }
 
-    fn on(pos: Pos) -> bool {
-        pos.x >= 0 && pos.x < 8 && pos.y >= 0 && pos.y < 8
+    /// Why the piece can not make a move, which should already be resolved.
+    pub fn reason(&self, board: &Board, mov: Move) -> MoveError {
+        let side = self.side;
+        if let Some(err) = Some(self.kind).filter(|kind| *kind == Piece::King).and_then(|_| Castling::reason(board, mov, side)) {
+            return err;
+        }
+        if board.pieces.at(&mov.to).is_some_and(|piece| piece.side == side) {
+            return MoveError::Occupied;
+        }
+
+        let size = board.size();
+        let reaches = |predicate: &dyn Fn(&&'static dyn PieceStep, Pos, PosInt) -> bool| self.kind.moves().any(|step| {
+            Self::with(*step, mov.from, side, |direction, num| size.contains(mov.from + (direction * num)) && predicate(step, direction, num)).any(|to| to == mov.to)
+        });
+        if !reaches(&|_, _, _| true) {
+            return MoveError::Pattern;
+        }
+        if !reaches(&|_, direction, num| Self::previous_unoccupied(board, mov.from, direction, num)) {
+            return MoveError::Blocked;
+        }
+        if !self.kind.moves().any(|step| Self::step_targets(board, step, mov.from, side).any(|to| to == mov.to)) {
+            let forward = Pos { x: 0, y: side.forward() };
+            return match self.kind {
+                Piece::Pawn if mov.from.x == mov.to.x && (1..=(mov.to.y - mov.from.y).abs()).any(|i| step::occupied(board, mov.from + forward * i)) => MoveError::Blocked,
+                Piece::King if board.rules.royal() => MoveError::Check,
+                _ => MoveError::Pattern,
+            };
+        }
+        if !board.rules.allowed(board, mov, side) {
+            return MoveError::Variant;
+        }
+        match self.kind != Piece::King && board.pinned(mov.from) {
+            true => MoveError::Pinned,
+            false => MoveError::Check,
+        }
     }
 
-    fn previous_unoccupied(board: &Board, position: Pos, direction: Pos, num: PosInt) -> bool {
-        num <= 1 || !step::occupied(board, position + (direction * (num - 1)))
+    pub fn moves<'a>(&'a self, board: &'a Board, position: Pos) -> impl Iterator<Item = Pos> + 'a {
+        self.kind.moves().flat_map(move |step| Self::step_moves(board, step, position, self.side))
     }
 
-    fn prevents_check(board: &Board, mov: &Move, side: Side) -> bool {
-        let kings = board.pieces.iter_with_move(mov).filter(|(.., piece)| piece.is_king(side)).map(|(pos, ..)| pos).collect::<Vec<_>>();
-        board.pieces.iter_with_move(mov).filter(|(.., piece)| piece.side == side.other()).flat_map(|(pos, piece)| piece.targets(board, *pos)).all(|target| !kings.contains(&&target))
+    fn previous_unoccupied(board: &Board, position: Pos, direction: Pos, num: PosInt) -> bool {
+        num <= 1 || !step::occupied(board, position + (direction * (num - 1)))
     }
 
     pub fn is_king(&self, side: Side) -> bool {
@@ -88,6 +134,7 @@ pub enum Piece {
     Bishop,
     Queen,
     King,
+    Fairy(&'static Fairy),
 }
 
 macro_rules! step {
@@ -107,6 +154,7 @@ impl Piece {
                 Self::Bishop => '♝',
                 Self::Queen => '♛',
                 Self::King => '♚',
+                Self::Fairy(fairy) => fairy.letter,
             },
             Side::Black => match self {
                 Self::Pawn => '♙',
@@ -115,10 +163,58 @@ impl Piece {
                 Self::Bishop => '♗',
                 Self::Queen => '♕',
                 Self::King => '♔',
+                Self::Fairy(fairy) => fairy.letter.to_ascii_lowercase(),
             },
         }
     }
 
+    /// The English letter of the piece, in upper case, as used in FEN.
+    pub const fn letter(&self) -> char {
+        match self {
+            Self::Pawn => 'P',
+            Self::Rook => 'R',
+            Self::Knight => 'N',
+            Self::Bishop => 'B',
+            Self::Queen => 'Q',
+            Self::King => 'K',
+            Self::Fairy(fairy) => fairy.letter,
+        }
+    }
+
+    /// The standard piece with the letter, see [`crate::Rules::fairies`] for the letters of fairy pieces.
+    pub const fn from_letter(letter: char) -> Option<Self> {
+        match letter.to_ascii_uppercase() {
+            'P' => Some(Self::Pawn),
+            'R' => Some(Self::Rook),
+            'N' => Some(Self::Knight),
+            'B' => Some(Self::Bishop),
+            'Q' => Some(Self::Queen),
+            'K' => Some(Self::King),
+            _ => None,
+        }
+    }
+
+    /// The pieces a pawn may be promoted to, the first being the one it becomes unless another is chosen.
+    pub const PROMOTIONS: [Self; 4] = [Self::Queen, Self::Rook, Self::Bishop, Self::Knight];
+
+    /// The piece a pawn may be promoted to with the letter, in either case.
+    pub fn promotion(letter: char) -> Option<Self> {
+        Self::from_letter(letter).filter(|kind| Self::PROMOTIONS.contains(kind))
+    }
+
+    /// The usual worth of the piece in centipawns. Kings are not counted as material and are worth nothing.
+    pub const fn value(&self) -> i32 {
+        match self {
+            Self::Pawn => 100,
+            Self::Knight => 300,
+            Self::Bishop => 300,
+            Self::Rook => 500,
+            Self::Queen => 900,
+            Self::King => 0,
+            Self::Fairy(fairy) => fairy.value,
+        }
+    }
+
     pub fn targets(&self) -> Iter<'static, &'static dyn PieceStep> {
         match self {
             Self::Pawn => step!(PawnTake),
@@ -127,6 +223,7 @@ impl Piece {
             Self::Bishop => step!(BishopStep),
             Self::Queen => step!(QueenStep),
             Self::King => step!(KingTarget),
+            Self::Fairy(fairy) => fairy.steps.iter(),
         }
     }
 
@@ -138,6 +235,7 @@ impl Piece {
             Self::Bishop => step!(BishopStep),
             Self::Queen => step!(QueenStep),
             Self::King => step!(KingMove, Castling),
+            Self::Fairy(fairy) => fairy.steps.iter(),
         }
     }
 
@@ -145,13 +243,9 @@ impl Piece {
 
 impl Display for Piece {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
-        write!(f, "{}", match self {
-            Self::Pawn => "",
-            Self::Rook => "R",
-            Self::Knight => "N",
-            Self::Bishop => "B",
-            Self::Queen => "Q",
-            Self::King => "K",
-        })
+        match self {
+            Self::Pawn => Ok(()),
+            piece => write!(f, "{}", piece.letter()),
+        }
     }
 }
\ No newline at end of file
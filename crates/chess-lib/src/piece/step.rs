@@ -1,11 +1,11 @@
 use core::slice::Iter;
 
-use crate::{Board, Move, Pos, Side};
+use crate::{Board, BoardPiece, Move, Pos, Side};
 
 pub mod pawn;
 pub mod king;
 
-pub trait PieceStep {
+pub trait PieceStep: Sync {
 
     fn once(&self) -> bool;
 
@@ -16,6 +16,18 @@ pub trait PieceStep {
         true
     }
 
+    /// Whether `condition` already checks the target square and the safety of the king,
+    /// skipping the usual checks for moving onto own pieces or into check.
+    fn custom_checks(&self) -> bool {
+        false
+    }
+
+    /// Moves the piece on the board, returning any piece taken.
+    #[allow(unused_variables)]
+    fn perform(&self, board: &mut Board, mov: Move, side: Side) -> Option<BoardPiece> {
+        board.pieces.move_piece(mov)
+    }
+
     #[allow(unused_variables)]
     fn on_move(&self, board: &mut Board, mov: Move, side: Side) {
 
@@ -82,4 +94,32 @@ impl PieceStep for KnightStep {
     fn once(&self) -> bool {
         true
     }
-}
\ No newline at end of file
+}
+
+/// Jumps straight to each of its offsets, such as the knight.
+pub struct Leaper(pub &'static [Pos]);
+
+impl PieceStep for Leaper {
+
+    fn once(&self) -> bool {
+        true
+    }
+
+    fn directions(&self) -> Iter<'static, Pos> {
+        self.0.iter()
+    }
+}
+
+/// Slides along each of its directions until blocked, such as the rook.
+pub struct Rider(pub &'static [Pos]);
+
+impl PieceStep for Rider {
+
+    fn once(&self) -> bool {
+        false
+    }
+
+    fn directions(&self) -> Iter<'static, Pos> {
+        self.0.iter()
+    }
+}
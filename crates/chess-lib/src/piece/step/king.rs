@@ -1,4 +1,6 @@
-use crate::{Board, Move, Piece, Pos, Side};
+use core::ops::RangeInclusive;
+
+use crate::{Board, BoardPiece, Move, MoveError, Piece, Pos, PosInt, Side};
 
 use super::{occupied, Iter, PieceStep, QueenStep};
 
@@ -34,12 +36,91 @@ impl PieceStep for KingMove {
     }
 
     fn condition(&self, board: &Board, mov: Move, side: Side) -> bool {
-        KingTarget.condition(board, mov, side) && Self::safe(board, mov.to, side)
+        KingTarget.condition(board, mov, side) && (!board.rules.royal() || Self::safe(board, mov.to, side))
     }
 }
 
+/// Castling is performed by the king moving onto its own rook, after which both land on their
+/// standard squares (g and f files on the king side, c and d on the queen side), as in Chess960.
+/// On wider boards, the king and rook land next to the corner on the king side.
 pub struct Castling;
 
+/// Offsets to rooks on boards up to 26 files wide.
+const CASTLING_STEPS: [Pos; 50] = {
+    let mut steps = [Pos { x: 0, y: 0 }; 50];
+    let mut i = 0;
+    while i < 25 {
+        steps[i * 2] = Pos { x: -(i as PosInt) - 1, y: 0 };
+        steps[i * 2 + 1] = Pos { x: i as PosInt + 1, y: 0 };
+        i += 1;
+    }
+    steps
+};
+
+impl Castling {
+
+    /// Files the king and rook land on, on a board `width` files wide.
+    pub const fn destinations(king_side: bool, width: PosInt) -> (PosInt, PosInt) {
+        match king_side {
+            true => (width - 2, width - 3),
+            false => (2, 3),
+        }
+    }
+
+    /// Turns a king move of two or more squares towards a rook it may castle with (e.g. "e1 g1")
+    /// into the king-takes-rook form used by the step.
+    pub fn resolve(board: &Board, mov: Move, side: Side) -> Move {
+        let y = board.size().origin(side);
+        let king_side = mov.to.x > mov.from.x;
+        match board.state.castling.get(side, king_side) {
+            Some(x) if mov.from.y == y && mov.to.y == y && (mov.to.x - mov.from.x).abs() >= 2 && !occupied(board, mov.to) => Move { to: Pos { x, y }, ..mov },
+            _ => mov,
+        }
+    }
+
+    /// Why a king move that castles, or looks like it means to, can not be played. `None` if it does not.
+    pub fn reason(board: &Board, mov: Move, side: Side) -> Option<MoveError> {
+        let y = board.size().origin(side);
+        let onto_rook = board.pieces.at(&mov.to).is_some_and(|piece| piece.side == side && piece.kind == Piece::Rook);
+        if mov.from.y != y || mov.to.y != y || ((mov.to.x - mov.from.x).abs() < 2 && !onto_rook) {
+            return None;
+        }
+        Some(match board.state.castling.get(side, mov.to.x > mov.from.x) {
+            None => MoveError::CastlingRights,
+            Some(x) if x != mov.to.x => match onto_rook {
+                true => MoveError::CastlingRights,
+                false => MoveError::Blocked,
+            },
+            Some(..) if !Self::free(board, mov, y) => MoveError::Blocked,
+            Some(..) if !Self::safe(board, mov, y, side) => MoveError::CastlingCheck,
+            Some(..) => MoveError::Variant,
+        })
+    }
+
+    fn span(a: PosInt, b: PosInt) -> RangeInclusive<PosInt> {
+        a.min(b)..=a.max(b)
+    }
+
+    /// Whether the squares the king and rook pass are empty.
+    fn free(board: &Board, mov: Move, y: PosInt) -> bool {
+        let (king, rook) = Self::destinations(mov.to.x > mov.from.x, board.size().width);
+        Self::span(mov.from.x, king).chain(Self::span(mov.to.x, rook)).all(|x| x == mov.from.x || x == mov.to.x || !occupied(board, Pos { x, y }))
+    }
+
+    /// Whether the squares the king passes, including where it starts and lands, are not attacked. They are
+    /// checked with the rook already on its square, since in Chess960 it may start out shielding them.
+    fn safe(board: &Board, mov: Move, y: PosInt, side: Side) -> bool {
+        let (king, rook) = Self::destinations(mov.to.x > mov.from.x, board.size().width);
+        let mut after = board.clone();
+        after.pieces.take(&mov.from);
+        if let Some(piece) = after.pieces.take(&mov.to) {
+            after.pieces.insert(Pos { x: rook, y }, piece);
+        }
+        Self::span(mov.from.x, king).all(|x| KingMove::safe(&after, Pos { x, y }, side))
+    }
+
+}
+
 impl PieceStep for Castling {
 
     fn once(&self) -> bool {
@@ -47,27 +128,30 @@ impl PieceStep for Castling {
     }
 
     fn directions(&self) -> Iter<'static, Pos> {
-        [Pos { x: -3, y: 0 }, Pos { x: -2, y: 0 }, Pos { x: 2, y: 0 }].iter()
+        CASTLING_STEPS.iter()
     }
 
-    fn condition(&self, board: &Board, mov: Move, side: Side) -> bool {
-        let king_offset = (mov.to - mov.from).x;
-        let direction = king_offset / king_offset.abs();
+    fn custom_checks(&self) -> bool {
+        true
+    }
 
-        let rook = Pos { x: if direction == 1 { 7 } else { 0 },  y: side.origin() };
-        let no_rook_move = || board.pieces.at(&rook).filter(|piece| board.history.of(rook).count() == 1 && piece.kind == Piece::Rook).is_some();
-        let no_king_move = || board.history.of(mov.from).count() == 1;
-        let not_in_check = || KingMove::safe(board, mov.from, side);
-        let safe_between = || (1..=king_offset.abs()).into_iter().map(|i| mov.from + Pos { x: (direction * i), y: 0 }).all(|pos| !occupied(board, pos) && KingMove::safe(board, pos, side));
+    fn condition(&self, board: &Board, mov: Move, side: Side) -> bool {
+        let y = board.size().origin(side);
+        let king_side = mov.to.x > mov.from.x;
 
-        // dbg!(rook, no_king_move, no_rook_move, not_in_check, safe_between);
-        // println!();
+        let has_right = || mov.from.y == y && board.state.castling.get(side, king_side) == Some(mov.to.x);
+        let has_rook = || board.pieces.at(&mov.to).filter(|piece| piece.side == side && piece.kind == Piece::Rook).is_some();
 
-        (no_king_move)() && (no_rook_move)() && (not_in_check)() && (safe_between)()
+        (has_right)() && (has_rook)() && Self::free(board, mov, y) && Self::safe(board, mov, y, side)
     }
 
-    fn on_move(&self, board: &mut Board, mov: Move, side: Side) {
-        let right = mov.to.x > 3;
-        board.pieces.move_piece(Move { from: Pos { x: if right { 7 } else { 0 }, y: side.origin()}, to: mov.to + Pos { x: if right { -1 } else { 1 }, y: 0 } });
+    fn perform(&self, board: &mut Board, mov: Move, side: Side) -> Option<BoardPiece> {
+        let (king, rook) = Self::destinations(mov.to.x > mov.from.x, board.size().width);
+        let y = board.size().origin(side);
+        let king_piece = board.pieces.take(&mov.from).expect("Could not get castling king!");
+        let rook_piece = board.pieces.take(&mov.to).expect("Could not get castling rook!");
+        board.pieces.insert(Pos { x: king, y }, king_piece);
+        board.pieces.insert(Pos { x: rook, y }, rook_piece);
+        None
     }
-}
\ No newline at end of file
+}
@@ -1,10 +1,10 @@
-use crate::{Board, Move, Piece, Pos, Side};
+use crate::{Board, BoardPiece, Move, Piece, Pos, Side};
 
 use super::{occupied, Iter, PieceStep};
 
 fn pawn_promotion(board: &mut Board, mov: Move, side: Side) {
-    if mov.to.y == side.origin() {
-        board.pieces.at_mut(&mov.to).expect("Could not get pawn to promote!").kind = Piece::Queen;
+    if mov.to.y == board.size().origin(side.other()) {
+        board.pieces.at_mut(&mov.to).expect("Could not get pawn to promote!").kind = mov.promotion.unwrap_or(Piece::Queen);
     }
 }
 
@@ -40,8 +40,13 @@ impl PieceStep for DoubleMove {
         [Pos { x: 0, y: 2 }].iter()
     }
 
+    /// Pawns on the first rank may also move two squares if the rules allow it, as in Horde.
     fn condition(&self, board: &Board, mov: Move, side: Side) -> bool {
-        mov.from.y == side.offset(1) && !occupied(board, mov.from + Pos { x: 0, y: side.forward() }) && !occupied(board, mov.to)
+        (mov.from.y == board.size().offset(side, 1) || (mov.from.y == board.size().origin(side) && board.rules.first_rank_double_move())) && !occupied(board, mov.from + Pos { x: 0, y: side.forward() }) && !occupied(board, mov.to)
+    }
+
+    fn on_move(&self, board: &mut Board, mov: Move, side: Side) {
+        board.state.en_passant = Some(mov.from + Pos { x: 0, y: side.forward() });
     }
     
     fn once(&self) -> bool {
@@ -86,11 +91,12 @@ impl PieceStep for EnPassant {
 
     fn condition(&self, board: &Board, mov: Move, side: Side) -> bool {
         let pos = mov.to + Pos { x: 0, y: -side.forward() };
-        board.pieces.at(&pos).filter(|piece| piece.side == side.other() && piece.kind == Piece::Pawn && board.history.of(pos).count() == 2).is_some()
+        board.pieces.at(&pos).filter(|piece| piece.side == side.other() && piece.kind == Piece::Pawn && board.state.en_passant == Some(mov.to)).is_some()
     }
     
-    fn on_move(&self, board: &mut Board, mov: Move, side: Side) {
-        board.pieces.take(&(mov.to - Pos { x: 0, y: side.forward() }));
+    fn perform(&self, board: &mut Board, mov: Move, side: Side) -> Option<BoardPiece> {
+        board.pieces.move_piece(mov);
+        board.pieces.take(&(mov.to - Pos { x: 0, y: side.forward() }))
     }
     
 }
\ No newline at end of file
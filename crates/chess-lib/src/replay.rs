@@ -0,0 +1,136 @@
+use alloc::vec::Vec;
+
+use crate::{Board, BoardHistory, MoveError, PreviousMove, Side};
+
+/// Steps through the positions of a game, from the start to after its last move, without changing the game.
+#[derive(Clone)]
+pub struct Replay {
+    /// The position before each move, and after the last.
+    boards: Vec<Board>,
+    moves: Vec<PreviousMove>,
+    ply: usize,
+}
+
+impl Replay {
+
+    /// Replays `history` from `start`, without its clock, and stands after the last move.
+    pub fn new(start: &Board, history: &BoardHistory) -> Result<Self, MoveError> {
+        let mut board = Board { clock: None, ..start.clone() };
+        let mut boards = alloc::vec![board.clone()];
+        for previous in history.moves() {
+            match previous.dropped {
+                Some(kind) => board.drop_piece(kind, previous.mov.to)?,
+                None => board.move_piece(previous.mov)?,
+            }
+            boards.push(board.clone());
+        }
+        Ok(Self { moves: history.moves().to_vec(), ply: boards.len() - 1, boards })
+    }
+
+    /// The position after `ply` half-moves.
+    pub fn board(&self) -> &Board {
+        &self.boards[self.ply]
+    }
+
+    /// The half-moves played to reach the position.
+    pub fn ply(&self) -> usize {
+        self.ply
+    }
+
+    /// The half-moves in the game.
+    pub fn len(&self) -> usize {
+        self.moves.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.moves.is_empty()
+    }
+
+    /// Every position of the game, from the start to after the last move, wherever the replay stands.
+    pub fn positions(&self) -> &[Board] {
+        &self.boards
+    }
+
+    /// Every move of the game, with the position it was played in.
+    pub fn moves(&self) -> impl Iterator<Item = (&Board, PreviousMove)> {
+        self.boards.iter().zip(self.moves.iter().copied())
+    }
+
+    /// The move that led to the position, with the position it was played in.
+    pub fn last_move(&self) -> Option<(&Board, PreviousMove)> {
+        self.ply.checked_sub(1).map(|ply| (&self.boards[ply], self.moves[ply]))
+    }
+
+    pub fn first(&mut self) -> &Board {
+        self.jump(0)
+    }
+
+    pub fn last(&mut self) -> &Board {
+        self.jump(self.len())
+    }
+
+    /// Goes back a half-move, returning `None` at the start.
+    pub fn back(&mut self) -> Option<&Board> {
+        self.ply = self.ply.checked_sub(1)?;
+        Some(self.board())
+    }
+
+    /// Goes forward a half-move, returning `None` after the last move.
+    pub fn forward(&mut self) -> Option<&Board> {
+        (self.ply < self.len()).then(|| {
+            self.ply += 1;
+            self.board()
+        })
+    }
+
+    /// Goes to the position after `ply` half-moves, or after the last move if the game is shorter.
+    pub fn jump(&mut self, ply: usize) -> &Board {
+        self.ply = ply.min(self.len());
+        self.board()
+    }
+
+    /// The half-moves played up to and including move `number` of `side`, as numbered in the game, or `None` if
+    /// the game has no such move.
+    pub fn ply_of(&self, number: usize, side: Side) -> Option<usize> {
+        let turn = self.boards[0].state.turn;
+        let ply = (number.checked_sub(turn.no + 1)? * 2 + side as usize + 1).checked_sub(turn.side as usize)?;
+        (ply <= self.len() && ply > 0).then_some(ply)
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Board, Game, Side};
+
+    #[test]
+    fn replay() {
+        let mut game = Game::new(Board::default());
+        for mov in ["e2 e4", "e7 e5", "g1 f3"] {
+            game.board.move_piece(mov.parse().unwrap()).unwrap();
+        }
+        let fen = game.board.fen();
+        let mut replay = game.replay().unwrap();
+        assert_eq!((replay.ply(), replay.len(), replay.board().fen()), (3, 3, fen.clone()));
+        assert!(replay.forward().is_none());
+        assert_eq!(replay.back().map(Board::fen), Some("rnbqkbnr/pppp1ppp/8/4p3/4P3/8/PPPP1PPP/RNBQKBNR w KQkq e6 0 2".to_string()));
+        assert_eq!(replay.first().fen(), Board::default().fen());
+        assert!(replay.back().is_none() && replay.last_move().is_none());
+        assert_eq!(replay.jump(10).fen(), fen);
+        let (before, last) = replay.last_move().unwrap();
+        assert_eq!(before.san(last.mov), "Nf3");
+        assert_eq!((replay.ply_of(1, Side::Black), replay.ply_of(2, Side::White), replay.ply_of(2, Side::Black)), (Some(2), Some(3), None));
+        assert_eq!(game.board.fen(), fen);
+        assert_eq!(replay.positions().len(), 4);
+        assert_eq!(replay.moves().map(|(board, previous)| board.san(previous.mov)).collect::<Vec<_>>(), ["e4", "e5", "Nf3"]);
+
+        // From a position with black to move on move 10, black's tenth move is the first.
+        let board = Board::from_fen("4k3/8/8/8/8/8/8/4K3 b - - 0 10").unwrap();
+        let mut game = Game::new(board);
+        for mov in ["e8 d8", "e1 d1"] {
+            game.board.move_piece(mov.parse().unwrap()).unwrap();
+        }
+        let replay = game.replay().unwrap();
+        assert_eq!((replay.ply_of(10, Side::White), replay.ply_of(10, Side::Black), replay.ply_of(11, Side::White)), (None, Some(1), Some(2)));
+    }
+}
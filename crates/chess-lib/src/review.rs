@@ -0,0 +1,185 @@
+use core::fmt::{Display, Formatter, Result as FmtResult};
+
+use alloc::string::ToString;
+use alloc::vec::Vec;
+
+use crate::pgn::Annotation;
+use crate::{Engine, Game, Limits, Move, MoveError, Pgn, PreviousMove, Score, Side};
+
+/// The most centipawns a score counts for when working out what a move lost, so that mates are not worth
+/// more than winning a great deal of material.
+const DECISIVE: i32 = 2000;
+
+/// How a move compares to the best one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Judgement {
+    Best,
+    Good,
+    Inaccuracy,
+    Mistake,
+    Blunder,
+}
+
+impl Judgement {
+
+    /// From the centipawns a move other than the best lost: under 50 is good, under 100 an inaccuracy and under
+    /// 300 a mistake.
+    pub fn from_loss(loss: i32) -> Self {
+        match loss {
+            ..50 => Self::Good,
+            50..100 => Self::Inaccuracy,
+            100..300 => Self::Mistake,
+            _ => Self::Blunder,
+        }
+    }
+
+    /// The numeric annotation glyph: `$6` (?!), `$2` (?) or `$4` (??), and none for good moves.
+    pub fn nag(&self) -> Option<u8> {
+        match self {
+            Self::Best | Self::Good => None,
+            Self::Inaccuracy => Some(6),
+            Self::Mistake => Some(2),
+            Self::Blunder => Some(4),
+        }
+    }
+
+}
+
+impl Display for Judgement {
+    fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
+        f.write_str(match self {
+            Self::Best => "best",
+            Self::Good => "good",
+            Self::Inaccuracy => "inaccuracy",
+            Self::Mistake => "mistake",
+            Self::Blunder => "blunder",
+        })
+    }
+}
+
+/// The engine's view of a move of a game.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct MoveReview {
+    pub side: Side,
+    pub played: PreviousMove,
+    /// The line the engine prefers, starting with its best move.
+    pub best: Vec<Move>,
+    /// The score of the position for the side to move before the move and after it, where `Mate(0)` is
+    /// being mated.
+    pub before: Score,
+    pub after: Score,
+    /// Centipawns the move lost against the best.
+    pub loss: i32,
+    pub judgement: Judgement,
+}
+
+/// The moves of a game, judged by the engine.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Review {
+    pub moves: Vec<MoveReview>,
+}
+
+impl Review {
+
+    /// Searches every position of the game within `limits`, judging each move by what it lost against the best.
+    pub fn new(engine: &Engine, game: &Game, limits: Limits) -> Result<Self, MoveError> {
+        let replay = game.replay()?;
+        let boards = replay.positions();
+        let searches = boards.iter().map(|board| match board.state.outcome() {
+            Some(crate::Outcome::Win(..)) => (Score::Mate(0), Vec::new()),
+            Some(crate::Outcome::Draw(..)) => (Score::Cp(0), Vec::new()),
+            None => {
+                let search = engine.search(board, limits);
+                (search.score, search.pv)
+            },
+        }).collect::<Vec<_>>();
+
+        let moves = game.moves().iter().zip(boards.iter().zip(searches.windows(2))).map(|(played, (board, pair))| {
+            let [(before, best), (after, ..)] = [&pair[0], &pair[1]];
+            let after = match *after {
+                Score::Cp(cp) => Score::Cp(-cp),
+                Score::Mate(moves) if moves > 0 => Score::Mate(-moves),
+                Score::Mate(moves) => Score::Mate(1 - moves),
+            };
+            let loss = (self::centipawns(*before) - self::centipawns(after)).max(0);
+            let judgement = match best.first() {
+                Some(mov) if *mov == played.mov && played.dropped.is_none() => Judgement::Best,
+                _ => Judgement::from_loss(loss),
+            };
+            MoveReview { side: board.state.turn.side, played: *played, best: best.clone(), before: *before, after, loss, judgement }
+        }).collect();
+        Ok(Self { moves })
+    }
+
+    /// How well a side played, from 0 to 100, by how much its moves lowered its chances of winning, as lichess
+    /// works it out. `None` if it made no moves.
+    #[cfg(feature = "std")]
+    pub fn accuracy(&self, side: Side) -> Option<f64> {
+        let chances = |score: Score| 50.0 + 50.0 * (2.0 / (1.0 + (-0.00368208 * self::centipawns(score) as f64).exp()) - 1.0);
+        let accuracies = self.moves.iter().filter(|review| review.side == side).map(|review| {
+            let drop = (chances(review.before) - chances(review.after)).max(0.0);
+            (103.1668 * (-0.04354 * drop).exp() - 3.1669).clamp(0.0, 100.0)
+        }).collect::<Vec<_>>();
+        (!accuracies.is_empty()).then(|| accuracies.iter().sum::<f64>() / accuracies.len() as f64)
+    }
+
+    /// The game in PGN, with inaccuracies, mistakes and blunders marked, the scores before and after them in
+    /// comments and the engine's line as a variation.
+    pub fn pgn(&self, game: &Game) -> Result<Pgn, MoveError> {
+        let mut pgn = game.pgn()?;
+        let replay = game.replay()?;
+        let boards = replay.positions();
+        for (i, (review, board)) in self.moves.iter().zip(boards).enumerate() {
+            let Some(nag) = review.judgement.nag() else {
+                continue;
+            };
+            let mut line = board.clone();
+            let variation = review.best.iter().map_while(|mov| {
+                let san = line.san(*mov);
+                line.move_piece(*mov).ok().map(|()| san)
+            }).collect();
+            let name = review.judgement.to_string();
+            let comment = alloc::format!("{}{}, {} to {}", name[..1].to_ascii_uppercase(), &name[1..], review.before, review.after);
+            pgn.annotations.push((i, Annotation { nags: alloc::vec![nag], comment: Some(comment), variation }));
+        }
+        pgn.set("Annotator", "chess-lib");
+        Ok(pgn)
+    }
+
+}
+
+fn centipawns(score: Score) -> i32 {
+    match score {
+        Score::Cp(cp) => cp.clamp(-DECISIVE, DECISIVE),
+        Score::Mate(moves) if moves > 0 => DECISIVE,
+        Score::Mate(..) => -DECISIVE,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Board, Engine, Game, Limits, Score, Side};
+
+    use super::{Judgement, Review};
+
+    #[test]
+    fn review() {
+        let mut game = Game::new(Board::default());
+        for mov in ["e2 e4", "e7 e5", "f1 c4", "b8 c6", "d1 h5", "g8 f6", "h5 f7"] {
+            game.board.move_piece(mov.parse().unwrap()).unwrap();
+        }
+        let review = Review::new(&Engine::new(), &game, Limits { depth: Some(2), ..Default::default() }).unwrap();
+        assert_eq!(review.moves.len(), 7);
+        let blunder = &review.moves[5];
+        assert_eq!((blunder.side, blunder.judgement, blunder.after), (Side::Black, Judgement::Blunder, Score::Mate(-1)));
+        assert_eq!((review.moves[6].judgement, review.moves[6].after), (Judgement::Best, Score::Mate(1)));
+        #[cfg(feature = "std")]
+        assert!(review.accuracy(Side::White).unwrap() > review.accuracy(Side::Black).unwrap());
+
+        let pgn = review.pgn(&game).unwrap().to_string();
+        assert!(pgn.ends_with("\n1. e4 e5 2. Bc4 Nc6 3. Qh5 Nf6 $4 {Blunder, cp 0 to mate -1} (3... g6 4. Qh3)\n4. Qxf7# 1-0\n"), "{pgn}");
+        assert_eq!(pgn.parse::<crate::Pgn>().unwrap().moves.len(), 7);
+
+        assert_eq!((Judgement::from_loss(49), Judgement::from_loss(50), Judgement::from_loss(300)), (Judgement::Good, Judgement::Inaccuracy, Judgement::Blunder));
+    }
+}
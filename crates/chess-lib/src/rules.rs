@@ -0,0 +1,291 @@
+mod antichess;
+mod atomic;
+mod capablanca;
+mod crazyhouse;
+mod horde;
+mod king_of_the_hill;
+mod three_check;
+
+use crate::{Board, BoardPiece, Fairy, Move, Piece, Side, Size};
+
+pub use antichess::Antichess;
+pub use atomic::Atomic;
+pub use capablanca::Capablanca;
+pub use crazyhouse::Crazyhouse;
+pub use horde::Horde;
+pub use king_of_the_hill::KingOfTheHill;
+pub use three_check::ThreeCheck;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Outcome {
+    /// The winning side and how it won.
+    Win(Side, &'static str),
+    Draw(&'static str),
+}
+
+/// The rules of a variant, on top of how the pieces move.
+pub trait Rules: Sync {
+
+    fn name(&self) -> &'static str;
+
+    /// The start position, in FEN.
+    fn start(&self) -> &'static str {
+        Board::START_FEN
+    }
+
+    /// The size of the board.
+    fn size(&self) -> Size {
+        Size::STANDARD
+    }
+
+    /// Fairy pieces used by the variant, which can be read from its FEN.
+    fn fairies(&self) -> &'static [&'static Fairy] {
+        &[]
+    }
+
+    /// The piece with a letter, in either case, including the fairy pieces of the variant.
+    fn piece(&self, letter: char) -> Option<Piece> {
+        Piece::from_letter(letter).or_else(|| self.fairies().iter().find(|fairy| fairy.letter == letter.to_ascii_uppercase()).map(|fairy| Piece::Fairy(fairy)))
+    }
+
+    /// Whether kings may not be left in check, and so can not be taken.
+    fn royal(&self) -> bool {
+        true
+    }
+
+    /// Whether captured pieces go to the capturer's hand, to be dropped back onto the board.
+    fn drops(&self) -> bool {
+        false
+    }
+
+    /// Whether pawns on the first rank may also move two squares, as in Horde.
+    fn first_rank_double_move(&self) -> bool {
+        false
+    }
+
+    /// The number of checks that wins the game, if any.
+    fn checks(&self) -> Option<u32> {
+        None
+    }
+
+    /// Whether a move that follows the piece's steps is legal, by default if it does not leave the king in check.
+    fn legal(&self, board: &Board, mov: Move, side: Side) -> bool {
+        let after = board.preview(mov);
+        after.king(side).is_none_or(|king| !after.attacked(king, side.other()))
+    }
+
+    /// Whether a move is allowed given the side's other moves, such as when captures are compulsory.
+    #[allow(unused_variables)]
+    fn allowed(&self, board: &Board, mov: Move, side: Side) -> bool {
+        true
+    }
+
+    /// Called after `piece` has made a move, taking `taken`.
+    #[allow(unused_variables)]
+    fn on_move(&self, board: &mut Board, mov: Move, piece: BoardPiece, taken: Option<BoardPiece>) {
+
+    }
+
+    /// Whether and how the game has ended, with the side to move about to play.
+    fn outcome(&self, board: &Board) -> Option<Outcome> {
+        let side = board.state.turn.side;
+        match board.moves().next().is_none() && board.drops().next().is_none() {
+            true => Some(match board.state.check().is_some() {
+                true => Outcome::Win(side.other(), "checkmate"),
+                false => Outcome::Draw("stalemate"),
+            }),
+            false => None,
+        }
+    }
+
+}
+
+pub struct Standard;
+
+impl Rules for Standard {
+    fn name(&self) -> &'static str {
+        "standard"
+    }
+}
+
+pub fn variants() -> [&'static dyn Rules; 8] {
+    [&Standard, &KingOfTheHill, &ThreeCheck, &Atomic, &Antichess, &Horde, &Crazyhouse, &Capablanca]
+}
+
+pub fn variant(name: &str) -> Option<&'static dyn Rules> {
+    variants().into_iter().find(|rules| rules.name().eq_ignore_ascii_case(name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{MoveError, ParseFenError, Piece, Pos};
+
+    fn play(board: &mut Board, moves: &[&str]) {
+        for mov in moves {
+            board.move_piece(mov.parse().unwrap()).unwrap_or_else(|err| panic!("Could not play {mov}: {err}"));
+        }
+    }
+
+    fn pos(s: &str) -> Pos {
+        s.parse().unwrap()
+    }
+
+    #[test]
+    fn standard() {
+        let mut board = Board::from_fen("4k3/8/8/8/8/8/4R3/4K2r w - - 0 1").unwrap();
+        assert!(board.state.check().is_some());
+        assert_eq!(board.move_piece("e2 e3".parse().unwrap()), Err(MoveError::Check));
+
+        let mut board = Board::from_fen("4k3/4r3/8/8/8/8/4R3/4K3 w - - 0 1").unwrap();
+        assert_eq!(board.move_piece("e2 d2".parse().unwrap()), Err(MoveError::Pinned));
+
+        let mut board = Board::default();
+        assert_eq!(board.move_piece("e7 e5".parse().unwrap()), Err(MoveError::WrongSide));
+        assert_eq!(board.move_piece("e3 e4".parse().unwrap()), Err(MoveError::NoPiece));
+        assert_eq!(board.move_piece("e2 e5".parse().unwrap()), Err(MoveError::Pattern));
+        assert_eq!(board.move_piece("a1 a3".parse().unwrap()), Err(MoveError::Blocked));
+        assert_eq!(board.move_piece("d1 d2".parse().unwrap()), Err(MoveError::Occupied));
+
+        let mut board = Board::from_fen("r3k2r/8/8/8/8/3r4/8/R3K2R w Qk - 0 1").unwrap();
+        assert_eq!(board.move_piece("e1 g1".parse().unwrap()), Err(MoveError::CastlingRights));
+        assert_eq!(board.move_piece("e1 c1".parse().unwrap()), Err(MoveError::CastlingCheck));
+        assert_eq!(board.move_piece("e1 d2".parse().unwrap()), Err(MoveError::Check));
+        assert_eq!(MoveError::Pinned.to_string(), "The piece is pinned to its king");
+
+        // En passant is only open on the move right after the double step.
+        let mut board = Board::default();
+        play(&mut board, &["e2 e4", "a7 a6", "e4 e5", "d7 d5"]);
+        assert!(board.clone().move_piece("e5 d6".parse().unwrap()).is_ok());
+        play(&mut board, &["h2 h3", "h7 h6"]);
+        assert_eq!(board.move_piece("e5 d6".parse().unwrap()), Err(MoveError::Pattern));
+
+        let mut board = Board::default();
+        play(&mut board, &["e2 e4", "e7 e5", "f1 c4", "b8 c6", "d1 h5", "g8 f6", "h5 f7"]);
+        assert_eq!(board.state.outcome(), Some(Outcome::Win(Side::White, "checkmate")));
+        assert!(matches!(board.move_piece("a7 a6".parse().unwrap()), Err(MoveError::Checkmate)));
+
+        let board = Board::from_fen("7k/5Q2/6K1/8/8/8/8/8 b - - 0 1").unwrap();
+        assert_eq!(board.state.outcome(), Some(Outcome::Draw("stalemate")));
+
+        // Only Horde lets a pawn on the first rank move two squares.
+        let mut board = Board::from_fen("4k3/8/8/8/8/8/8/P3K3 w - - 0 1").unwrap();
+        assert_eq!(board.move_piece("a1 a3".parse().unwrap()), Err(MoveError::Pattern));
+
+        let mut board = Board::from_fen("4k3/P7/8/8/8/8/8/4K3 w - - 0 1").unwrap();
+        play(&mut board, &["a7 a8"]);
+        assert_eq!(board.pieces.at(&pos("a8")).map(|piece| piece.kind), Some(Piece::Queen));
+    }
+
+    #[test]
+    fn king_of_the_hill() {
+        let mut board = Board::new(&KingOfTheHill);
+        play(&mut board, &["e2 e3", "e7 e6", "e1 e2", "e8 e7", "e2 d3", "e7 d6"]);
+        assert_eq!(board.state.outcome(), None);
+        play(&mut board, &["d3 d4"]);
+        assert_eq!(board.state.outcome(), Some(Outcome::Win(Side::White, "king of the hill")));
+    }
+
+    #[test]
+    fn three_check() {
+        let mut board = Board::new(&ThreeCheck);
+        assert_eq!(board.fen(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 3+3 0 1");
+        play(&mut board, &["e2 e4", "e7 e5", "d1 h5", "b8 c6", "h5 f7", "e8 f7", "f1 c4", "d7 d5"]);
+        assert_eq!(board.state.checks, [2, 0]);
+        assert_eq!(board.fen(), "r1bq1bnr/ppp2kpp/2n5/3pp3/2B1P3/8/PPPP1PPP/RNB1K1NR w KQ d6 1+3 0 5");
+        assert_eq!(Board::from_variant_fen(&board.fen(), &ThreeCheck).map(|board| board.state.checks).ok(), Some([2, 0]));
+        // Lichess writes the checks given instead of those left.
+        let given = Board::from_variant_fen("rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - +1+0 0 1", &ThreeCheck).unwrap();
+        assert_eq!((given.state.checks, given.state.outcome()), ([1, 0], None));
+        assert_eq!(given.fen(), "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w KQkq - 2+3 0 1");
+        for fen in ["4k3/8/8/8/8/8/8/4K3 w - - 4+3 0 1", "4k3/8/8/8/8/8/8/4K3 w - - +1 0 1", "4k3/8/8/8/8/8/8/4K3 w - - 1+2+3 0 1"] {
+            assert_eq!(Board::from_variant_fen(fen, &ThreeCheck).err(), Some(ParseFenError::Counter));
+        }
+        assert_eq!(Board::from_fen("4k3/8/8/8/8/8/8/4K3 w - - 3+3 0 1").err(), Some(ParseFenError::Counter));
+        assert_eq!(board.state.outcome(), None);
+        play(&mut board, &["c4 d5"]);
+        assert_eq!(board.state.outcome(), Some(Outcome::Win(Side::White, "three checks")));
+    }
+
+    #[test]
+    fn atomic() {
+        let mut board = Board::new(&Atomic);
+        play(&mut board, &["g1 f3", "d7 d5", "f3 e5", "b8 c6"]);
+        play(&mut board, &["e5 c6"]);
+        assert!(board.pieces.at(&pos("c6")).is_none());
+        assert!(board.pieces.at(&pos("e5")).is_none());
+        assert!(board.pieces.at(&pos("c7")).is_some());
+        play(&mut board, &["e7 e6", "e2 e4", "f8 b4", "a2 a3", "b4 d2"]);
+        assert_eq!(board.state.outcome(), Some(Outcome::Win(Side::Black, "explosion")));
+
+        let mut board = Board::from_fen("8/8/8/8/8/8/3k4/3Kq3 w - - 0 1").unwrap();
+        board.rules = &Atomic;
+        assert!(board.moves().all(|mov| mov.to != pos("e1")));
+    }
+
+    #[test]
+    fn antichess() {
+        let mut board = Board::new(&Antichess);
+        play(&mut board, &["e2 e4", "d7 d5"]);
+        assert_eq!(board.move_piece("a2 a3".parse().unwrap()), Err(MoveError::Variant));
+        play(&mut board, &["e4 d5", "d8 d5", "e1 e2"]);
+        assert!(board.moves().all(|mov| board.capture(mov).is_some()));
+        play(&mut board, &["d5 d2"]);
+        assert!(board.state.check().is_none());
+        play(&mut board, &["e2 d2"]);
+        assert_eq!(board.pieces.at(&pos("d2")).map(|piece| piece.kind), Some(Piece::King));
+    }
+
+    #[test]
+    fn horde() {
+        let mut board = Board::new(&Horde);
+        assert_eq!(board.pieces.of(Side::White).count(), 36);
+        play(&mut board, &["a4 a5"]);
+        assert_eq!(board.state.outcome(), None);
+
+        let mut board = Board { rules: &Horde, ..Board::from_fen("4k3/8/8/8/8/8/8/P7 w - - 0 1").unwrap() };
+        play(&mut board, &["a1 a3"]);
+
+        let board = Board { rules: &Horde, ..Board::from_fen("4k3/8/8/8/8/8/8/8 w - - 0 1").unwrap() };
+        assert_eq!(board.rules.outcome(&board), Some(Outcome::Win(Side::Black, "horde destroyed")));
+    }
+
+    #[test]
+    fn crazyhouse() {
+        let mut board = Board::new(&Crazyhouse);
+        play(&mut board, &["e2 e4", "d7 d5", "e4 d5"]);
+        assert_eq!(board.state.hands, [vec![Piece::Pawn], vec![]]);
+        assert_eq!(board.fen(), "rnbqkbnr/ppp1pppp/8/3P4/8/8/PPPP1PPP/RNBQKBNR[P] b KQkq - 0 2");
+        assert!(matches!(board.drop_piece(Piece::Pawn, pos("e5")), Err(MoveError::NoPiece)));
+        play(&mut board, &["d8 d5"]);
+        assert_eq!(board.drop_piece(Piece::Pawn, pos("e8")), Err(MoveError::Occupied));
+        assert_eq!(board.drop_piece(Piece::Pawn, pos("d8")), Err(MoveError::Pattern));
+        board.drop_piece(Piece::Pawn, pos("c4")).unwrap();
+        assert_eq!(board.state.hands, [vec![], vec![Piece::Pawn]]);
+        assert_eq!(board.history.of(pos("c4")).count(), 1);
+
+        let board = Board { rules: &Crazyhouse, ..Board::from_fen("k7/pp6/8/8/8/8/8/K6R[n] w - - 0 1").unwrap() };
+        assert_eq!(board.fen(), "k7/pp6/8/8/8/8/8/K6R[n] w - - 0 1");
+        let mut board = Board::from_fen("k7/pp6/8/8/8/8/8/K6R[q] w - - 0 1").unwrap();
+        board.rules = &Crazyhouse;
+        play(&mut board, &["h1 h8"]);
+        assert_eq!(board.state.outcome(), None);
+        assert!(board.drops().all(|(.., pos)| pos.y == 7));
+    }
+
+    #[test]
+    fn capablanca() {
+        let mut board = Board::new(&Capablanca);
+        assert_eq!(board.fen(), Capablanca.start());
+        assert_eq!(board.moves().count(), 28);
+        play(&mut board, &["c1 d3", "i8 h6", "d3 h7"]);
+        assert_eq!(board.pieces.at(&pos("h7")).map(|piece| piece.kind), Some(Piece::Fairy(&crate::ARCHBISHOP)));
+        assert_eq!(board.fen(), "rnabqkbc1r/pppppppApp/7n2/10/10/10/PPPPPPPPPP/RN1BQKBCNR b KQkq - 0 2");
+
+        let mut board = Board::from_variant_fen("r4k3r/10/10/10/10/10/10/R4K3R w KQkq - 0 1", &Capablanca).unwrap();
+        play(&mut board, &["f1 i1", "f8 c8"]);
+        assert_eq!(board.fen(), "2kr5r/10/10/10/10/10/10/R6RK1 w - - 2 2");
+        assert!(Board::from_variant_fen(Board::START_FEN, &Capablanca).is_err());
+        assert_eq!(pos("j10"), Pos { x: 9, y: 9 });
+    }
+}
@@ -0,0 +1,37 @@
+use crate::{Board, Move, Side};
+
+use super::{Outcome, Rules};
+
+/// Captures are compulsory and the king is an ordinary piece.
+/// The game is won by losing every piece or having no moves.
+pub struct Antichess;
+
+impl Rules for Antichess {
+    fn name(&self) -> &'static str {
+        "antichess"
+    }
+
+    fn start(&self) -> &'static str {
+        "rnbqkbnr/pppppppp/8/8/8/8/PPPPPPPP/RNBQKBNR w - - 0 1"
+    }
+
+    fn royal(&self) -> bool {
+        false
+    }
+
+    fn legal(&self, _board: &Board, _mov: Move, _side: Side) -> bool {
+        true
+    }
+
+    fn allowed(&self, board: &Board, mov: Move, side: Side) -> bool {
+        board.capture(mov).is_some() || !board.pieces.of(side).any(|(pos, piece)| piece.targets(board, *pos).any(|target| board.pieces.at(&target).is_some_and(|taken| taken.side != side)))
+    }
+
+    fn outcome(&self, board: &Board) -> Option<Outcome> {
+        let side = board.state.turn.side;
+        match board.pieces.of(side).next().is_none() {
+            true => Some(Outcome::Win(side, "no pieces")),
+            false => board.moves().next().is_none().then_some(Outcome::Win(side, "no moves")),
+        }
+    }
+}
@@ -0,0 +1,54 @@
+use crate::{Board, BoardPiece, Move, Piece, Pieces, Pos, Side};
+
+use super::{Outcome, Rules, Standard};
+
+/// Captures explode, removing the capturing piece and every piece but pawns next to it.
+/// The game is won by exploding the other king, and kings next to each other can not give check.
+pub struct Atomic;
+
+impl Atomic {
+
+    fn explode(pieces: &mut Pieces, center: Pos) {
+        pieces.take(&center);
+        for x in -1..=1 {
+            for y in -1..=1 {
+                let pos = center + Pos { x, y };
+                if pieces.at(&pos).is_some_and(|piece| piece.kind != Piece::Pawn) {
+                    pieces.take(&pos);
+                }
+            }
+        }
+    }
+
+}
+
+impl Rules for Atomic {
+    fn name(&self) -> &'static str {
+        "atomic"
+    }
+
+    fn legal(&self, board: &Board, mov: Move, side: Side) -> bool {
+        let mut after = board.preview(mov);
+        if board.capture(mov).is_some() {
+            Self::explode(&mut after.pieces, mov.to);
+        }
+        match (after.king(side), after.king(side.other())) {
+            (None, ..) => false,
+            (Some(..), None) => true,
+            (Some(king), Some(other)) => (king - other).abs().max() <= 1 || !after.attacked(king, side.other()),
+        }
+    }
+
+    fn on_move(&self, board: &mut Board, mov: Move, _piece: BoardPiece, taken: Option<BoardPiece>) {
+        if taken.is_some() {
+            Self::explode(&mut board.pieces, mov.to);
+        }
+    }
+
+    fn outcome(&self, board: &Board) -> Option<Outcome> {
+        Side::sides().into_iter()
+            .find(|side| board.king(*side).is_none())
+            .map(|side| Outcome::Win(side.other(), "explosion"))
+            .or_else(|| Standard.outcome(board))
+    }
+}
@@ -0,0 +1,26 @@
+use crate::{Fairy, Size, ARCHBISHOP, CHANCELLOR};
+
+use super::Rules;
+
+static FAIRIES: [&Fairy; 2] = [&ARCHBISHOP, &CHANCELLOR];
+
+/// Played on a 10x8 board, with an archbishop and a chancellor for each side.
+pub struct Capablanca;
+
+impl Rules for Capablanca {
+    fn name(&self) -> &'static str {
+        "capablanca"
+    }
+
+    fn start(&self) -> &'static str {
+        "rnabqkbcnr/pppppppppp/10/10/10/10/PPPPPPPPPP/RNABQKBCNR w KQkq - 0 1"
+    }
+
+    fn size(&self) -> Size {
+        Size { width: 10, height: 8 }
+    }
+
+    fn fairies(&self) -> &'static [&'static Fairy] {
+        &FAIRIES
+    }
+}
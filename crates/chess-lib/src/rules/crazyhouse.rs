@@ -0,0 +1,36 @@
+use crate::{Board, BoardPiece, Move, Piece};
+
+use super::Rules;
+
+/// Captured pieces join the capturer's hand, and may be dropped onto an empty square instead of moving.
+/// Promoted pieces go back to being pawns when captured.
+pub struct Crazyhouse;
+
+impl Rules for Crazyhouse {
+    fn name(&self) -> &'static str {
+        "crazyhouse"
+    }
+
+    fn drops(&self) -> bool {
+        true
+    }
+
+    fn on_move(&self, board: &mut Board, mov: Move, piece: BoardPiece, taken: Option<BoardPiece>) {
+        let promoted = &mut board.state.promoted;
+        if let Some(taken) = taken {
+            let kind = match promoted.iter().position(|pos| *pos == mov.to) {
+                Some(i) => {
+                    promoted.swap_remove(i);
+                    Piece::Pawn
+                },
+                None => taken.kind,
+            };
+            board.state.hands[piece.side as usize].push(kind);
+        }
+        if let Some(pos) = promoted.iter_mut().find(|pos| **pos == mov.from) {
+            *pos = mov.to;
+        } else if piece.kind == Piece::Pawn && board.pieces.at(&mov.to).is_some_and(|piece| piece.kind != Piece::Pawn) {
+            promoted.push(mov.to);
+        }
+    }
+}
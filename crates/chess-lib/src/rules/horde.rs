@@ -0,0 +1,27 @@
+use crate::{Board, Side};
+
+use super::{Outcome, Rules, Standard};
+
+/// White has 36 pawns and no king, and wins by checkmate. Black wins by taking every white piece.
+pub struct Horde;
+
+impl Rules for Horde {
+    fn name(&self) -> &'static str {
+        "horde"
+    }
+
+    fn start(&self) -> &'static str {
+        "rnbqkbnr/pppppppp/8/1PP2PP1/PPPPPPPP/PPPPPPPP/PPPPPPPP/PPPPPPPP w kq - 0 1"
+    }
+
+    fn first_rank_double_move(&self) -> bool {
+        true
+    }
+
+    fn outcome(&self, board: &Board) -> Option<Outcome> {
+        match board.pieces.of(Side::White).next().is_none() {
+            true => Some(Outcome::Win(Side::Black, "horde destroyed")),
+            false => Standard.outcome(board),
+        }
+    }
+}
@@ -0,0 +1,23 @@
+use crate::{Board, Pos, Side};
+
+use super::{Outcome, Rules, Standard};
+
+/// Standard chess, also won by bringing the king to one of the four center squares.
+pub struct KingOfTheHill;
+
+impl KingOfTheHill {
+    pub const HILL: [Pos; 4] = [Pos { x: 3, y: 3 }, Pos { x: 4, y: 3 }, Pos { x: 3, y: 4 }, Pos { x: 4, y: 4 }];
+}
+
+impl Rules for KingOfTheHill {
+    fn name(&self) -> &'static str {
+        "kingofthehill"
+    }
+
+    fn outcome(&self, board: &Board) -> Option<Outcome> {
+        Side::sides().into_iter()
+            .find(|side| board.king(*side).is_some_and(|king| Self::HILL.contains(&king)))
+            .map(|side| Outcome::Win(side, "king of the hill"))
+            .or_else(|| Standard.outcome(board))
+    }
+}
@@ -0,0 +1,30 @@
+use crate::{Board, BoardPiece, Move, Side};
+
+use super::{Outcome, Rules, Standard};
+
+/// Standard chess, also won by giving check three times.
+pub struct ThreeCheck;
+
+impl Rules for ThreeCheck {
+    fn name(&self) -> &'static str {
+        "threecheck"
+    }
+
+    fn checks(&self) -> Option<u32> {
+        Some(3)
+    }
+
+    fn on_move(&self, board: &mut Board, _mov: Move, piece: BoardPiece, _taken: Option<BoardPiece>) {
+        let other = piece.side.other();
+        if board.king(other).is_some_and(|king| board.attacked(king, piece.side)) {
+            board.state.checks[piece.side as usize] += 1;
+        }
+    }
+
+    fn outcome(&self, board: &Board) -> Option<Outcome> {
+        Side::sides().into_iter()
+            .find(|side| self.checks().is_some_and(|checks| board.state.checks[*side as usize] >= checks))
+            .map(|side| Outcome::Win(side, "three checks"))
+            .or_else(|| Standard.outcome(board))
+    }
+}
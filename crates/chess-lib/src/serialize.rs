@@ -0,0 +1,297 @@
+//! Serde support, enabled by the `serde` feature.
+//!
+//! Squares and moves are written as strings (e.g. "e4" and "e2 e4"), pieces by their FEN letter, and boards by
+//! their variant name, pieces, state and history. Fairy pieces are only written if they are built in or part of a
+//! variant, as others could not be found again from their letter. Clocks are saved with games, with the time left on them, and
+//! can only be read back with the `std` feature, as they run on real time.
+
+use core::time::Duration;
+
+use alloc::string::String;
+
+use hashbrown::HashMap;
+
+use serde::de::{Error, Unexpected};
+use serde::ser::Error as _;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+
+use crate::{variant, variants, Board, BoardHistory, BoardPiece, Clock, Game, Move, Outcome, Piece, Pieces, Pos, Side, State, TimeControl, FAIRIES};
+
+macro_rules! string {
+    ($ty:ty, $expected:literal) => {
+        impl Serialize for $ty {
+            fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+                serializer.collect_str(self)
+            }
+        }
+
+        impl<'de> Deserialize<'de> for $ty {
+            fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+                let s = String::deserialize(deserializer)?;
+                s.parse().map_err(|_| D::Error::invalid_value(Unexpected::Str(&s), &$expected))
+            }
+        }
+    };
+}
+
+string!(Pos, "a square such as \"e4\"");
+string!(Move, "a move such as \"e2 e4\"");
+string!(TimeControl, "a time control such as \"300+2\"");
+
+/// The piece with the letter, fairy pieces being found among the variants and then the built-in fairies.
+fn piece(letter: char) -> Option<Piece> {
+    variants().into_iter().find_map(|rules| rules.piece(letter))
+        .or_else(|| FAIRIES.into_iter().find(|fairy| fairy.letter == letter).map(Piece::Fairy))
+}
+
+impl Serialize for Piece {
+    /// Fails for a fairy piece that can not be found again by its letter.
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        match (self, self::piece(self.letter())) {
+            (Piece::Fairy(fairy), Some(Piece::Fairy(found))) if core::ptr::eq(*fairy, found) => (),
+            (Piece::Fairy(fairy), ..) => return Err(S::Error::custom(format_args!("the {fairy:?} ({}) is not a built-in fairy piece, so it can not be read back", fairy.letter))),
+            _ => (),
+        }
+        serializer.serialize_char(self.letter())
+    }
+}
+
+impl<'de> Deserialize<'de> for Piece {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let letter = char::deserialize(deserializer)?;
+        self::piece(letter).ok_or_else(|| D::Error::invalid_value(Unexpected::Char(letter), &"a piece letter"))
+    }
+}
+
+impl Serialize for Pieces {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        serializer.collect_map(self.iter())
+    }
+}
+
+impl<'de> Deserialize<'de> for Pieces {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        Ok(HashMap::<Pos, BoardPiece>::deserialize(deserializer)?.into_iter().collect())
+    }
+}
+
+#[derive(Serialize)]
+struct BoardRef<'a> {
+    variant: &'a str,
+    pieces: &'a Pieces,
+    state: &'a State,
+    history: &'a BoardHistory,
+}
+
+#[derive(Deserialize)]
+struct BoardData {
+    variant: String,
+    pieces: Pieces,
+    state: State,
+    history: BoardHistory,
+}
+
+impl Serialize for Board {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        BoardRef {
+            variant: self.rules.name(),
+            pieces: &self.pieces,
+            state: &self.state,
+            history: &self.history,
+        }.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Board {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = BoardData::deserialize(deserializer)?;
+        let rules = variant(&data.variant).ok_or_else(|| D::Error::invalid_value(Unexpected::Str(&data.variant), &"a variant name"))?;
+        let mut board = Board {
+            pieces: data.pieces,
+            state: data.state,
+            history: data.history,
+            clock: None,
+            rules,
+        };
+        board.refresh();
+        Ok(board)
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+struct ClockData {
+    control: TimeControl,
+    remaining: [Duration; 2],
+    moves: [u32; 2],
+    stages: [usize; 2],
+    running: Option<Side>,
+}
+
+impl Serialize for Clock {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        ClockData {
+            control: self.control().clone(),
+            remaining: Side::sides().map(|side| self.remaining(side)),
+            moves: self.moves,
+            stages: self.stages,
+            running: self.running(),
+        }.serialize(serializer)
+    }
+}
+
+#[cfg(feature = "std")]
+impl<'de> Deserialize<'de> for Clock {
+    /// The clock carries on in real time, from when it is read.
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = ClockData::deserialize(deserializer)?;
+        let mut clock = Clock::new(data.control);
+        clock.remaining = data.remaining;
+        clock.moves = data.moves;
+        clock.stages = data.stages;
+        if let Some(side) = data.running {
+            clock.start(side);
+        }
+        Ok(clock)
+    }
+}
+
+/// How games may end other than by the rules, which are found again when a game is read.
+const TERMINATIONS: [&str; 4] = ["resignation", "agreement", "time", "adjudication"];
+
+#[derive(Serialize)]
+struct GameRef<'a> {
+    white: &'a str,
+    black: &'a str,
+    event: &'a str,
+    site: &'a str,
+    date: &'a str,
+    round: &'a str,
+    start: &'a Board,
+    board: &'a Board,
+    clock: Option<&'a Clock>,
+    result: &'a str,
+    termination: Option<&'a str>,
+}
+
+#[derive(Deserialize)]
+struct GameData {
+    white: String,
+    black: String,
+    event: String,
+    site: String,
+    date: String,
+    round: String,
+    start: Board,
+    board: Board,
+    #[cfg(feature = "std")]
+    clock: Option<Clock>,
+    result: String,
+    termination: Option<String>,
+}
+
+impl Serialize for Game {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        GameRef {
+            white: &self.white,
+            black: &self.black,
+            event: &self.event,
+            site: &self.site,
+            date: &self.date,
+            round: &self.round,
+            start: self.start(),
+            board: &self.board,
+            clock: self.board.clock.as_ref(),
+            result: self.result(),
+            termination: self.outcome().map(|(Outcome::Win(.., reason) | Outcome::Draw(reason))| reason),
+        }.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Game {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        let data = GameData::deserialize(deserializer)?;
+        let mut game = Game::new(data.start);
+        game.board = data.board;
+        #[cfg(feature = "std")]
+        {
+            game.board.clock = data.clock;
+        }
+        (game.white, game.black, game.event, game.site, game.date, game.round) = (data.white, data.black, data.event, data.site, data.date, data.round);
+
+        let reason = TERMINATIONS.into_iter().find(|reason| data.termination.as_deref() == Some(*reason)).unwrap_or("adjudication");
+        let outcome = match data.result.as_str() {
+            "1-0" => Some(Outcome::Win(Side::White, reason)),
+            "0-1" => Some(Outcome::Win(Side::Black, reason)),
+            "1/2-1/2" => Some(Outcome::Draw(reason)),
+            _ => None,
+        };
+        if let Some(outcome) = outcome.filter(|_| game.outcome().is_none()) {
+            // A side that ran out of time is found again by the board itself.
+            game.board.end(outcome).ok();
+        }
+        Ok(game)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::{Board, BoardPiece, Capablanca, Clock, Fairy, Game, Move, Piece, Pos, Side, AMAZON, CAMEL};
+
+    #[test]
+    fn round_trip() {
+        assert_eq!(serde_json::to_string(&"e4".parse::<Pos>().unwrap()).unwrap(), "\"e4\"");
+        assert_eq!(serde_json::from_str::<Move>("\"e2 e4\"").unwrap(), "e2 e4".parse().unwrap());
+        assert!(serde_json::from_str::<Pos>("\"e\"").is_err());
+        assert_eq!(serde_json::to_string(&BoardPiece { kind: Piece::Queen, side: Side::Black }).unwrap(), r#"{"kind":"Q","side":"Black"}"#);
+
+        let mut board = Board::default();
+        for mov in ["e2 e4", "e7 e5", "g1 f3"] {
+            board.move_piece(mov.parse().unwrap()).unwrap();
+        }
+        let json = serde_json::to_string(&board).unwrap();
+        let mut loaded = serde_json::from_str::<Board>(&json).unwrap();
+        assert_eq!(loaded.fen(), board.fen());
+        assert_eq!(loaded.history.of("f3".parse().unwrap()).count(), 2);
+        loaded.move_piece("b8 c6".parse().unwrap()).unwrap();
+
+        let board = Board::new(&Capablanca);
+        let loaded = serde_json::from_str::<Board>(&serde_json::to_string(&board).unwrap()).unwrap();
+        assert_eq!(loaded.fen(), board.fen());
+    }
+    #[test]
+    fn fairies() {
+        static GRASSHOPPER: Fairy = Fairy { name: "Grasshopper", letter: 'G', steps: &[], value: 200 };
+        static FAKE: Fairy = Fairy { name: "Fake amazon", letter: 'Z', steps: &[], value: 0 };
+
+        for fairy in [&AMAZON, &CAMEL] {
+            let json = serde_json::to_string(&Piece::Fairy(fairy)).unwrap();
+            assert_eq!(serde_json::from_str::<Piece>(&json).unwrap(), Piece::Fairy(fairy));
+        }
+        let mut board = Board::default();
+        board.pieces.insert("d4".parse().unwrap(), BoardPiece { kind: Piece::Fairy(&CAMEL), side: Side::White });
+        let loaded = serde_json::from_str::<Board>(&serde_json::to_string(&board).unwrap()).unwrap();
+        assert_eq!(loaded.pieces.at(&"d4".parse().unwrap()).map(|piece| piece.kind), Some(Piece::Fairy(&CAMEL)));
+
+        // Pieces defined elsewhere are refused rather than read back as something else.
+        assert!(serde_json::to_string(&Piece::Fairy(&GRASSHOPPER)).is_err());
+        assert!(serde_json::to_string(&Piece::Fairy(&FAKE)).is_err());
+    }
+
+    #[test]
+    fn game() {
+        let mut game = Game::new(Board { clock: Some(Clock::new("300+2".parse().unwrap())), ..Board::default() });
+        for mov in ["e2 e4", "e7 e5"] {
+            game.board.move_piece(mov.parse().unwrap()).unwrap();
+        }
+        game.white = "Ann".into();
+        game.resign(Side::Black).unwrap();
+
+        let loaded = serde_json::from_str::<Game>(&serde_json::to_string(&game).unwrap()).unwrap();
+        assert_eq!((loaded.white.as_str(), loaded.result(), loaded.moves()), ("Ann", "1-0", game.moves()));
+        assert_eq!(loaded.outcome(), game.outcome());
+        assert_eq!(loaded.start().fen(), Board::START_FEN);
+        let (clock, saved) = (loaded.board.clock.as_ref().unwrap(), game.board.clock.as_ref().unwrap());
+        assert_eq!((clock.moves(Side::Black), clock.running()), (saved.moves(Side::Black), None));
+        assert_eq!(clock.remaining(Side::Black), saved.remaining(Side::Black));
+    }
+}
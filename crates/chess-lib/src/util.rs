@@ -3,6 +3,7 @@ mod pos;
 pub use pos::*;
 
 #[derive(Default, Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum Side {
     #[default]
     White, 
@@ -17,25 +18,69 @@ impl Side {
         }
     }
     
-    pub fn origin(&self) -> PosInt {
-        match self {
-            Self::White => 0,
-            Self::Black => 7,
-        }
+    /// The back rank of a side on an 8x8 board.
+    #[deprecated(note = "use `Size::origin`, which knows the board's height")]
+    pub const fn origin(&self) -> PosInt {
+        Size::STANDARD.origin(*self)
     }
 
-    pub fn forward(&self) -> PosInt {
+    pub const fn forward(&self) -> PosInt {
         match self {
             Self::White => 1,
             Self::Black => -1,
         }
     }
 
-    pub fn offset(&self, offset: PosInt) -> PosInt {
-        self.origin() + (offset * self.forward())
+    /// The rank `offset` ranks in front of a side's back rank on an 8x8 board.
+    #[deprecated(note = "use `Size::offset`, which knows the board's height")]
+    pub const fn offset(&self, offset: PosInt) -> PosInt {
+        Size::STANDARD.offset(*self, offset)
     }
 
     pub fn sides() -> [Self; 2] {
         [Self::White, Self::Black]
     }
-}
\ No newline at end of file
+}
+
+/// The number of files and ranks of a board.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Size {
+    pub width: PosInt,
+    pub height: PosInt,
+}
+
+impl Size {
+
+    pub const STANDARD: Self = Self { width: 8, height: 8 };
+
+    pub const fn contains(&self, pos: Pos) -> bool {
+        pos.x >= 0 && pos.x < self.width && pos.y >= 0 && pos.y < self.height
+    }
+
+    /// The back rank of a side.
+    pub const fn origin(&self, side: Side) -> PosInt {
+        match side {
+            Side::White => 0,
+            Side::Black => self.height - 1,
+        }
+    }
+
+    /// The rank `offset` ranks in front of a side's back rank.
+    pub const fn offset(&self, side: Side, offset: PosInt) -> PosInt {
+        self.origin(side) + (offset * side.forward())
+    }
+
+    /// Every square, rank by rank from a1.
+    pub fn squares(&self) -> impl Iterator<Item = Pos> {
+        let size = *self;
+        (0..size.height).flat_map(move |y| (0..size.width).map(move |x| Pos { x, y }))
+    }
+
+}
+
+impl Default for Size {
+    fn default() -> Self {
+        Self::STANDARD
+    }
+}
@@ -2,7 +2,9 @@ use core::fmt::{Display, Result as FmtResult, Formatter};
 use core::ops::{Add, Mul, Sub};
 use core::str::FromStr;
 
-use std::error::Error;
+use core::error::Error;
+
+use crate::Piece;
 
 pub type PosInt = i8;
 
@@ -17,13 +19,15 @@ pub struct Pos {
 pub struct Move {
     pub from: Pos,
     pub to: Pos,
+    /// The piece a pawn reaching the last rank becomes, which is a queen when `None`.
+    pub promotion: Option<Piece>,
 }
 
 impl Pos {
 
     pub const CHAR_MIN: char = 'a';
-    /// Inclusive
-    pub const CHAR_MAX: char = 'h';
+    /// Inclusive, for boards of up to 26 files
+    pub const CHAR_MAX: char = 'z';
 
     pub const fn directions() -> [Self; 4] {
         [
@@ -51,12 +55,32 @@ impl Pos {
         Self { x: self.x.abs(), y: self.y.abs() }
     }
 
+    /// The squares strictly between two squares, along the smallest step joining them (e.g. d2 and e3 from c1 to f4).
+    /// There are none between neighbours or a knight's move apart.
+    pub fn between(self, to: Self) -> impl Iterator<Item = Self> {
+        let diff = to - self;
+        let (mut a, mut b) = (diff.x.abs(), diff.y.abs());
+        while b != 0 {
+            (a, b) = (b, a % b);
+        }
+        let step = match a {
+            0 => Self::default(),
+            n => Self { x: diff.x / n, y: diff.y / n },
+        };
+        (1..a.max(1)).map(move |i| self + step * i)
+    }
+
 }
 
 impl Move {
 
     pub const fn new(from: Pos, to: Pos) -> Self {
-        Self { from, to }
+        Self { from, to, promotion: None }
+    }
+
+    /// The move promoting to `kind`, with a queen kept as `None` so that both forms are the same move.
+    pub const fn promoting(self, kind: Piece) -> Self {
+        Self { promotion: if matches!(kind, Piece::Queen) { None } else { Some(kind) }, ..self }
     }
 
 }
@@ -107,17 +131,21 @@ impl Mul<i8> for Pos {
 
 impl Display for Pos {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
-        write!(f, "{}{}", ('a' as u8 + self.x as u8) as char, self.y + 1)
+        write!(f, "{}{}", (b'a' + self.x as u8) as char, self.y + 1)
     }
 }
 
 impl Display for Move {
     fn fmt(&self, f: &mut Formatter<'_>) -> FmtResult {
-        write!(f, "{} {}", self.from, self.to)
+        write!(f, "{} {}", self.from, self.to)?;
+        match self.promotion {
+            Some(kind) => write!(f, "={}", kind.letter()),
+            None => Ok(()),
+        }
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ParsePosError {
     Char,
     Digit,
@@ -139,8 +167,9 @@ impl Error for ParsePosError {}
 impl FromStr for Pos {
     type Err = ParsePosError;
 
+    /// Parses squares of any board size, such as "e4" or "j10".
     fn from_str(s: &str) -> Result<Self, Self::Err> {
-        if s.len() != 2 {
+        if !(2..=3).contains(&s.len()) {
             return Err(ParsePosError::Length);
         }
         let mut chars = s.chars();
@@ -149,18 +178,24 @@ impl FromStr for Pos {
             return Err(ParsePosError::Char);
         }
         let x = xchar as PosInt - (Self::CHAR_MIN as PosInt);
-        let y = chars.next().unwrap().to_digit(10).ok_or(ParsePosError::Digit)? as PosInt - 1;
-        if !(0..8).contains(&y) {
+        let digits = chars.as_str();
+        if !digits.bytes().all(|b| b.is_ascii_digit()) {
+            return Err(ParsePosError::Digit);
+        }
+        let y = digits.parse::<PosInt>().map_err(|_| ParsePosError::Digit)? - 1;
+        if !(0..26).contains(&y) {
             return Err(ParsePosError::Digit);
         }
         Ok(Self { x, y })
     }
 }
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum ParseMoveError {
     Pos(bool, ParsePosError),
     Length,
+    /// A promotion to a piece that pawns can not become, or on a move that does not promote.
+    Promotion,
 }
 
 impl Display for ParseMoveError {
@@ -168,6 +203,7 @@ impl Display for ParseMoveError {
         match self {
             ParseMoveError::Pos(first, err) => write!(f, "Invalid {} position with error {}", if *first { "first" } else { "second" }, err),
             ParseMoveError::Length => write!(f, "Invalid length / components"),
+            ParseMoveError::Promotion => write!(f, "Invalid promotion"),
         }
     }
 }
@@ -180,15 +216,27 @@ impl FromStr for Move {
     fn from_str(s: &str) -> Result<Self, Self::Err> {
         let mut parts = s.split_whitespace();
         let first = parts.next().ok_or(ParseMoveError::Length)?.parse::<Pos>().map_err(|e| ParseMoveError::Pos(true, e))?;
-        let second = parts.next().ok_or(ParseMoveError::Length)?.parse::<Pos>().map_err(|e| ParseMoveError::Pos(false, e))?;
+        let (second, promotion) = self::promoted(parts.next().ok_or(ParseMoveError::Length)?)?;
         if parts.next().is_some() {
-            return Err(ParseMoveError::Length);
+            Err(ParseMoveError::Length)
         } else {
-            return Ok(Self { from: first, to: second });
+            Ok(Self { from: first, to: second, promotion })
         }
     }
 }
 
+/// Reads a square, which may be followed by "=" and the letter of the piece a pawn becomes there (e.g. "a8=N").
+fn promoted(s: &str) -> Result<(Pos, Option<Piece>), ParseMoveError> {
+    let (pos, promotion) = s.split_once('=').map_or((s, None), |(pos, letter)| (pos, Some(letter)));
+    let pos = pos.parse().map_err(|err| ParseMoveError::Pos(false, err))?;
+    let promotion = match promotion.map(|letter| letter.parse::<char>().ok().and_then(Piece::promotion)) {
+        Some(None) => return Err(ParseMoveError::Promotion),
+        Some(kind) => kind.filter(|kind| *kind != Piece::Queen),
+        None => None,
+    };
+    Ok((pos, promotion))
+}
+
 
 pub enum ParsePositions {
     Pos(Pos),
@@ -205,9 +253,10 @@ impl ParsePositions {
             Err(err) => return Self::Error(err),
         };
         match parts.next() {
-            Some(pos) => match pos.parse() {
-                Ok(to) => Self::Move(Move { from, to }),
-                Err(err) => Self::Error(err),
+            Some(pos) => match self::promoted(pos) {
+                Ok((to, promotion)) => Self::Move(Move { from, to, promotion }),
+                Err(ParseMoveError::Pos(.., err)) => Self::Error(err),
+                Err(..) => Self::Error(ParsePosError::Char),
             },
             None => Self::Pos(from),
         }
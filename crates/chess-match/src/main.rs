@@ -0,0 +1,348 @@
+pub mod openings;
+pub mod play;
+pub mod player;
+pub mod sprt;
+pub mod stats;
+pub mod suite;
+
+use std::fmt::Display;
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::Duration;
+
+use chess_lib::{Board, Game, Limits, MoveError, Outcome, Side};
+use openings::OpeningsError;
+use play::Settings;
+use player::{Player, PlayerError};
+use sprt::{Sprt, Verdict};
+use stats::Results;
+use suite::{Report, SuiteError};
+
+const USAGE: &str = "Usage: chess-match --engine <spec> --engine <spec> [options]
+       chess-match --engine <spec> --suite <file> [--suite <file>...] --depth <n> | --movetime <ms>
+
+The second form runs the engine on test suites of EPD positions, such as WAC, ECM or STS, and reports how many of
+their best moves it finds.
+
+Engines are given as the command line of a UCI engine, or as \"builtin\" with options such as
+\"builtin:depth=3,nodes=5000,center=0,quiescence=false,name=Test\".
+
+Options:
+  --games <n>              Games to play, in pairs with colours reversed (default 2, no limit with --sprt)
+  --tc <control>           PGN time control in seconds, e.g. 60+1
+  --depth <n>              Search depth for engines without a clock or limits of their own
+  --movetime <ms>          Time for each move for engines without a clock
+  --openings <file>        Openings from an EPD or PGN file, each played twice
+  --pgn <file>             Write the games to a PGN file
+  --resign <cp>/<moves>    Adjudicate a loss after moves scored at most -cp by the loser
+  --draw <cp>/<moves>/<n>  Adjudicate a draw after moves scored within cp from move n
+  --max-moves <n>          Adjudicate a draw after n moves
+  --tablebase builtin      Adjudicate endgames known from the material, such as a rook against a bare king or a
+                           lone minor piece; Syzygy tablebases are not supported
+  --sprt <elo0>/<elo1>[/<alpha>/<beta>]
+                           Stop once the first engine is shown to be elo1 stronger or no more than elo0
+                           stronger, alpha and beta being the odds of either being wrong (default 0.05)";
+
+/// What to play, as given on the command line.
+#[derive(Debug, Clone, Default)]
+pub struct Options {
+    pub engines: Vec<String>,
+    /// Games to play, or `None` for the default.
+    pub games: Option<usize>,
+    pub openings: Option<PathBuf>,
+    pub pgn: Option<PathBuf>,
+    pub settings: Settings,
+    pub sprt: Option<Sprt>,
+    /// EPD test suites to run the engine on, instead of playing a match.
+    pub suites: Vec<PathBuf>,
+}
+
+impl Options {
+
+    pub fn parse(args: impl IntoIterator<Item = String>) -> Result<Self, String> {
+        let mut options = Self::default();
+        let mut args = args.into_iter();
+        while let Some(arg) = args.next() {
+            let value = args.next().ok_or_else(|| format!("Expected a value after {arg}"))?;
+            let numbers = || value.split('/').map(|number| self::parse::<i64>(&arg, number)).collect::<Result<Vec<_>, _>>();
+            match arg.as_str() {
+                "--engine" => options.engines.push(value),
+                "--games" => options.games = Some(self::parse(&arg, &value)?),
+                "--tc" => options.settings.control = Some(value.parse().map_err(|err| format!("Invalid time control \"{value}\" with error {err}"))?),
+                "--depth" => options.settings.limits.depth = Some(self::parse(&arg, &value)?),
+                "--movetime" => options.settings.limits.time = Some(Duration::from_millis(self::parse(&arg, &value)?)),
+                "--suite" => options.suites.push(value.into()),
+                "--openings" => options.openings = Some(value.into()),
+                "--pgn" => options.pgn = Some(value.into()),
+                "--resign" => match numbers()?.as_slice() {
+                    &[cp, moves] => options.settings.adjudication.resign = Some((cp as i32, moves as usize)),
+                    _ => return Err(format!("Invalid value \"{value}\" for {arg}, expected <cp>/<moves>")),
+                },
+                "--draw" => match numbers()?.as_slice() {
+                    &[cp, moves, from] => options.settings.adjudication.draw = Some((cp as i32, moves as usize, from as usize)),
+                    _ => return Err(format!("Invalid value \"{value}\" for {arg}, expected <cp>/<moves>/<move number>")),
+                },
+                "--max-moves" => options.settings.adjudication.max_moves = Some(self::parse(&arg, &value)?),
+                "--tablebase" => match value.as_str() {
+                    "builtin" => options.settings.adjudication.tablebase = true,
+                    _ => return Err(format!("Invalid value \"{value}\" for {arg}, only \"builtin\" is supported")),
+                },
+                "--sprt" => {
+                    let numbers = value.split('/').map(|number| self::parse::<f64>(&arg, number)).collect::<Result<Vec<_>, _>>()?;
+                    options.sprt = match *numbers.as_slice() {
+                        [elo0, elo1] => Some(Sprt::new(elo0, elo1)),
+                        [elo0, elo1, alpha, beta] => Some(Sprt { elo0, elo1, alpha, beta }),
+                        _ => return Err(format!("Invalid value \"{value}\" for {arg}, expected <elo0>/<elo1>[/<alpha>/<beta>]")),
+                    };
+                },
+                _ => return Err(format!("Unknown option {arg}")),
+            }
+        }
+        let limited = options.settings.limits != Limits::default();
+        match options.suites.is_empty() {
+            true if options.engines.len() != 2 => return Err("Expected two engines".to_string()),
+            true if options.settings.control.is_none() && !limited => return Err("Expected a time control, depth or move time".to_string()),
+            false if options.engines.len() != 1 => return Err("Expected one engine to run the suites".to_string()),
+            false if !limited => return Err("Expected a depth or move time for the suites".to_string()),
+            _ => (),
+        }
+        Ok(options)
+    }
+
+}
+
+fn parse<T: std::str::FromStr>(arg: &str, value: &str) -> Result<T, String> {
+    value.parse().map_err(|_| format!("Invalid value \"{value}\" for {arg}"))
+}
+
+#[derive(Debug)]
+pub enum MatchError {
+    Player(PlayerError),
+    Openings(OpeningsError),
+    Pgn(std::io::Error),
+    /// A game whose moves could not be written down.
+    Record(MoveError),
+    Suite(SuiteError),
+}
+
+impl Display for MatchError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Player(err) => write!(f, "{err}"),
+            Self::Openings(err) => write!(f, "{err}"),
+            Self::Pgn(err) => write!(f, "Could not write PGN: {err}"),
+            Self::Record(err) => write!(f, "Could not record the moves of a game: {err}"),
+            Self::Suite(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for MatchError {}
+
+fn main() {
+    let options = match Options::parse(std::env::args().skip(1)) {
+        Ok(options) => options,
+        Err(err) => {
+            eprintln!("{err}\n\n{USAGE}");
+            std::process::exit(2);
+        },
+    };
+    let result = match options.suites.is_empty() {
+        true => run(&options).map(|_| ()),
+        false => suites(&options).map(|_| ()),
+    };
+    if let Err(err) = result {
+        eprintln!("Error: {err}");
+        std::process::exit(1);
+    }
+}
+
+/// Plays the match, printing each result and then the table, and returns the results of the first engine.
+pub fn run(options: &Options) -> Result<Results, MatchError> {
+    let mut players = options.engines.iter().map(|spec| Player::new(spec)).collect::<Result<Vec<_>, _>>().map_err(MatchError::Player)?;
+    let openings = match &options.openings {
+        Some(path) => openings::load(path).map_err(MatchError::Openings)?,
+        None => vec![Game::new(Board::default())],
+    };
+    let mut pgn = options.pgn.as_ref().map(std::fs::File::create).transpose().map_err(MatchError::Pgn)?;
+
+    let games = options.games.or(options.sprt.is_none().then_some(2));
+    let mut results = Results::default();
+    for round in 0..games.unwrap_or(usize::MAX) {
+        let opening = &openings[round / 2 % openings.len()];
+        // The first engine plays white in even rounds.
+        let [first, second] = &mut players[..] else {
+            unreachable!("Expected two players");
+        };
+        let (white, black) = if round % 2 == 0 { (first, second) } else { (second, first) };
+        let mut game = play::play([white, black], opening, &options.settings).map_err(MatchError::Player)?;
+        game.event = "chess-match".to_string();
+        game.round = (round + 1).to_string();
+
+        let score = match game.outcome() {
+            Some(Outcome::Win(side, ..)) => if (side == Side::White) == (round % 2 == 0) { 1.0 } else { 0.0 },
+            _ => 0.5,
+        };
+        results.scores.push(score);
+        let reason = match game.outcome() {
+            Some(Outcome::Win(.., reason) | Outcome::Draw(reason)) => reason,
+            None => "unfinished",
+        };
+        let of = games.map(|games| format!(" of {games}")).unwrap_or_default();
+        println!("Game {}{of}: {} vs {} {} ({reason})", round + 1, game.white, game.black, game.result());
+
+        if let Some(file) = pgn.as_mut() {
+            let mut text = game.pgn().map_err(MatchError::Record)?;
+            text.set("Termination", play::termination(game.outcome()));
+            writeln!(file, "{text}").map_err(MatchError::Pgn)?;
+        }
+
+        if let Some(sprt) = options.sprt.filter(|_| round % 2 == 1) {
+            let (lower, upper) = sprt.bounds();
+            println!("LLR {:.2} ({lower:.2}, {upper:.2})", sprt.llr(&results));
+            if sprt.verdict(&results) != Verdict::Undecided {
+                break;
+            }
+        }
+    }
+
+    println!();
+    self::table(&results, [players[0].name(), players[1].name()]);
+    if let Some(sprt) = options.sprt {
+        println!();
+        println!("Pentanomial {:?}", results.pentanomial());
+        println!("SPRT elo0 {} elo1 {} alpha {} beta {}: LLR {:.2}, {}", sprt.elo0, sprt.elo1, sprt.alpha, sprt.beta, sprt.llr(&results), sprt.verdict(&results));
+    }
+    Ok(results)
+}
+
+/// Runs the engine on each suite, printing how it did on each position and then a summary of every suite.
+pub fn suites(options: &Options) -> Result<Vec<Report>, MatchError> {
+    let mut player = Player::new(&options.engines[0]).map_err(MatchError::Player)?;
+    let reports = options.suites.iter().map(|path| suite::run(&mut player, path, options.settings.limits).map_err(MatchError::Suite)).collect::<Result<Vec<_>, _>>()?;
+    println!();
+    for report in &reports {
+        let positions = report.solutions.len();
+        print!("{}: {} of {positions} solved ({:.1}%)", report.name, report.solved(), report.solved() as f64 * 100.0 / positions.max(1) as f64);
+        if let Some((points, max)) = report.points() {
+            print!(", {points} of {max} points");
+        }
+        match report.unreadable.len() {
+            0 => println!(),
+            unreadable => println!(", {unreadable} unreadable"),
+        }
+    }
+    Ok(reports)
+}
+
+/// Prints the results of both engines, with their Elo difference to the other.
+fn table(results: &Results, names: [&str; 2]) {
+    let width = names.iter().map(|name| name.len()).max().unwrap_or(0).max(4);
+    println!("{:<width$}  {:>7}  {:>7}  {:>5}  {:>5}  {:>6}  {:>5}  {:>6}", "Name", "Elo", "+/-", "Games", "Wins", "Losses", "Draws", "Score");
+    let (elo, margin) = results.elo().unwrap_or((0.0, 0.0));
+    let games = results.games();
+    for (i, name) in names.into_iter().enumerate() {
+        let (elo, wins, losses, score) = match i {
+            0 => (elo, results.wins(), results.losses(), results.score()),
+            _ => (-elo, results.losses(), results.wins(), 1.0 - results.score()),
+        };
+        println!("{name:<width$}  {elo:>+7.1}  {margin:>7.1}  {games:>5}  {wins:>5}  {losses:>6}  {:>5}  {:>5.1}%", results.draws(), score * 100.0);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{run, Options};
+
+    fn options(args: &str) -> Options {
+        Options::parse(args.split(' ').map(str::to_string)).unwrap()
+    }
+
+    #[test]
+    fn stand_in() {
+        // The stand-in plays scholar's mate whichever side it is on, so white wins every game.
+        let path = std::env::temp_dir().join(format!("chess-match-{}.pgn", std::process::id()));
+        let mut options = options("--engine x --engine x --games 2 --tc 60+1");
+        options.engines = vec!["sh src/tests/stand_in.sh".to_string(); 2];
+        options.pgn = Some(path.clone());
+        let results = run(&options).unwrap();
+        let pgn = std::fs::read_to_string(&path);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(results.scores, [1.0, 0.0]);
+        assert_eq!(results.elo(), Some((0.0, f64::INFINITY)));
+        let pgn = pgn.unwrap();
+        assert_eq!(chess_lib::Pgn::split(&pgn).len(), 2);
+        assert!(pgn.contains("[White \"Stand-in\"]") && pgn.contains("[Round \"2\"]") && pgn.contains("[TimeControl \"60+1\"]"));
+        assert!(pgn.contains("4. Qxf7# 1-0"));
+    }
+
+    #[test]
+    fn suite() {
+        let reports = super::suites(&options("--engine builtin --suite src/tests/suite.epd --depth 2")).unwrap();
+        let report = &reports[0];
+        assert_eq!((report.name.as_str(), report.solutions.len(), report.solved()), ("suite", 4, 3));
+        assert_eq!(report.points(), Some((10, 10)));
+        let missed = report.solutions.iter().find(|solution| !solution.solved).unwrap();
+        assert_eq!((missed.id.as_str(), missed.played.as_deref()), ("deep", Some("Ne4")));
+        assert_eq!(report.unreadable.iter().map(|(line, ..)| *line).collect::<Vec<_>>(), [6, 7, 8]);
+        assert!(Options::parse("--engine builtin --suite x.epd --tc 60+1".split(' ').map(str::to_string)).is_err());
+    }
+
+    #[test]
+    fn sprt() {
+        // The second stand-in only plays illegal moves, so it loses every game and the test stops as soon as it may.
+        let mut options = options("--engine x --engine x --depth 1 --sprt 0/10 --games 40");
+        options.engines = vec!["sh src/tests/stand_in.sh".to_string(), "sh src/tests/stand_in.sh illegal".to_string()];
+        assert_eq!(run(&options).unwrap().scores, [1.0; 2 * super::sprt::MIN_PAIRS as usize]);
+
+        // A move that is not UCI at all stops the match instead of losing the game.
+        options.engines[1] = "sh src/tests/stand_in.sh garbled".to_string();
+        assert!(matches!(run(&options), Err(super::MatchError::Player(super::PlayerError::Protocol(line))) if line == "bestmove e4"));
+    }
+
+    #[test]
+    fn tablebase() {
+        use chess_lib::{Board, Outcome, Side};
+
+        use crate::play::endgame;
+
+        let judge = |fen: &str| endgame(&Board::from_fen(fen).unwrap());
+        assert_eq!(judge("8/8/8/4k3/8/8/8/R3K3 b - - 0 1"), Some(Outcome::Win(Side::White, "adjudication")));
+        assert_eq!(judge("8/8/8/8/8/8/3k4/3Q3K b - - 0 1"), None);
+        assert_eq!(judge("8/8/8/8/8/8/3k4/3Q3K w - - 0 1"), Some(Outcome::Win(Side::White, "adjudication")));
+        assert_eq!(judge("4k3/8/8/8/8/8/8/1NN1K3 w - - 0 1"), Some(Outcome::Draw("adjudication")));
+        assert_eq!(judge("4k3/8/8/8/8/8/8/1B2K1b1 w - - 0 1"), None);
+        assert_eq!(judge("4k3/8/8/8/8/8/P7/4K3 w - - 0 1"), None);
+
+        let path = std::env::temp_dir().join(format!("chess-match-tablebase-{}.epd", std::process::id()));
+        std::fs::write(&path, "8/8/8/4k3/8/8/8/R3K3 w - -\n").unwrap();
+        let mut rook = options("--engine builtin:depth=1 --engine builtin:depth=1 --depth 1 --tablebase builtin --games 2");
+        rook.openings = Some(path.clone());
+        let results = run(&rook);
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(results.unwrap().scores, [1.0, 0.0]);
+    }
+
+    #[test]
+    fn builtin() {
+        let results = run(&options("--engine builtin:depth=1 --engine builtin:depth=1,center=0,name=Flat --depth 1 --max-moves 3 --games 2")).unwrap();
+        assert_eq!(results.scores, [0.5, 0.5]);
+        assert_eq!(results.elo(), Some((0.0, 0.0)));
+
+        let path = std::env::temp_dir().join(format!("chess-match-{}.epd", std::process::id()));
+        std::fs::write(&path, "4k3/8/8/8/8/8/8/R3K3 w Q - bm Ra8+; id \"rook\";\n").unwrap();
+        let mut rook = options("--engine builtin:depth=2 --engine builtin:depth=1 --depth 1 --resign 400/2 --games 2");
+        rook.openings = Some(path.clone());
+        let results = run(&rook);
+        std::fs::remove_file(&path).unwrap();
+        // White is a rook up in the opening, so each engine wins with it.
+        assert_eq!(results.unwrap().scores, [1.0, 0.0]);
+
+        assert!(Options::parse("--engine a --engine b --depth 1 --tablebase /syzygy".split(' ').map(str::to_string)).is_err());
+        assert_eq!(options("--engine a --engine b --depth 1 --sprt 0/5/0.1/0.2").sprt, Some(super::Sprt { elo0: 0.0, elo1: 5.0, alpha: 0.1, beta: 0.2 }));
+        assert!(Options::parse(["--engine".to_string(), "builtin".to_string()]).is_err());
+        assert!(Options::parse("--engine a --engine b --depth 1 --resign 500".split(' ').map(str::to_string)).is_err());
+        assert_eq!(options("--engine a --engine b --depth 1 --draw 10/4/30").settings.adjudication.draw, Some((10, 4, 30)));
+        assert!(super::Player::new("builtin:depth=x").is_err());
+    }
+}
@@ -0,0 +1,44 @@
+use std::fmt::Display;
+use std::path::Path;
+
+use chess_lib::{Epd, Game, ParseEpdError, ParsePgnError, Pgn};
+
+#[derive(Debug)]
+pub enum OpeningsError {
+    Io(std::io::Error),
+    /// An EPD position that could not be read, with its line number.
+    Epd(usize, ParseEpdError),
+    /// A PGN game that could not be read, with its number in the file.
+    Pgn(usize, ParsePgnError),
+    Empty,
+}
+
+impl Display for OpeningsError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "Could not read openings: {err}"),
+            Self::Epd(line, err) => write!(f, "Invalid EPD on line {line} with error {err}"),
+            Self::Pgn(game, err) => write!(f, "Invalid opening in game {game} with error {err}"),
+            Self::Empty => write!(f, "No openings found"),
+        }
+    }
+}
+
+impl std::error::Error for OpeningsError {}
+
+/// Reads openings from the games of a PGN file, or from an EPD file with one position per line.
+pub fn load(path: &Path) -> Result<Vec<Game>, OpeningsError> {
+    let text = std::fs::read_to_string(path).map_err(OpeningsError::Io)?;
+    let openings = match path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("pgn")) {
+        true => Pgn::split(&text).into_iter().enumerate()
+            .map(|(i, game)| game.parse::<Pgn>().and_then(|pgn| pgn.game()).map_err(|err| OpeningsError::Pgn(i + 1, err)))
+            .collect::<Result<Vec<_>, _>>()?,
+        false => text.lines().enumerate().filter(|(.., line)| !line.trim().is_empty() && !line.starts_with('#'))
+            .map(|(i, line)| line.parse::<Epd>().map(|epd| Game::new(epd.board)).map_err(|err| OpeningsError::Epd(i + 1, err)))
+            .collect::<Result<Vec<_>, _>>()?,
+    };
+    match openings.is_empty() {
+        true => Err(OpeningsError::Empty),
+        false => Ok(openings),
+    }
+}
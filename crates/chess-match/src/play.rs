@@ -0,0 +1,135 @@
+use chess_lib::{Board, Clock, Game, Limits, MoveError, Outcome, Piece, Side, TimeControl};
+
+use crate::player::{Player, PlayerError};
+
+/// When to end a game early, going by the scores the engines give.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct Adjudication {
+    /// Centipawns and moves: a side loses once it has scored itself at most minus the centipawns for that many
+    /// moves in a row, with the other side agreeing.
+    pub resign: Option<(i32, usize)>,
+    /// Centipawns, moves and the move to start from: a draw once both sides have scored within the centipawns of
+    /// zero for that many moves in a row.
+    pub draw: Option<(i32, usize, usize)>,
+    /// A draw once this many moves have been played.
+    pub max_moves: Option<usize>,
+    /// Whether to end standard games whose result is known from the material, see [`endgame`]. This stands in
+    /// for a tablebase, as Syzygy tablebases are not probed.
+    pub tablebase: bool,
+}
+
+impl Adjudication {
+
+    /// How to end the game, given the last scores of each side from its own point of view.
+    fn judge(&self, board: &Board, scores: &[Vec<i32>; 2]) -> Option<Outcome> {
+        if let Some(outcome) = self::endgame(board).filter(|_| self.tablebase) {
+            return Some(outcome);
+        }
+        let last = |side: Side, moves: usize| {
+            let scores = &scores[side as usize];
+            scores.get(scores.len().checked_sub(moves)?..).filter(|_| moves > 0)
+        };
+        if let Some((cp, moves)) = self.resign {
+            for side in Side::sides() {
+                let lost = last(side, moves).is_some_and(|scores| scores.iter().all(|score| *score <= -cp));
+                let won = last(side.other(), moves).is_some_and(|scores| scores.iter().all(|score| *score >= cp));
+                if lost && won {
+                    return Some(Outcome::Win(side.other(), "adjudication"));
+                }
+            }
+        }
+        if let Some((cp, moves, from)) = self.draw {
+            let drawn = Side::sides().into_iter().all(|side| last(side, moves).is_some_and(|scores| scores.iter().all(|score| score.abs() <= cp)));
+            if drawn && board.state.turn.no + 1 >= from {
+                return Some(Outcome::Draw("adjudication"));
+            }
+        }
+        self.max_moves.filter(|max| board.state.turn.no >= *max).map(|_| Outcome::Draw("adjudication"))
+    }
+
+}
+
+/// The result of a standard game that is known from the material alone, as a tablebase would give it: a win for
+/// a queen or a rook against a bare king, unless the bare king is to move and can take it, and a draw for a knight,
+/// a bishop or two knights against a bare king, or two bare kings.
+pub fn endgame(board: &Board) -> Option<Outcome> {
+    if board.rules.name() != "standard" {
+        return None;
+    }
+    let material = Side::sides().map(|side| {
+        let mut kinds = board.pieces.of(side).map(|(.., piece)| piece.kind).filter(|kind| *kind != Piece::King).collect::<Vec<_>>();
+        kinds.sort();
+        kinds
+    });
+    for side in Side::sides() {
+        if !material[side.other() as usize].is_empty() {
+            continue;
+        }
+        match material[side as usize].as_slice() {
+            [] | [Piece::Knight | Piece::Bishop] | [Piece::Knight, Piece::Knight] => return Some(Outcome::Draw("adjudication")),
+            [Piece::Queen | Piece::Rook] => {
+                let takes = board.state.turn.side != side && board.moves().any(|mov| board.pieces.at(&mov.to).is_some());
+                return (!takes).then_some(Outcome::Win(side, "adjudication"));
+            },
+            _ => (),
+        }
+    }
+    None
+}
+
+/// How the games of a match are played.
+#[derive(Debug, Clone, Default)]
+pub struct Settings {
+    pub control: Option<TimeControl>,
+    /// What players search to without a clock.
+    pub limits: Limits,
+    pub adjudication: Adjudication,
+}
+
+/// Plays a game from an opening, `players` being white and black.
+pub fn play(players: [&mut Player; 2], opening: &Game, settings: &Settings) -> Result<Game, PlayerError> {
+    let [white, black] = players;
+    let mut game = opening.clone();
+    (game.white, game.black) = (white.name().to_string(), black.name().to_string());
+    game.board.clock = settings.control.clone().map(Clock::new);
+    white.new_game()?;
+    black.new_game()?;
+    let players = [white, black];
+
+    let mut scores = [Vec::new(), Vec::new()];
+    while game.outcome().is_none() {
+        if let Some(outcome) = settings.adjudication.judge(&game.board, &scores) {
+            game.board.end(outcome).ok();
+            break;
+        }
+        let side = game.board.state.turn.side;
+        let reply = match players[side as usize].go(&game, settings.limits) {
+            Err(PlayerError::Timeout(..)) if game.board.clock.is_some() => {
+                game.board.end(Outcome::Win(side.other(), "time")).ok();
+                break;
+            },
+            reply => reply?,
+        };
+        if let Some(score) = reply.score {
+            scores[side as usize].push(score.value());
+        }
+        match reply.mov.map(|mov| game.board.move_piece(mov)) {
+            Some(Ok(()) | Err(MoveError::Timeout)) => (),
+            Some(Err(..)) | None => {
+                game.board.end(Outcome::Win(side.other(), "illegal move")).ok();
+            },
+        }
+    }
+    Ok(game)
+}
+
+/// The PGN `Termination` tag for how a game ended.
+pub fn termination(outcome: Option<Outcome>) -> &'static str {
+    match outcome {
+        Some(Outcome::Win(.., "time")) => "time forfeit",
+        Some(Outcome::Win(.., "adjudication") | Outcome::Draw("adjudication")) => "adjudication",
+        Some(Outcome::Win(.., "illegal move")) => "rules infraction",
+        Some(..) => "normal",
+        None => "unterminated",
+    }
+}
@@ -0,0 +1,276 @@
+use std::fmt::Display;
+use std::io::{BufRead, BufReader, Write};
+use std::process::{Child, ChildStdin, Command, Stdio};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use chess_lib::{Board, Bonus, Engine, Game, Limits, Move, Score};
+
+/// How long an engine may take to start up or get ready.
+const STARTUP: Duration = Duration::from_secs(10);
+/// Time an engine gets on top of its clock to send its move.
+const GRACE: Duration = Duration::from_secs(1);
+
+#[derive(Debug)]
+pub enum PlayerError {
+    /// An engine spec or option that could not be understood.
+    Spec(String),
+    Io(std::io::Error),
+    /// The engine did not answer in time, while doing what is named.
+    Timeout(&'static str),
+    /// A line from the engine that does not follow UCI.
+    Protocol(String),
+    Exited,
+}
+
+impl Display for PlayerError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Spec(spec) => write!(f, "Invalid engine option {spec}"),
+            Self::Io(err) => write!(f, "Could not talk to the engine: {err}"),
+            Self::Timeout(what) => write!(f, "The engine took too long to {what}"),
+            Self::Protocol(line) => write!(f, "The engine sent \"{line}\", which is not valid UCI"),
+            Self::Exited => write!(f, "The engine exited"),
+        }
+    }
+}
+
+impl std::error::Error for PlayerError {}
+
+/// A move chosen by a player, with its score and how deep it searched if it said.
+pub struct Reply {
+    /// `None` if the player had no move to give.
+    pub mov: Option<Move>,
+    pub score: Option<Score>,
+    pub depth: Option<u32>,
+}
+
+pub enum Player {
+    Builtin {
+        name: String,
+        engine: Engine,
+        limits: Limits,
+    },
+    Uci(Uci),
+}
+
+impl Player {
+
+    /// A player from its spec: "builtin", optionally with options as in "builtin:depth=3,center=0", or the
+    /// command line of a UCI engine.
+    pub fn new(spec: &str) -> Result<Self, PlayerError> {
+        match spec.strip_prefix("builtin") {
+            Some(options) if options.is_empty() || options.starts_with(':') => Self::builtin(spec, &options[options.len().min(1)..]),
+            _ => Uci::new(spec).map(Self::Uci),
+        }
+    }
+
+    /// The built-in engine, with the options name, depth, nodes, center and quiescence.
+    fn builtin(spec: &str, options: &str) -> Result<Self, PlayerError> {
+        let (mut name, mut engine, mut limits) = (spec.to_string(), Engine::new(), Limits::default());
+        for option in options.split(',').filter(|option| !option.is_empty()) {
+            let invalid = || PlayerError::Spec(option.to_string());
+            let (key, value) = option.split_once('=').ok_or_else(invalid)?;
+            match key {
+                "name" => name = value.to_string(),
+                "depth" => limits.depth = Some(value.parse().map_err(|_| invalid())?),
+                "nodes" => limits.nodes = Some(value.parse().map_err(|_| invalid())?),
+                "center" => engine.center = value.parse().map_err(|_| invalid())?,
+                "quiescence" => engine.quiescence = value.parse().map_err(|_| invalid())?,
+                _ => return Err(invalid()),
+            }
+        }
+        Ok(Self::Builtin { name, engine, limits })
+    }
+
+    pub fn name(&self) -> &str {
+        match self {
+            Self::Builtin { name, .. } => name,
+            Self::Uci(uci) => &uci.name,
+        }
+    }
+
+    pub fn new_game(&mut self) -> Result<(), PlayerError> {
+        match self {
+            Self::Builtin { .. } => Ok(()),
+            Self::Uci(uci) => {
+                uci.send("ucinewgame")?;
+                uci.ready()
+            },
+        }
+    }
+
+    /// Chooses a move for the side to move, on the clock if the game has one and otherwise within `limits`.
+    /// The built-in engine keeps to its own depth and nodes over those in `limits`.
+    pub fn go(&mut self, game: &Game, limits: Limits) -> Result<Reply, PlayerError> {
+        match self {
+            Self::Builtin { engine, limits: own, .. } => {
+                let mut limits = Limits { depth: own.depth.or(limits.depth), nodes: own.nodes.or(limits.nodes), time: limits.time };
+                if let Some(clock) = &game.board.clock {
+                    limits.time = Some(clock.allocation(game.board.state.turn.side));
+                }
+                let search = engine.search(&game.board, limits);
+                Ok(Reply { mov: search.best, score: search.best.map(|_| search.score), depth: Some(search.depth) })
+            },
+            Self::Uci(uci) => uci.go(game, limits),
+        }
+    }
+
+}
+
+/// An engine running as another process, spoken to in UCI.
+pub struct Uci {
+    name: String,
+    child: Child,
+    stdin: ChildStdin,
+    lines: Receiver<String>,
+}
+
+impl Uci {
+
+    /// Starts the engine from its command line, with its arguments separated by spaces.
+    pub fn new(command: &str) -> Result<Self, PlayerError> {
+        let mut words = command.split_whitespace();
+        let program = words.next().ok_or_else(|| PlayerError::Spec(command.to_string()))?;
+        let mut child = Command::new(program).args(words).stdin(Stdio::piped()).stdout(Stdio::piped()).spawn().map_err(PlayerError::Io)?;
+        let stdin = child.stdin.take().ok_or(PlayerError::Exited)?;
+        let stdout = child.stdout.take().ok_or(PlayerError::Exited)?;
+
+        let (sender, lines) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in BufReader::new(stdout).lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+
+        let mut uci = Self { name: command.to_string(), child, stdin, lines };
+        uci.send("uci")?;
+        let deadline = Instant::now() + STARTUP;
+        loop {
+            let line = uci.receive(deadline, "start")?;
+            if let Some(name) = line.strip_prefix("id name ") {
+                uci.name = name.trim().to_string();
+            } else if line.trim() == "uciok" {
+                break;
+            }
+        }
+        uci.ready()?;
+        Ok(uci)
+    }
+
+    fn send(&mut self, line: &str) -> Result<(), PlayerError> {
+        writeln!(self.stdin, "{line}").and_then(|()| self.stdin.flush()).map_err(PlayerError::Io)
+    }
+
+    fn receive(&mut self, deadline: Instant, what: &'static str) -> Result<String, PlayerError> {
+        match self.lines.recv_timeout(deadline.saturating_duration_since(Instant::now())) {
+            Ok(line) => Ok(line),
+            Err(RecvTimeoutError::Timeout) => Err(PlayerError::Timeout(what)),
+            Err(RecvTimeoutError::Disconnected) => Err(PlayerError::Exited),
+        }
+    }
+
+    fn ready(&mut self) -> Result<(), PlayerError> {
+        self.send("isready")?;
+        let deadline = Instant::now() + STARTUP;
+        while self.receive(deadline, "get ready")?.trim() != "readyok" {}
+        Ok(())
+    }
+
+    fn go(&mut self, game: &Game, limits: Limits) -> Result<Reply, PlayerError> {
+        let mut board = game.start().clone();
+        let mut position = match board.fen() == Board::START_FEN {
+            true => "position startpos".to_string(),
+            false => format!("position fen {}", board.fen()),
+        };
+        for (i, previous) in game.moves().iter().enumerate() {
+            position += if i == 0 { " moves " } else { " " };
+            position += &board.uci(previous.mov, false);
+            board.move_piece(previous.mov).map_err(|_| PlayerError::Spec(format!("move {}", previous.mov)))?;
+        }
+        self.send(&position)?;
+
+        let (command, deadline) = match &game.board.clock {
+            Some(clock) => {
+                let [white, black] = chess_lib::Side::sides().map(|side| clock.remaining(side).as_millis());
+                let increment = match clock.control().stages()[0].bonus {
+                    Bonus::Increment(time) => time.as_millis(),
+                    _ => 0,
+                };
+                let deadline = Instant::now() + clock.remaining(game.board.state.turn.side) + GRACE;
+                (format!("go wtime {white} btime {black} winc {increment} binc {increment}"), Some(deadline))
+            },
+            None => {
+                let mut command = "go".to_string();
+                for (name, limit) in [("depth", limits.depth.map(u64::from)), ("nodes", limits.nodes), ("movetime", limits.time.map(|time| time.as_millis() as u64))] {
+                    command += &limit.map(|limit| format!(" {name} {limit}")).unwrap_or_default();
+                }
+                if command == "go" {
+                    command += " depth 1";
+                }
+                (command, limits.time.map(|time| Instant::now() + time + GRACE))
+            },
+        };
+        self.send(&command)?;
+
+        let (mut score, mut depth) = (None, None);
+        loop {
+            let line = match deadline {
+                Some(deadline) => match self.receive(deadline, "move") {
+                    Err(PlayerError::Timeout(what)) => {
+                        // Wait a little for the move that is still coming, so it is not taken for the next one.
+                        self.send("stop")?;
+                        let deadline = Instant::now() + GRACE;
+                        while self.receive(deadline, what).is_ok_and(|line| !line.starts_with("bestmove")) {}
+                        return Err(PlayerError::Timeout(what));
+                    },
+                    line => line?,
+                },
+                None => self.lines.recv().map_err(|_| PlayerError::Exited)?,
+            };
+            let mut words = line.split_whitespace();
+            match words.next() {
+                Some("info") => while let Some(word) = words.next() {
+                    match word {
+                        "score" => score = match (words.next(), words.next().and_then(|value| value.parse().ok())) {
+                            (Some("cp"), Some(cp)) => Some(Score::Cp(cp)),
+                            (Some("mate"), Some(moves)) => Some(Score::Mate(moves)),
+                            _ => score,
+                        },
+                        "depth" => depth = words.next().and_then(|value| value.parse().ok()).or(depth),
+                        _ => (),
+                    }
+                },
+                Some("bestmove") => {
+                    // A move that can be read but not played loses the game, while one that can not be read at all
+                    // is the engine not speaking UCI.
+                    let mov = match words.next() {
+                        None | Some("(none)" | "0000") => None,
+                        Some(uci) => Some(game.board.parse_uci(uci).map_err(|_| PlayerError::Protocol(line.clone()))?),
+                    };
+                    return Ok(Reply { mov, score, depth });
+                },
+                _ => (),
+            }
+        }
+    }
+
+}
+
+impl Drop for Uci {
+    fn drop(&mut self) {
+        if self.send("quit").is_ok() {
+            let deadline = Instant::now() + GRACE;
+            while Instant::now() < deadline {
+                if let Ok(Some(..)) = self.child.try_wait() {
+                    return;
+                }
+                std::thread::sleep(Duration::from_millis(10));
+            }
+        }
+        let _ = self.child.kill();
+        let _ = self.child.wait();
+    }
+}
@@ -0,0 +1,121 @@
+use std::fmt::Display;
+
+use crate::stats::Results;
+
+/// Counts standing in for the outcomes of game pairs that have not happened yet, so that a match without draws or
+/// losses still has some spread.
+const PRIOR: f64 = 1e-3;
+
+/// Game pairs to play before deciding. With few pairs the prior barely spreads the results, so a single pair won
+/// twice would already look like a certain gain.
+pub const MIN_PAIRS: u32 = 10;
+
+/// A sequential probability ratio test between the first engine being `elo0` or `elo1` Elo stronger than the
+/// second, on the results of game pairs, accepting the first with `alpha` odds of being wrong and the second with
+/// `beta` odds.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Sprt {
+    pub elo0: f64,
+    pub elo1: f64,
+    pub alpha: f64,
+    pub beta: f64,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Verdict {
+    /// Stronger by `elo1`: the change passes.
+    H1,
+    /// No stronger than `elo0`: the change fails.
+    H0,
+    Undecided,
+}
+
+impl Display for Verdict {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::H1 => write!(f, "H1 accepted, the first engine is stronger"),
+            Self::H0 => write!(f, "H0 accepted, the first engine is not stronger"),
+            Self::Undecided => write!(f, "undecided"),
+        }
+    }
+}
+
+impl Sprt {
+
+    pub fn new(elo0: f64, elo1: f64) -> Self {
+        Self { elo0, elo1, alpha: 0.05, beta: 0.05 }
+    }
+
+    /// The log-likelihood ratios at which H0 and H1 are accepted.
+    pub fn bounds(&self) -> (f64, f64) {
+        ((self.beta / (1.0 - self.alpha)).ln(), ((1.0 - self.beta) / self.alpha).ln())
+    }
+
+    /// The log-likelihood ratio of H1 over H0, from the pentanomial frequencies of the game pairs, using the normal
+    /// approximation of the generalised SPRT.
+    pub fn llr(&self, results: &Results) -> f64 {
+        let pentanomial = results.pentanomial();
+        let pairs = pentanomial.iter().sum::<u32>() as f64;
+        if pairs == 0.0 {
+            return 0.0;
+        }
+        let counts = pentanomial.map(|count| if count == 0 { PRIOR } else { count as f64 });
+        let total = counts.iter().sum::<f64>();
+        let score = |i: usize| i as f64 / 4.0;
+        let mean = counts.iter().enumerate().map(|(i, count)| count * score(i)).sum::<f64>() / total;
+        let variance = counts.iter().enumerate().map(|(i, count)| count * (score(i) - mean).powi(2)).sum::<f64>() / total;
+        let (s0, s1) = (expected(self.elo0), expected(self.elo1));
+        pairs * (s1 - s0) * (2.0 * mean - s0 - s1) / (2.0 * variance)
+    }
+
+    /// Undecided until [`MIN_PAIRS`] pairs have been played.
+    pub fn verdict(&self, results: &Results) -> Verdict {
+        let (lower, upper) = self.bounds();
+        if results.pentanomial().iter().sum::<u32>() < MIN_PAIRS {
+            return Verdict::Undecided;
+        }
+        match self.llr(results) {
+            llr if llr >= upper => Verdict::H1,
+            llr if llr <= lower => Verdict::H0,
+            _ => Verdict::Undecided,
+        }
+    }
+
+}
+
+/// The expected score for an Elo difference.
+fn expected(elo: f64) -> f64 {
+    1.0 / (1.0 + 10f64.powf(-elo / 400.0))
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::stats::Results;
+
+    use super::{Sprt, Verdict};
+
+    /// Results with pairs scoring 0, 0.5, 1, 1.5 and 2 points as often as given.
+    fn results(pentanomial: [usize; 5]) -> Results {
+        let pairs = [[0.0, 0.0], [0.5, 0.0], [0.5, 0.5], [1.0, 0.5], [1.0, 1.0]];
+        let scores = pentanomial.iter().zip(pairs).flat_map(|(count, pair)| std::iter::repeat_n(pair, *count).flatten()).collect();
+        Results { scores }
+    }
+
+    #[test]
+    fn sprt() {
+        let sprt = Sprt::new(0.0, 10.0);
+        let (lower, upper) = sprt.bounds();
+        assert!((upper - 2.944).abs() < 1e-3 && (lower + 2.944).abs() < 1e-3);
+        assert_eq!(results([1, 2, 3, 4, 5]).pentanomial(), [1, 2, 3, 4, 5]);
+
+        assert_eq!(sprt.llr(&Results::default()), 0.0);
+        assert!(sprt.llr(&results([10, 20, 40, 20, 10])) < 0.0);
+        assert_eq!(sprt.verdict(&results([10, 20, 40, 20, 10])), Verdict::Undecided);
+        assert_eq!(sprt.verdict(&results([400, 800, 1600, 800, 400])), Verdict::H0);
+        assert_eq!(sprt.verdict(&results([100, 300, 800, 500, 200])), Verdict::H1);
+        // One pair won twice is far from enough, however high its LLR.
+        assert!(sprt.llr(&results([0, 0, 0, 0, 1])) > upper);
+        assert_eq!(sprt.verdict(&results([0, 0, 0, 0, 1])), Verdict::Undecided);
+        assert_eq!(sprt.verdict(&results([0, 0, 0, 0, 10])), Verdict::H1);
+    }
+}
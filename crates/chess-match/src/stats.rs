@@ -0,0 +1,65 @@
+/// The results of a match for the first engine, 1 for a win, 0.5 for a draw and 0 for a loss, in the order the
+/// games were played.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Results {
+    pub scores: Vec<f64>,
+}
+
+impl Results {
+
+    pub fn games(&self) -> usize {
+        self.scores.len()
+    }
+
+    pub fn wins(&self) -> usize {
+        self.scores.iter().filter(|score| **score == 1.0).count()
+    }
+
+    pub fn losses(&self) -> usize {
+        self.scores.iter().filter(|score| **score == 0.0).count()
+    }
+
+    pub fn draws(&self) -> usize {
+        self.scores.iter().filter(|score| **score == 0.5).count()
+    }
+
+    /// How many pairs of games, played on the same opening with colours reversed, scored 0, 0.5, 1, 1.5 and 2
+    /// points. A game still waiting for its pair is left out.
+    pub fn pentanomial(&self) -> [u32; 5] {
+        let mut counts = [0; 5];
+        for pair in self.scores.chunks_exact(2) {
+            counts[((pair[0] + pair[1]) * 2.0) as usize] += 1;
+        }
+        counts
+    }
+
+    /// The share of the points won, from 0 to 1.
+    pub fn score(&self) -> f64 {
+        self.scores.iter().sum::<f64>() / self.games().max(1) as f64
+    }
+
+    /// The Elo difference that would give the score, with the margin of its 95% confidence interval.
+    /// Either may be infinite, as after winning every game.
+    pub fn elo(&self) -> Option<(f64, f64)> {
+        if self.scores.is_empty() {
+            return None;
+        }
+        let games = self.games() as f64;
+        let score = self.score();
+        let variance = self.scores.iter().map(|s| (s - score).powi(2)).sum::<f64>() / games;
+        let margin = 1.96 * (variance / games).sqrt();
+        let (low, high) = (self::elo((score - margin).max(0.0)), self::elo((score + margin).min(1.0)));
+        // Winning or losing every game leaves no spread to go by, and no bound on the difference.
+        let margin = match high - low {
+            width if width.is_nan() => f64::INFINITY,
+            width => width / 2.0,
+        };
+        Some((self::elo(score), margin))
+    }
+
+}
+
+/// The Elo difference for a share of the points.
+pub fn elo(score: f64) -> f64 {
+    400.0 * (score / (1.0 - score)).log10()
+}
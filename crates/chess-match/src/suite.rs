@@ -0,0 +1,117 @@
+use std::fmt::Display;
+use std::path::Path;
+
+use chess_lib::{Epd, Game, Limits, Move, ParseEpdError};
+
+use crate::player::{Player, PlayerError};
+
+#[derive(Debug)]
+pub enum SuiteError {
+    Io(std::io::Error),
+    Player(PlayerError),
+}
+
+impl Display for SuiteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "Could not read the suite: {err}"),
+            Self::Player(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+impl std::error::Error for SuiteError {}
+
+/// How the engine did on a position of a suite.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Solution {
+    pub id: String,
+    /// The move played in SAN, if the engine gave one.
+    pub played: Option<String>,
+    pub solved: bool,
+    /// The points for the move and the most there were to get, in suites that give points for moves (STS).
+    pub points: Option<(u32, u32)>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Report {
+    pub name: String,
+    pub solutions: Vec<Solution>,
+    /// The positions that could not be read, or whose best moves or moves to avoid could not, with their line
+    /// numbers.
+    pub unreadable: Vec<(usize, ParseEpdError)>,
+}
+
+impl Report {
+
+    pub fn solved(&self) -> usize {
+        self.solutions.iter().filter(|solution| solution.solved).count()
+    }
+
+    /// The points scored and the most there were to get, over the positions that give points.
+    pub fn points(&self) -> Option<(u32, u32)> {
+        self.solutions.iter().filter_map(|solution| solution.points).reduce(|(points, max), (more, most)| (points + more, max + most))
+    }
+
+}
+
+/// Points for moves as given by STS suites in the first comment, e.g. `c0 "Nf3=10, Nd2=5"`.
+fn points(epd: &Epd) -> Option<Vec<(Move, u32)>> {
+    epd.comment()?.split(',').map(|entry| {
+        let (san, points) = entry.trim().split_once('=')?;
+        Some((epd.board.parse_san(san).ok()?, points.parse().ok()?))
+    }).collect()
+}
+
+/// Runs the player on each position of an EPD suite, named after its file. A position is solved when the player
+/// plays one of its best moves (`bm`) and none of the moves to avoid (`am`). Positions that can not be read are
+/// reported and skipped.
+pub fn run(player: &mut Player, path: &Path, limits: Limits) -> Result<Report, SuiteError> {
+    let name = path.file_stem().map(|stem| stem.to_string_lossy().to_string()).unwrap_or_default();
+    let text = std::fs::read_to_string(path).map_err(SuiteError::Io)?;
+    let (mut solutions, mut unreadable) = (Vec::new(), Vec::new());
+    for (i, line) in text.lines().enumerate().filter(|(.., line)| !line.trim().is_empty() && !line.starts_with('#')) {
+        let read = line.parse::<Epd>().and_then(|epd| {
+            let invalid = |err| ParseEpdError::Operation(format!("{err}"));
+            let (best, avoid) = (epd.best_moves().map_err(invalid)?, epd.avoid_moves().map_err(invalid)?);
+            Ok((epd, best, avoid))
+        });
+        let (epd, best, avoid) = match read {
+            Ok(read) => read,
+            Err(err) => {
+                println!("{name} line {}: unreadable, {err}", i + 1);
+                unreadable.push((i + 1, err));
+                continue;
+            },
+        };
+
+        player.new_game().map_err(SuiteError::Player)?;
+        let reply = player.go(&Game::new(epd.board.clone()), limits).map_err(SuiteError::Player)?;
+        let points = self::points(&epd).map(|points| {
+            let max = points.iter().map(|(.., points)| *points).max().unwrap_or(0);
+            (points.iter().find(|(mov, ..)| Some(*mov) == reply.mov).map_or(0, |(.., points)| *points), max)
+        });
+        // Positions with only points are solved by the move with the most.
+        let solved = match (reply.mov, points) {
+            (None, ..) => false,
+            (Some(..), Some((points, max))) if best.is_empty() && avoid.is_empty() => points == max && max > 0,
+            (Some(..), ..) if best.is_empty() && avoid.is_empty() => false,
+            (Some(mov), ..) => (best.is_empty() || best.contains(&mov)) && !avoid.contains(&mov),
+        };
+        let solution = Solution {
+            id: epd.id().map_or_else(|| format!("{name} {}", solutions.len() + 1), str::to_string),
+            played: reply.mov.map(|mov| epd.board.san(mov)),
+            solved,
+            points,
+        };
+
+        let expected = [("bm", &best), ("am", &avoid)].iter().filter(|(.., moves)| !moves.is_empty())
+            .map(|(opcode, moves)| format!("{opcode} {}", moves.iter().map(|mov| epd.board.san(*mov)).collect::<Vec<_>>().join(" ")))
+            .collect::<Vec<_>>().join(", ");
+        let depth = reply.depth.map(|depth| format!(" at depth {depth}")).unwrap_or_default();
+        let result = if solution.solved { "solved" } else { "unsolved" };
+        println!("{}: {result}, played {}{depth}, expected {expected}", solution.id, solution.played.as_deref().unwrap_or("nothing"));
+        solutions.push(solution);
+    }
+    Ok(Report { name, solutions, unreadable })
+}
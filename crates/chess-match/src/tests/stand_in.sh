@@ -0,0 +1,27 @@
+#!/bin/sh
+# A stand-in UCI engine for tests, which plays the moves of scholar's mate whichever side it is on, or only
+# illegal moves when started with "illegal", or moves that are not UCI when started with "garbled".
+set -f
+illegal="$1"
+mate="e2e4 e7e5 f1c4 b8c6 d1h5 g8f6 h5f7"
+played=0
+while read -r line; do
+    case "$line" in
+        uci) echo "id name Stand-in"; echo "uciok" ;;
+        isready) echo "readyok" ;;
+        "position "*" moves "*) set -- ${line#* moves }; played=$# ;;
+        "position "*) played=0 ;;
+        go*)
+            if [ "$illegal" = "illegal" ]; then
+                echo "bestmove a1a1"
+            elif [ "$illegal" = "garbled" ]; then
+                echo "bestmove e4"
+            else
+                set -- $mate
+                shift $played
+                echo "info depth 1 score cp 0 pv $1"
+                echo "bestmove $1"
+            fi ;;
+        quit) exit 0 ;;
+    esac
+done
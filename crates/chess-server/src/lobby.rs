@@ -0,0 +1,364 @@
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::sync::mpsc::Sender;
+use std::sync::Arc;
+use std::time::Duration;
+
+use chess_lib::{Board, Clock, Game, MonotonicTime, MoveError, Outcome, Piece, Pos, Rules, Side, TimeControl, TimeSource};
+
+use crate::protocol::{Request, Response, Summary};
+
+struct Client {
+    name: String,
+    sender: Sender<Response>,
+}
+
+/// How long a finished game is kept for those still watching it.
+const LINGER: Duration = Duration::from_secs(600);
+
+/// A client looking for an opponent.
+struct Seek {
+    client: u64,
+    control: Option<TimeControl>,
+    rules: &'static dyn Rules,
+}
+
+/// A game being played, with who plays and who watches it.
+struct Room {
+    game: Game,
+    /// The clients playing white and black.
+    players: [u64; 2],
+    watchers: HashSet<u64>,
+    /// The side that offered a draw, until the other side moves or answers.
+    offer: Option<Side>,
+    /// When the game ended.
+    ended: Option<Duration>,
+}
+
+impl Room {
+
+    fn side(&self, client: u64) -> Option<Side> {
+        Side::sides().into_iter().find(|side| self.players[*side as usize] == client)
+    }
+
+    /// The players and then the watchers.
+    fn audience(&self) -> impl Iterator<Item = u64> + '_ {
+        self.players.into_iter().chain(self.watchers.iter().copied().filter(|client| !self.players.contains(client)))
+    }
+
+    fn clock(&self) -> Option<[u64; 2]> {
+        self.game.board.clock.as_ref().map(|clock| Side::sides().map(|side| clock.remaining(side).as_millis() as u64))
+    }
+
+    fn summary(&self, id: u64) -> Summary {
+        Summary {
+            game: id,
+            white: self.game.white.clone(),
+            black: self.game.black.clone(),
+            variant: self.game.board.rules.name().to_string(),
+            control: self.game.time_control().map(ToString::to_string),
+            moves: self.game.moves().len(),
+            result: self.game.result().to_string(),
+        }
+    }
+
+}
+
+/// The clients connected to the server, those looking for a game and the games being played. Every request is
+/// handled under one lock, with responses queued for each client's connection to send. Finished games are kept
+/// until no one watches them, or for [`LINGER`] at most.
+pub struct Lobby {
+    clients: HashMap<u64, Client>,
+    seeks: Vec<Seek>,
+    games: BTreeMap<u64, Room>,
+    next: u64,
+    /// The time for the clocks of the games and for how long finished games are kept.
+    source: Arc<dyn TimeSource>,
+}
+
+impl Default for Lobby {
+    fn default() -> Self {
+        Self::with_source(Arc::new(MonotonicTime::default()))
+    }
+}
+
+impl Lobby {
+
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A lobby keeping time with `source`.
+    pub fn with_source(source: Arc<dyn TimeSource>) -> Self {
+        Self { clients: HashMap::new(), seeks: Vec::new(), games: BTreeMap::new(), next: 0, source }
+    }
+
+    /// Adds a client, whose responses are sent to `sender`, returning its id.
+    pub fn connect(&mut self, sender: Sender<Response>) -> u64 {
+        self.next += 1;
+        let client = self.next;
+        self.clients.insert(client, Client { name: format!("Guest {client}"), sender });
+        self.send(client, Response::Welcome { client });
+        client
+    }
+
+    /// Removes a client, resigning the games it was playing.
+    pub fn disconnect(&mut self, client: u64) {
+        self.seeks.retain(|seek| seek.client != client);
+        let playing = self.games.iter().filter(|(.., room)| room.side(client).is_some() && room.game.outcome().is_none()).map(|(id, ..)| *id).collect::<Vec<_>>();
+        for id in playing {
+            self.resign(client, id).ok();
+        }
+        self.games.values_mut().for_each(|room| {
+            room.watchers.remove(&client);
+        });
+        self.clients.remove(&client);
+        self.evict();
+    }
+
+    /// Handles a request in JSON from a client.
+    pub fn handle(&mut self, client: u64, text: &str) {
+        let handled = match serde_json::from_str::<Request>(text) {
+            Ok(request) => self.request(client, request),
+            Err(err) => Err(format!("Invalid request: {err}")),
+        };
+        if let Err(message) = handled {
+            self.send(client, Response::Error { message });
+        }
+    }
+
+    fn request(&mut self, client: u64, request: Request) -> Result<(), String> {
+        match request {
+            Request::Hello { name } => {
+                let name = name.trim();
+                if name.is_empty() {
+                    return Err("Expected a name".to_string());
+                }
+                if let Some(entry) = self.clients.get_mut(&client) {
+                    entry.name = name.to_string();
+                }
+            },
+            Request::Seek { control, variant } => {
+                let control = control.map(|control| control.parse::<TimeControl>().map_err(|err| format!("Invalid time control \"{control}\" with error {err}"))).transpose()?;
+                let rules = match variant {
+                    Some(name) => chess_lib::variant(&name).ok_or_else(|| format!("Unknown variant \"{name}\""))?,
+                    None => chess_lib::variants()[0],
+                };
+                self.seeks.retain(|seek| seek.client != client);
+                let opponent = self.seeks.iter().position(|seek| seek.control == control && seek.rules.name() == rules.name());
+                match opponent {
+                    Some(index) => {
+                        let seek = self.seeks.remove(index);
+                        self.start([seek.client, client], control, rules);
+                    },
+                    None => {
+                        self.send(client, Response::Seeking { control: control.as_ref().map(ToString::to_string), variant: rules.name().to_string() });
+                        self.seeks.push(Seek { client, control, rules });
+                    },
+                }
+            },
+            Request::Cancel => self.seeks.retain(|seek| seek.client != client),
+            Request::Move { game, mov } => self.play(client, game, &mov)?,
+            Request::Resign { game } => self.resign(client, game)?,
+            Request::Draw { game } => {
+                let (room, side) = self.playing(client, game)?;
+                if room.game.outcome().is_some() {
+                    return Err(format!("Game {game} is over"));
+                }
+                match room.offer {
+                    Some(offer) if offer != side => {
+                        room.game.draw().map_err(|err| err.to_string())?;
+                        self.end(game);
+                    },
+                    _ => {
+                        room.offer = Some(side);
+                        self.broadcast(game, Response::Offer { game, side });
+                    },
+                }
+            },
+            Request::Decline { game } => {
+                let (room, side) = self.playing(client, game)?;
+                if room.offer != Some(side.other()) {
+                    return Err("No draw has been offered".to_string());
+                }
+                room.offer = None;
+                self.broadcast(game, Response::Declined { game, side });
+            },
+            Request::Watch { game } => {
+                let room = self.games.get_mut(&game).ok_or_else(|| format!("No game {game}"))?;
+                room.watchers.insert(client);
+                let replay = room.game.replay().map_err(|err| format!("Could not replay game {game}: {err}"))?;
+                let moves = replay.moves().map(|(board, previous)| match previous.dropped {
+                    Some(kind) => board.san_drop(kind, previous.mov.to),
+                    None => board.san(previous.mov),
+                }).collect();
+                let state = Response::State {
+                    game,
+                    white: room.game.white.clone(),
+                    black: room.game.black.clone(),
+                    variant: room.game.board.rules.name().to_string(),
+                    fen: room.game.board.fen(),
+                    moves,
+                    clock: room.clock(),
+                    result: room.game.result().to_string(),
+                };
+                self.send(client, state);
+            },
+            Request::Unwatch { game } => {
+                self.games.get_mut(&game).ok_or_else(|| format!("No game {game}"))?.watchers.remove(&client);
+                self.evict();
+            },
+            Request::Games => {
+                let games = self.games.iter().map(|(id, room)| room.summary(*id)).collect();
+                self.send(client, Response::Games { games });
+            },
+        }
+        Ok(())
+    }
+
+    /// Starts a game between two clients, the one that sought first playing white.
+    fn start(&mut self, players: [u64; 2], control: Option<TimeControl>, rules: &'static dyn Rules) {
+        self.next += 1;
+        let id = self.next;
+        let mut board = Board::new(rules);
+        board.clock = control.map(|control| Clock::with_source(control, self.source.clone()));
+        let mut game = Game::new(board);
+        game.event = "chess-server".to_string();
+        [game.white, game.black] = players.map(|client| self.clients.get(&client).map(|client| client.name.clone()).unwrap_or_default());
+        for side in Side::sides() {
+            let started = Response::Started {
+                game: id,
+                side,
+                white: game.white.clone(),
+                black: game.black.clone(),
+                variant: rules.name().to_string(),
+                control: game.time_control().map(ToString::to_string),
+                fen: game.board.fen(),
+            };
+            self.send(players[side as usize], started);
+        }
+        self.games.insert(id, Room { game, players, watchers: HashSet::new(), offer: None, ended: None });
+    }
+
+    /// The game a client plays in, and its side.
+    fn playing(&mut self, client: u64, game: u64) -> Result<(&mut Room, Side), String> {
+        let room = self.games.get_mut(&game).ok_or_else(|| format!("No game {game}"))?;
+        let side = room.side(client).ok_or_else(|| format!("You do not play in game {game}"))?;
+        Ok((room, side))
+    }
+
+    fn play(&mut self, client: u64, game: u64, mov: &str) -> Result<(), String> {
+        let (room, side) = self.playing(client, game)?;
+        let board = &mut room.game.board;
+        if board.state.outcome().is_none() && board.state.turn.side != side {
+            return Err("It is not your turn".to_string());
+        }
+        let before = board.pieces.clone();
+        let ply = board.history.moves().len();
+        let played = match self::parse_drop(mov) {
+            Some((kind, pos)) => {
+                let san = board.san_drop(kind, pos);
+                board.drop_piece(kind, pos).map(|()| san)
+            },
+            None => {
+                let parsed = board.parse_uci(mov).ok().or_else(|| board.parse_san(mov).ok()).ok_or_else(|| format!("Invalid move \"{mov}\""))?;
+                let san = board.san(parsed);
+                board.move_piece(parsed).map(|()| san)
+            },
+        };
+        let san = match played {
+            Ok(san) => san,
+            Err(MoveError::Timeout) => {
+                self.end(game);
+                return Err("Your time has run out".to_string());
+            },
+            Err(err) => return Err(format!("Could not play {mov}: {err}")),
+        };
+        if room.offer == Some(side.other()) {
+            room.offer = None;
+        }
+        let update = Response::Update {
+            game,
+            ply,
+            san,
+            pieces: before.diff(&room.game.board.pieces),
+            fen: room.game.board.fen(),
+            clock: room.clock(),
+        };
+        let over = room.game.outcome().is_some();
+        self.broadcast(game, update);
+        if over {
+            self.end(game);
+        }
+        Ok(())
+    }
+
+    fn resign(&mut self, client: u64, game: u64) -> Result<(), String> {
+        let (room, side) = self.playing(client, game)?;
+        room.game.resign(side).map_err(|err| err.to_string())?;
+        self.end(game);
+        Ok(())
+    }
+
+    /// Ends the games whose side to move has run out of time, and lets go of finished games.
+    pub fn tick(&mut self) {
+        let flagged = self.games.iter_mut().filter_map(|(id, room)| {
+            let side = room.game.board.clock.as_ref().and_then(Clock::flagged).filter(|_| room.game.outcome().is_none())?;
+            // Ending a game with a flagged clock records the loss on time.
+            room.game.board.end(Outcome::Win(side.other(), "time")).ok();
+            Some(*id)
+        }).collect::<Vec<_>>();
+        for id in flagged {
+            self.end(id);
+        }
+        self.evict();
+    }
+
+    /// Removes the finished games that no one watches any more, or that ended [`LINGER`] ago.
+    fn evict(&mut self) {
+        let now = self.source.now();
+        self.games.retain(|_, room| match room.ended {
+            Some(ended) => !room.watchers.is_empty() && now.saturating_sub(ended) < LINGER,
+            None => true,
+        });
+    }
+
+    /// Tells the players and watchers of a game that it is over.
+    fn end(&mut self, game: u64) {
+        let now = self.source.now();
+        let Some(room) = self.games.get_mut(&game) else {
+            return;
+        };
+        let reason = match room.game.outcome() {
+            Some(Outcome::Win(.., reason) | Outcome::Draw(reason)) => reason,
+            None => return,
+        };
+        room.ended.get_or_insert(now);
+        let ended = Response::Ended { game, result: room.game.result().to_string(), reason: reason.to_string() };
+        self.broadcast(game, ended);
+    }
+
+    fn broadcast(&self, game: u64, response: Response) {
+        if let Some(room) = self.games.get(&game) {
+            room.audience().for_each(|client| self.send(client, response.clone()));
+        }
+    }
+
+    fn send(&self, client: u64, response: Response) {
+        if let Some(client) = self.clients.get(&client) {
+            client.sender.send(response).ok();
+        }
+    }
+
+}
+
+/// Reads a drop from the hand, written as the piece letter, "@" and the square (e.g. "N@e4").
+fn parse_drop(mov: &str) -> Option<(Piece, Pos)> {
+    let (piece, pos) = mov.split_once('@')?;
+    let mut chars = piece.chars();
+    let kind = match (chars.next(), chars.next()) {
+        (None, ..) => Piece::Pawn,
+        (Some(letter), None) => Piece::from_letter(letter)?,
+        _ => return None,
+    };
+    Some((kind, pos.parse().ok()?))
+}
@@ -0,0 +1,244 @@
+pub mod lobby;
+pub mod protocol;
+
+use std::io::ErrorKind;
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+use tungstenite::{Error as WsError, Message};
+
+use lobby::Lobby;
+
+const USAGE: &str = "Usage: chess-server [--addr <host:port>]
+
+Hosts chess games over WebSocket, with JSON messages such as {\"type\":\"seek\",\"control\":\"300+2\"} and
+{\"type\":\"move\",\"game\":1,\"move\":\"e2e4\"}. Listens on 127.0.0.1:8080 by default.";
+
+/// How often a connection checks for responses to send while waiting for its client, and how often clocks are
+/// checked for a side that has run out of time.
+const POLL: Duration = Duration::from_millis(20);
+
+fn main() {
+    let mut args = std::env::args().skip(1);
+    let addr = match (args.next().as_deref(), args.next(), args.next()) {
+        (None, ..) => "127.0.0.1:8080".to_string(),
+        (Some("--addr"), Some(addr), None) => addr,
+        _ => {
+            eprintln!("{USAGE}");
+            std::process::exit(2);
+        },
+    };
+    let listener = match TcpListener::bind(&addr) {
+        Ok(listener) => listener,
+        Err(err) => {
+            eprintln!("Error: Could not listen on {addr}: {err}");
+            std::process::exit(1);
+        },
+    };
+    println!("Listening on {addr}");
+    serve(listener, Lobby::new());
+}
+
+/// Accepts WebSocket connections to `lobby` until the listener fails, each handled on its own thread, while another
+/// ends games on time.
+pub fn serve(listener: TcpListener, lobby: Lobby) {
+    let lobby = Arc::new(Mutex::new(lobby));
+    let clocks = lobby.clone();
+    std::thread::spawn(move || loop {
+        std::thread::sleep(POLL);
+        clocks.lock().expect("Could not lock the lobby!").tick();
+    });
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                let lobby = lobby.clone();
+                std::thread::spawn(move || self::connection(stream, &lobby));
+            },
+            Err(err) => eprintln!("Could not accept a connection: {err}"),
+        }
+    }
+}
+
+/// Relays a client's requests to the lobby and the lobby's responses to the client, until either side closes.
+fn connection(stream: TcpStream, lobby: &Mutex<Lobby>) {
+    let Ok(mut socket) = tungstenite::accept(stream) else {
+        return;
+    };
+    // Reads time out so that responses are sent while the client is quiet.
+    if socket.get_ref().set_read_timeout(Some(POLL)).is_err() {
+        return;
+    }
+    let (sender, responses) = mpsc::channel();
+    let client = lobby.lock().expect("Could not lock the lobby!").connect(sender);
+    loop {
+        match socket.read() {
+            Ok(Message::Text(text)) => lobby.lock().expect("Could not lock the lobby!").handle(client, &text),
+            Ok(Message::Close(..)) | Err(WsError::ConnectionClosed | WsError::AlreadyClosed) => break,
+            Ok(..) => (),
+            Err(WsError::Io(err)) if matches!(err.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => (),
+            Err(..) => break,
+        }
+        let sent = responses.try_iter().try_for_each(|response| {
+            let text = serde_json::to_string(&response).expect("Could not write a response in JSON!");
+            socket.send(Message::Text(text)).map_err(drop)
+        });
+        if sent.is_err() {
+            break;
+        }
+    }
+    lobby.lock().expect("Could not lock the lobby!").disconnect(client);
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::{TcpListener, TcpStream};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    use chess_lib::{ManualTime, Piece, PieceUpdate, Side};
+    use tungstenite::{Message, WebSocket};
+
+    use crate::lobby::Lobby;
+    use crate::protocol::{Request, Response};
+
+    type Socket = WebSocket<tungstenite::stream::MaybeTlsStream<TcpStream>>;
+
+    /// A server on a free port, with time that only moves when the test says.
+    fn server() -> (String, ManualTime) {
+        let time = ManualTime::default();
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = format!("ws://{}", listener.local_addr().unwrap());
+        let lobby = Lobby::with_source(Arc::new(time.clone()));
+        std::thread::spawn(move || super::serve(listener, lobby));
+        (addr, time)
+    }
+
+    fn connect(addr: &str, name: &str) -> Socket {
+        let (mut socket, ..) = tungstenite::connect(addr).unwrap();
+        assert!(matches!(receive(&mut socket), Response::Welcome { .. }));
+        send(&mut socket, Request::Hello { name: name.to_string() });
+        socket
+    }
+
+    fn send(socket: &mut Socket, request: Request) {
+        socket.send(Message::Text(serde_json::to_string(&request).unwrap())).unwrap();
+    }
+
+    fn receive(socket: &mut Socket) -> Response {
+        loop {
+            if let Message::Text(text) = socket.read().unwrap() {
+                return serde_json::from_str(&text).unwrap();
+            }
+        }
+    }
+
+    fn play(socket: &mut Socket, game: u64, mov: &str) {
+        send(socket, Request::Move { game, mov: mov.to_string() });
+    }
+
+    #[test]
+    fn game() {
+        let (addr, ..) = server();
+        let mut ann = connect(&addr, "Ann");
+        let mut bob = connect(&addr, "Bob");
+        send(&mut ann, Request::Seek { control: None, variant: None });
+        assert_eq!(receive(&mut ann), Response::Seeking { control: None, variant: "standard".to_string() });
+        send(&mut bob, Request::Seek { control: None, variant: None });
+        let Response::Started { game, side, white, black, .. } = receive(&mut ann) else {
+            panic!("Expected the game to start");
+        };
+        assert_eq!((side, white.as_str(), black.as_str()), (Side::White, "Ann", "Bob"));
+        assert!(matches!(receive(&mut bob), Response::Started { side: Side::Black, .. }));
+
+        let mut eve = connect(&addr, "Eve");
+        send(&mut eve, Request::Watch { game });
+        assert!(matches!(receive(&mut eve), Response::State { moves, .. } if moves.is_empty()));
+
+        // Moves are checked, and the squares they change go to both players and the watcher.
+        play(&mut bob, game, "e7e5");
+        assert_eq!(receive(&mut bob), Response::Error { message: "It is not your turn".to_string() });
+        play(&mut ann, game, "e2e5");
+        assert!(matches!(receive(&mut ann), Response::Error { .. }));
+        play(&mut ann, game, "e2e4");
+        for socket in [&mut ann, &mut bob, &mut eve] {
+            let Response::Update { ply, san, pieces, .. } = receive(socket) else {
+                panic!("Expected a move");
+            };
+            assert_eq!((ply, san.as_str()), (0, "e4"));
+            assert_eq!(pieces[0], PieceUpdate::Update("e2".parse().unwrap(), None));
+            assert!(matches!(pieces[1], PieceUpdate::Update(.., Some(piece)) if piece.kind == Piece::Pawn));
+        }
+        for (i, mov) in ["e5", "Bc4", "Nc6", "Qh5", "Nf6", "Qxf7#"].into_iter().enumerate() {
+            play(if i % 2 == 0 { &mut bob } else { &mut ann }, game, mov);
+            assert!(matches!(receive(&mut eve), Response::Update { san, .. } if san == mov));
+        }
+        assert_eq!(receive(&mut eve), Response::Ended { game, result: "1-0".to_string(), reason: "checkmate".to_string() });
+
+        send(&mut eve, Request::Games);
+        let Response::Games { games } = receive(&mut eve) else {
+            panic!("Expected the games");
+        };
+        assert_eq!((games.len(), games[0].moves, games[0].result.as_str()), (1, 7, "1-0"));
+
+        // Once no one watches it, the finished game is let go.
+        send(&mut eve, Request::Unwatch { game });
+        send(&mut eve, Request::Games);
+        assert_eq!(receive(&mut eve), Response::Games { games: Vec::new() });
+    }
+
+    #[test]
+    fn clock() {
+        let (addr, time) = server();
+        let mut ann = connect(&addr, "Ann");
+        let mut bob = connect(&addr, "Bob");
+        send(&mut ann, Request::Seek { control: Some("1".to_string()), variant: None });
+        receive(&mut ann);
+        send(&mut bob, Request::Seek { control: Some("1".to_string()), variant: None });
+        let Response::Started { game, control, .. } = receive(&mut bob) else {
+            panic!("Expected the game to start");
+        };
+        assert_eq!(control.as_deref(), Some("1"));
+
+        assert!(matches!(receive(&mut ann), Response::Started { .. }));
+
+        // Black offers a draw that white turns down, then lets its second pass.
+        play(&mut ann, game, "d4");
+        assert!(matches!(receive(&mut ann), Response::Update { clock: Some([1000, 1000]), .. }));
+        send(&mut bob, Request::Draw { game });
+        assert_eq!(receive(&mut ann), Response::Offer { game, side: Side::Black });
+        send(&mut ann, Request::Decline { game });
+        assert_eq!(receive(&mut ann), Response::Declined { game, side: Side::White });
+        time.advance(Duration::from_millis(999));
+        play(&mut ann, game, "e4");
+        assert_eq!(receive(&mut ann), Response::Error { message: "It is not your turn".to_string() });
+        time.advance(Duration::from_millis(1));
+        assert_eq!(receive(&mut ann), Response::Ended { game, result: "1-0".to_string(), reason: "time".to_string() });
+        // No one watches the game, so it is let go as it ends.
+        play(&mut bob, game, "d5");
+        // After the move, the offer, its answer and the end.
+        assert_eq!((0..5).map(|_| receive(&mut bob)).last(), Some(Response::Error { message: format!("No game {game}") }));
+        send(&mut ann, Request::Games);
+        assert_eq!(receive(&mut ann), Response::Games { games: Vec::new() });
+    }
+
+    #[test]
+    fn disconnect() {
+        let (addr, ..) = server();
+        let mut ann = connect(&addr, "Ann");
+        let mut bob = connect(&addr, "Bob");
+        send(&mut ann, Request::Seek { control: None, variant: Some("crazyhouse".to_string()) });
+        receive(&mut ann);
+        send(&mut bob, Request::Seek { control: None, variant: Some("chess960".to_string()) });
+        assert!(matches!(receive(&mut bob), Response::Error { .. }));
+        send(&mut bob, Request::Seek { control: None, variant: Some("crazyhouse".to_string()) });
+        let Response::Started { game, .. } = receive(&mut ann) else {
+            panic!("Expected the game to start");
+        };
+        receive(&mut bob);
+        bob.close(None).unwrap();
+        while bob.read().is_ok() {}
+        assert_eq!(receive(&mut ann), Response::Ended { game, result: "1-0".to_string(), reason: "resignation".to_string() });
+    }
+}
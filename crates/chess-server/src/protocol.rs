@@ -0,0 +1,89 @@
+use serde::{Deserialize, Serialize};
+
+use chess_lib::{PieceUpdate, Side};
+
+/// A message from a client, as a JSON object whose "type" is the variant in snake case, e.g.
+/// `{"type":"move","game":1,"move":"e2e4"}`.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Request {
+    /// Sets the name the client plays under.
+    Hello { name: String },
+    /// Looks for an opponent who wants the same time control (e.g. "300+2", none for an untimed game) and
+    /// variant (standard if left out).
+    Seek {
+        #[serde(default)]
+        control: Option<String>,
+        #[serde(default)]
+        variant: Option<String>,
+    },
+    /// Stops looking for an opponent.
+    Cancel,
+    /// Plays a move in UCI ("e2e4"), SAN ("Nf3") or a drop ("N@e4").
+    Move {
+        game: u64,
+        #[serde(rename = "move")]
+        mov: String,
+    },
+    Resign { game: u64 },
+    /// Offers a draw, or accepts the one the opponent offered.
+    Draw { game: u64 },
+    Decline { game: u64 },
+    /// Follows the moves of a game without playing in it.
+    Watch { game: u64 },
+    Unwatch { game: u64 },
+    /// Lists the games being played.
+    Games,
+}
+
+/// A game in the list of games.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Summary {
+    pub game: u64,
+    pub white: String,
+    pub black: String,
+    pub variant: String,
+    pub control: Option<String>,
+    pub moves: usize,
+    pub result: String,
+}
+
+/// A message from the server, tagged like [`Request`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Response {
+    Welcome { client: u64 },
+    Seeking { control: Option<String>, variant: String },
+    /// A game the client was matched into, with the side it plays.
+    Started { game: u64, side: Side, white: String, black: String, variant: String, control: Option<String>, fen: String },
+    /// The whole of a game, sent on starting to watch it.
+    State {
+        game: u64,
+        white: String,
+        black: String,
+        variant: String,
+        fen: String,
+        /// The moves so far in SAN.
+        moves: Vec<String>,
+        /// Milliseconds left on white's and black's clocks.
+        clock: Option<[u64; 2]>,
+        result: String,
+    },
+    /// A move played in a game, with the squares it changed.
+    Update {
+        game: u64,
+        /// The number of moves played before it.
+        ply: usize,
+        san: String,
+        pieces: Vec<PieceUpdate>,
+        fen: String,
+        clock: Option<[u64; 2]>,
+    },
+    /// A draw offered by the side.
+    Offer { game: u64, side: Side },
+    Declined { game: u64, side: Side },
+    /// The end of a game, with its PGN result ("1-0") and how it ended ("checkmate", "time").
+    Ended { game: u64, result: String, reason: String },
+    Games { games: Vec<Summary> },
+    Error { message: String },
+}
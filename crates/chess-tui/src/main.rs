@@ -1,25 +1,139 @@
+pub mod net;
+pub mod puzzle;
 pub mod reader;
+pub mod save;
+
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
 
 use chess_lib::{MoveError, ParsePositions};
-use chess_lib::{BoardPiece, Side};
-use chess_lib::{Board, Pos};
+use chess_lib::{BoardPiece, Outcome, Piece, Side};
+use chess_lib::{Board, Clock, Game, GameManager, Pos, Setup, TimeControl};
+use chess_lib::{Engine, Judgement, Limits, Review, Search};
+use chess_lib::{Eco, Explorer, Pgn, Replay};
+use net::{Event, Message, NetError, Session, Sync};
+use puzzle::{Answer, Trainer};
 use reader::Reader;
 
+/// How many plies into each game the opening explorer goes.
+const EXPLORER_PLIES: usize = 40;
+
 fn main() {
-    let mut args = std::env::args();
-    args.next();
-    run(Reader::new(args));
+    let mut args = std::env::args().skip(1).collect::<Vec<_>>();
+    let mut game = Game::new(Board::default());
+    if let Some(index) = args.iter().position(|arg| arg == "--load") {
+        let Some(path) = args.get(index + 1).cloned() else {
+            eprintln!("Error: Expected a file after --load");
+            std::process::exit(1);
+        };
+        game = match save::load(Path::new(&path)) {
+            Ok(game) => game,
+            Err(err) => {
+                eprintln!("Error: Could not load {path}: {err}");
+                std::process::exit(1);
+            },
+        };
+        args.drain(index..=index + 1);
+    }
+    run_with(Reader::new(args.into_iter()), game);
 }
 
-pub fn run(mut io: Reader) -> Vec<MoveError> {
-    let mut board = Board::default();
+pub fn run(io: Reader) -> Vec<MoveError> {
+    run_with(io, Game::new(Board::default()))
+}
+
+/// Runs the engine, starting with `game`.
+pub fn run_with(mut io: Reader, game: Game) -> Vec<MoveError> {
+    let mut games = GameManager::new();
+    games.add(game);
     let mut errors = Vec::new();
     let mut input = String::new();
+    let mut setup: Option<Setup> = None;
+    let mut trainer: Option<Trainer> = None;
+    let mut explorer: Option<Explorer> = None;
+    let mut session: Option<Session> = None;
+    let mut viewer: Option<Replay> = None;
+    let eco = Eco::builtin();
     println!("Chess engine running... Type \"help\" for commands");
-    while io.read_line(&mut input) {
+    loop {
+        if let Some(online) = session.as_mut() {
+            let game = games.current_mut().expect("Could not get current game!");
+            if !self::wait(online, game, &mut io, &mut input) {
+                session = None;
+            }
+        }
+        if input.is_empty() && !io.read_line(&mut input) {
+            break;
+        }
+        if let Some(puzzles) = trainer.as_mut() {
+            match input.trim() {
+                "exit" => break,
+                "stop" => {
+                    trainer = None;
+                    println!("Stopped the puzzles");
+                },
+                line => self::puzzle(puzzles, line, &io, &mut errors),
+            }
+            input.clear();
+            continue;
+        }
+        if let Some(replay) = viewer.as_mut() {
+            match input.trim() {
+                "exit" => break,
+                "stop" => {
+                    viewer = None;
+                    println!("Stopped the replay");
+                },
+                line => self::view(replay, line),
+            }
+            input.clear();
+            continue;
+        }
+        if let Some(online) = session.as_mut() {
+            let game = games.current_mut().expect("Could not get current game!");
+            match input.trim() {
+                "exit" => {
+                    online.send(&Message::Bye).ok();
+                    break;
+                },
+                line => if !self::online(online, game, line, &io, &mut errors) {
+                    session = None;
+                },
+            }
+            input.clear();
+            continue;
+        }
+        if setup.is_none() && self::manage(&mut games, input.trim()) {
+            input.clear();
+            continue;
+        }
+        let game = games.current_mut().expect("Could not get current game!");
+        let board = &mut game.board;
+        if let Some(editor) = setup.as_mut() {
+            match input.trim() {
+                "exit" => break,
+                "done" => match editor.clone().build() {
+                    Ok(new) => {
+                        setup = None;
+                        self::replace(game, new);
+                    },
+                    Err(err) => println!("Invalid position with error {err}"),
+                },
+                "cancel" => {
+                    setup = None;
+                    println!("Stopped editing");
+                },
+                line => if self::edit(editor, line) && io.print() {
+                    self::print(editor.board());
+                },
+            }
+            input.clear();
+            continue;
+        }
         match input.trim() {
             "exit" => break,
-            "print" => self::print(&board),
+            "print" => self::print(board),
             "taken" => {
                 println!();
                 Side::sides().into_iter().for_each(|side| {
@@ -31,25 +145,200 @@ pub fn run(mut io: Reader) -> Vec<MoveError> {
                 });
             },
             "reset" => {
-                board.reset();
+                game.reset();
+            },
+            "clock" => match &board.clock {
+                Some(clock) => self::clock(clock),
+                None => println!("No clock set, use \"clock <control>\" (e.g. \"clock 300+2\")"),
+            },
+            line if line.starts_with("clock ") => match line["clock ".len()..].parse::<TimeControl>() {
+                Ok(control) => {
+                    println!("Clock set to {control}");
+                    board.clock = Some(Clock::new(control));
+                },
+                Err(err) => println!("Invalid time control \"{line}\" with error {err}"),
+            },
+            "fen" => println!("{}", board.fen()),
+            line if line.starts_with("fen ") => match Board::from_fen(&line["fen ".len()..]) {
+                Ok(new) => {
+                    self::replace(game, new);
+                },
+                Err(err) => println!("Invalid FEN \"{line}\" with error {err}"),
+            },
+            line if line.starts_with("chess960") => {
+                let index = match line["chess960".len()..].trim() {
+                    "" => Ok(std::time::SystemTime::now().duration_since(std::time::UNIX_EPOCH).map(|time| time.subsec_nanos()).unwrap_or_default() as u16 % 960),
+                    index => index.parse::<u16>(),
+                };
+                match index.ok().and_then(|index| Board::chess960(index).map(|new| (index, new))) {
+                    Some((index, new)) => {
+                        println!("Chess960 position {index}");
+                        self::replace(game, new);
+                    },
+                    None => println!("Invalid Chess960 position \"{line}\", expected a number from 0 to 959"),
+                }
+            },
+            line if line.starts_with("save ") => match save::save(game, Path::new(line["save ".len()..].trim())) {
+                Ok(()) => println!("Saved game to {}", line["save ".len()..].trim()),
+                Err(err) => println!("Could not save game to {} with error {err}", line["save ".len()..].trim()),
+            },
+            line if line.starts_with("load ") => match save::load(Path::new(line["load ".len()..].trim())) {
+                Ok(loaded) => {
+                    *game = loaded;
+                    self::print(&game.board);
+                },
+                Err(err) => println!("Could not load game from {} with error {err}", line["load ".len()..].trim()),
+            },
+            line if line == "analyze" || line.starts_with("analyze ") => self::analyze(board, &line["analyze".len()..], &mut io),
+            line if line == "review" || line.starts_with("review ") => self::review(game, &line["review".len()..]),
+            line if line.starts_with("puzzles ") => match puzzle::load(Path::new(line["puzzles ".len()..].trim())) {
+                Ok((puzzles, skipped)) => {
+                    println!("Loaded {} puzzles, skipping {} that could not be read, type \"help\" for puzzle commands", puzzles.len(), skipped.len());
+                    if let Some(err) = skipped.first() {
+                        println!("The first was skipped with error: {err}");
+                    }
+                    let mut puzzles = Trainer::new(puzzles);
+                    self::next(&mut puzzles);
+                    trainer = Some(puzzles);
+                },
+                Err(err) => println!("Could not load puzzles from {} with error {err}", line["puzzles ".len()..].trim()),
+            },
+            "replay" => match game.replay() {
+                Ok(replay) => {
+                    println!("Replaying {} moves, type \"help\" for replay commands", replay.len());
+                    self::step(&replay);
+                    viewer = Some(replay);
+                },
+                Err(err) => {
+                    errors.push(err);
+                    println!("Could not replay the game: {err}");
+                },
+            },
+            "explore" => match &explorer {
+                Some(explorer) => self::explore(explorer, board),
+                None => println!("No games to explore, type \"explore\" and a PGN file of them"),
+            },
+            line if line.starts_with("explore ") => {
+                let path = line["explore ".len()..].trim();
+                match std::fs::read_to_string(path) {
+                    Ok(text) => {
+                        let mut games = Explorer::new(EXPLORER_PLIES);
+                        let skipped = Pgn::split(&text).into_iter()
+                            .filter(|game| game.parse::<Pgn>().and_then(|pgn| pgn.game()).map(|game| games.add(&game)).is_err())
+                            .count();
+                        println!("Indexed {} finished games from {path}, skipping {skipped} that could not be read", games.games());
+                        self::explore(&games, board);
+                        explorer = Some(games);
+                    },
+                    Err(err) => println!("Could not read games from {path} with error {err}"),
+                }
+            },
+            "edit" => {
+                println!("Editing position, type \"help\" for commands");
+                setup = Some(Setup::from(board.clone()));
+            },
+            "variants" => {
+                print!("Variants:");
+                for rules in chess_lib::variants() {
+                    print!(" {}", rules.name());
+                }
+                println!();
+            },
+            line if line.starts_with("variant ") => match chess_lib::variant(line["variant ".len()..].trim()) {
+                Some(rules) => {
+                    println!("Playing {}", rules.name());
+                    self::replace(game, Board::new(rules));
+                },
+                None => println!("Unknown variant \"{line}\", type \"variants\" to list them"),
+            },
+            line if line.starts_with("host ") => {
+                // A port alone is listened on from every interface, while an address keeps to its own.
+                let addr = |addr: &str| match addr.parse::<u16>() {
+                    Ok(port) => Some(format!("0.0.0.0:{port}")),
+                    Err(..) => addr.contains(':').then(|| addr.to_string()),
+                };
+                let host = match *line["host ".len()..].split_whitespace().collect::<Vec<_>>() {
+                    [port] | [port, "white"] => addr(port).map(|addr| (addr, Side::White)),
+                    [port, "black"] => addr(port).map(|addr| (addr, Side::Black)),
+                    _ => None,
+                };
+                match host {
+                    Some((addr, side)) => {
+                        println!("Hosting on {addr} as {side:?}, waiting for an opponent to join");
+                        match Session::host(&addr, side, game) {
+                            Ok((online, sync)) => {
+                                println!("{:?} joined, type \"help\" for commands", side.other());
+                                self::synced(sync, game);
+                                session = Some(online);
+                            },
+                            Err(err) => println!("Could not host on {addr} with error {err}"),
+                        }
+                    },
+                    None => println!("Invalid command \"{line}\", expected a port or address and optionally a side (e.g. \"host 7878 black\")"),
+                }
+            },
+            line if line.starts_with("join ") => {
+                let addr = line["join ".len()..].trim();
+                println!("Joining {addr}");
+                match Session::join(addr, game) {
+                    Ok((online, sync)) => {
+                        println!("Joined as {:?}, type \"help\" for commands", online.side);
+                        self::synced(sync, game);
+                        session = Some(online);
+                    },
+                    Err(err) => println!("Could not join {addr} with error {err}"),
+                }
+            },
+            line if line.contains('@') => {
+                match self::parse_drop(line) {
+                    Some((piece, pos)) => match board.drop_piece(piece, pos) {
+                        Ok(()) => if io.print() {
+                            self::print(board);
+                        },
+                        Err(err) => {
+                            errors.push(err);
+                            println!("Could not drop {piece:?} on {pos}: {err}");
+                        },
+                    },
+                    None => println!("Invalid drop command \"{line}\", expected a piece letter and a square (e.g. \"N@e4\")"),
+                }
             },
             "help" => {
-                println!("Commands: exit, print, taken, reset, help");
+                println!("Commands: exit, print, taken, reset, clock, fen, chess960, edit, variants, variant, new, switch, list, save, load, analyze, review, replay, puzzles, explore, host, join, help");
+                println!("To play several games at once, type \"new\" to start another, \"list\" to see them and \"switch\" and a number to go to one");
+                println!("To set up a position, type \"edit\" and then \"help\" for editing commands");
+                println!("To play a variant, type \"variant\" and its name (e.g. \"variant atomic\")");
+                println!("To drop a piece from your hand in crazyhouse, type its letter, @ and the square (e.g. \"N@e4\")");
+                println!("To load a position, type \"fen\" and the position in (X-/Shredder-)FEN, or \"chess960\" and a start position number (random if left out)");
+                println!("To save or load the game, type \"save\" or \"load\" and a file, in PGN if it ends in \".pgn\" and JSON otherwise");
+                println!("To see the best lines, type \"analyze\", optionally the number of lines and a limit (e.g. \"analyze 3 depth 4\", \"analyze time 5\"), without which it goes on until Enter is pressed");
+                println!("To have the engine go over the game, type \"review\", optionally a depth and a file to save it to annotated (e.g. \"review depth 3 annotated.pgn\")");
+                println!("To go back over the game move by move without changing it, type \"replay\" and then \"help\" for replay commands");
+                println!("To solve puzzles, type \"puzzles\" and a CSV file of them laid out as the lichess puzzle database");
+                println!("To see the moves played from the position in your games, type \"explore\" and a PGN file of them, then \"explore\" in later positions");
+                println!("To play the game over the network, type \"host\", a port or address to listen on and optionally your side (e.g. \"host 7878 black\" or \"host 127.0.0.1:7878\"), and on the other side \"join\" and the address (e.g. \"join 192.168.1.2:7878\")");
+                println!("To set a clock, type \"clock\" and a PGN time control in seconds (e.g. \"clock 40/5400+30:1800+30\", \"clock 300d5\")");
                 println!("To see the status of a piece, type its position (e.g. \"a1\")");
-                println!("To move a piece, type the move (e.g. \"e2 e4\")");
+                println!("To move a piece, type the move (e.g. \"e2 e4\"), castle by moving the king onto the rook or two squares towards it, and promote to a piece other than a queen by adding \"=\" and its letter (e.g. \"a7 a8=N\")");
             },
             line => {
                 match ParsePositions::parse(line) {
-                    ParsePositions::Move(mov) => match board.move_piece(mov) {
-                        Ok(()) => {
+                    ParsePositions::Move(mov) => match (board.see(mov), board.move_piece(mov)) {
+                        (see, Ok(())) => {
                             // println!("{:?} moved {:?} to {}", piece.side, piece.kind, piece.position);
                             if io.print() {
-                                self::print(&board);
+                                self::print(board);
                             }                        
+                            if see < 0 {
+                                println!("Warning: {mov} hangs material, losing {} centipawns in the exchange", -see);
+                            }
+                            if let Some(opening) = eco.classify_game(game) {
+                                println!("Opening: {} {}", opening.eco, opening.name);
+                            }
                         },
-                        Err(err) => {
+                        (.., Err(err)) => {
                             errors.push(err);
-                            println!("Could not perform move {mov} with error {err:?}");
+                            println!("Could not perform move {mov}: {err}");
                         },
                     },
                     ParsePositions::Pos(pos) => {
@@ -57,9 +346,9 @@ pub fn run(mut io: Reader) -> Vec<MoveError> {
                             Some(piece) => {
                                 println!("{:?} at {}", piece.kind, pos);
 
-                                let moves = piece.moves(&board, pos).collect::<Vec<_>>();
+                                let moves = piece.moves(board, pos).collect::<Vec<_>>();
 
-                                let mut targets = piece.targets(&board, pos).filter(|target| !moves.contains(target));
+                                let mut targets = piece.targets(board, pos).filter(|target| !moves.contains(target));
 
                                 match targets.next() {
                                     Some(first) => {
@@ -72,7 +361,7 @@ pub fn run(mut io: Reader) -> Vec<MoveError> {
                                     None => println!("No targets"),
                                 }
 
-                                match moves.get(0) {
+                                match moves.first() {
                                     Some(first) => {
                                         print!("Moves: {first}");
                                         for mov in &moves[1..] {
@@ -82,6 +371,14 @@ pub fn run(mut io: Reader) -> Vec<MoveError> {
                                     },
                                     None => println!("No moves"),
                                 }
+
+                                let attackers = board.attackers(pos, piece.side.other()).map(|pos| pos.to_string()).collect::<Vec<_>>();
+                                if !attackers.is_empty() {
+                                    println!("Attacked by: {}", attackers.join(", "));
+                                }
+                                if let Some(pin) = board.pins(piece.side).into_iter().find(|pin| pin.pinned == pos) {
+                                    println!("Pinned by {}", pin.attacker);
+                                }
                             },
                             None => println!("No piece at {}", pos),
                         }
@@ -95,36 +392,650 @@ pub fn run(mut io: Reader) -> Vec<MoveError> {
     errors
 }
 
+/// Reads a drop from the hand, written as the piece letter (none for a pawn), "@" and the square (e.g. "N@e4").
+fn parse_drop(line: &str) -> Option<(Piece, Pos)> {
+    line.split_once('@').and_then(|(piece, pos)| {
+        let mut chars = piece.chars();
+        let piece = match (chars.next(), chars.next()) {
+            (None, ..) => Some(Piece::Pawn),
+            (Some(letter), None) => Piece::from_letter(letter),
+            _ => None,
+        };
+        piece.zip(pos.parse::<Pos>().ok())
+    })
+}
+
+/// Handles a command while playing over the network, returning whether the session goes on.
+fn online(session: &mut Session, game: &mut Game, line: &str, io: &Reader, errors: &mut Vec<MoveError>) -> bool {
+    let sent = match line {
+        "print" => {
+            self::print(&game.board);
+            return true;
+        },
+        "fen" => {
+            println!("{}", game.board.fen());
+            return true;
+        },
+        "help" => {
+            println!("Network game commands: resign, draw, accept, decline, print, fen, leave, exit");
+            println!("To move, type the move (e.g. \"e2 e4\"), the move in SAN (e.g. \"Nf3\") or a drop (e.g. \"N@e4\")");
+            println!("To offer a draw, type \"draw\", and the offer stands until your opponent moves");
+            println!("You can resign, offer or answer a draw and leave while your opponent is thinking too");
+            println!("To stop playing, keeping the game to carry on later with \"host\" or \"join\", type \"leave\"");
+            return true;
+        },
+        "leave" => {
+            session.send(&Message::Bye).ok();
+            println!("Left the game, type \"host\" or \"join\" to carry it on later");
+            return false;
+        },
+        "resign" => match game.resign(session.side) {
+            Ok(()) => session.send(&Message::Resign),
+            Err(err) => {
+                println!("Could not resign: {err}");
+                return true;
+            },
+        },
+        "draw" | "accept" if session.offered => match game.draw() {
+            Ok(()) => session.send(&Message::Accept),
+            Err(err) => {
+                println!("Could not agree to a draw: {err}");
+                return true;
+            },
+        },
+        "draw" => {
+            session.offering = true;
+            println!("Offered a draw, which stands until {:?} moves", session.side.other());
+            session.send(&Message::Draw)
+        },
+        "decline" if session.offered => {
+            session.offered = false;
+            println!("Declined the draw");
+            session.send(&Message::Decline)
+        },
+        "accept" | "decline" => {
+            println!("No draw has been offered");
+            return true;
+        },
+        _ if game.board.state.turn.side != session.side => {
+            println!("Waiting for {:?} to move, type \"help\" for what can be done meanwhile", session.side.other());
+            return true;
+        },
+        line => {
+            let played = match (ParsePositions::parse(line), self::parse_drop(line)) {
+                (.., Some((piece, pos))) => game.board.drop_piece(piece, pos),
+                (ParsePositions::Move(mov), ..) => game.board.move_piece(mov),
+                _ => match game.board.parse_san(line) {
+                    Ok(mov) => game.board.move_piece(mov),
+                    Err(err) => {
+                        println!("Invalid move \"{line}\" with error {err}, type \"help\" for commands");
+                        return true;
+                    },
+                },
+            };
+            if let Err(err) = played {
+                errors.push(err);
+                println!("Could not perform move {line}: {err}");
+                return true;
+            }
+            if io.print() {
+                self::print(&game.board);
+            }
+            session.send_move(game)
+        },
+    };
+    match sent {
+        Ok(()) => true,
+        Err(NetError::Io(..)) => self::reconnect(session, game),
+        Err(err) => {
+            println!("{err}");
+            true
+        },
+    }
+}
+
+/// Waits for the next command, printing what the opponent does meanwhile, and leaves it in `input`. Commands from a
+/// file are only read on this side's turn. The session ends when the game is over or the opponent leaves, and this
+/// returns whether it goes on.
+fn wait(session: &mut Session, game: &mut Game, io: &mut Reader, input: &mut String) -> bool {
+    /// How long the opponent is waited for before checking for a command again.
+    const POLL: Duration = Duration::from_millis(100);
+
+    let opponent = session.side.other();
+    if io.print() && game.outcome().is_none() && game.board.state.turn.side == opponent {
+        println!("Waiting for {opponent:?} to move");
+    }
+    while game.outcome().is_none() && (io.print() || game.board.state.turn.side == opponent) {
+        if io.poll(input) {
+            return true;
+        }
+        match session.receive(game, POLL) {
+            Ok(None) => {},
+            Ok(Some(Event::Moved(san))) => {
+                println!("{opponent:?} played {san}");
+                if io.print() {
+                    self::print(&game.board);
+                }
+            },
+            Ok(Some(Event::Offered)) => println!("{opponent:?} offers a draw, type \"accept\" or \"decline\", or move to decline"),
+            Ok(Some(Event::Resigned)) => println!("{opponent:?} resigned"),
+            Ok(Some(Event::Accepted)) => println!("{opponent:?} accepted the draw"),
+            Ok(Some(Event::Declined)) => println!("{opponent:?} declined the draw"),
+            Ok(Some(Event::Synced(sync))) => self::synced(sync, game),
+            Ok(Some(Event::Left)) => {
+                println!("{opponent:?} left the game, type \"host\" or \"join\" to carry it on later");
+                return false;
+            },
+            Ok(Some(Event::Lost)) | Err(NetError::Io(..)) => if !self::reconnect(session, game) {
+                return false;
+            },
+            Err(err) => println!("{err}"),
+        }
+    }
+    if game.outcome().is_some() {
+        session.send(&Message::Bye).ok();
+        println!("The game is over with result {}", game.result());
+        self::print(&game.board);
+        return false;
+    }
+    true
+}
+
+/// Makes the connection again after it was lost, returning whether it was.
+fn reconnect(session: &mut Session, game: &mut Game) -> bool {
+    println!("Lost the connection, trying for {} seconds to get it back", net::RECONNECT.as_secs());
+    match session.reconnect(game) {
+        Ok(sync) => {
+            self::synced(sync, game);
+            true
+        },
+        Err(err) => {
+            println!("Could not reconnect with error {err}, type \"host\" or \"join\" to carry on the game later");
+            false
+        },
+    }
+}
+
+/// Prints how the games of the two sides were settled.
+fn synced(sync: Sync, game: &Game) {
+    match sync {
+        Sync::Same => println!("Both sides have the game after {} moves", game.moves().len()),
+        Sync::Kept => println!("The opponent has taken this game after {} moves", game.moves().len()),
+        Sync::Taken => {
+            println!("Took the opponent's game after {} moves", game.moves().len());
+            self::print(&game.board);
+        },
+    }
+}
+
+/// Prints the moves played from the position in the explorer's games and how those games ended.
+fn explore(explorer: &Explorer, board: &Board) {
+    let moves = explorer.moves(board);
+    if moves.is_empty() {
+        return println!("No games with this position");
+    }
+    println!("{:<8}{:>6}{:>7}{:>7}{:>7}", "Move", "Games", "White", "Draw", "Black");
+    for continuation in moves {
+        let [white, draws, black] = continuation.tally.percentages();
+        println!("{:<8}{:>6}{:>6.0}%{:>6.0}%{:>6.0}%", continuation.san, continuation.tally.games(), white, draws, black);
+    }
+}
+
+/// Handles a command while solving puzzles: a move, or asking about a square, as in a game.
+fn puzzle(trainer: &mut Trainer, line: &str, io: &Reader, errors: &mut Vec<MoveError>) {
+    match line {
+        "next" | "skip" => self::next(trainer),
+        "print" => self::print(&trainer.board),
+        "fen" => println!("{}", trainer.board.fen()),
+        "hint" => match trainer.hint() {
+            Some(pos) => println!("Move the {:?} on {pos}", trainer.board.pieces.at(&pos).map(|piece| piece.kind).unwrap_or(Piece::Pawn)),
+            None => println!("The puzzle is over, type \"next\" for another"),
+        },
+        "solution" => {
+            let mut board = trainer.board.clone();
+            let moves = trainer.give_up().into_iter().map_while(|mov| {
+                let san = board.san(mov);
+                board.move_piece(mov).ok().map(|()| san)
+            }).collect::<Vec<_>>();
+            match moves.is_empty() {
+                true => println!("The puzzle is over, type \"next\" for another"),
+                false => println!("The solution was {}, type \"next\" for another puzzle", moves.join(" ")),
+            }
+        },
+        "stats" => println!("Rating {:.0}, streak {} (best {}), solved {} of {}", trainer.rating, trainer.streak, trainer.best, trainer.solved, trainer.attempted),
+        "help" => {
+            println!("Puzzle commands: next, hint, solution, stats, print, fen, stop, exit");
+            println!("To answer, type the move (e.g. \"e2 e4\") or the move in SAN (e.g. \"Qxf7#\")");
+            println!("To see the status of a piece, type its position (e.g. \"a1\")");
+            println!("To skip the puzzle, counting it as failed, type \"next\", and to go back to the game type \"stop\"");
+        },
+        line => {
+            let mov = match ParsePositions::parse(line) {
+                ParsePositions::Move(mov) => mov,
+                ParsePositions::Pos(pos) => return match trainer.board.pieces.at(&pos) {
+                    Some(piece) => println!("{:?} {:?} at {pos}, moves: {}", piece.side, piece.kind, piece.moves(&trainer.board, pos).map(|pos| pos.to_string()).collect::<Vec<_>>().join(", ")),
+                    None => println!("No piece at {pos}"),
+                },
+                ParsePositions::Error(err) => match trainer.board.parse_san(line) {
+                    Ok(mov) => mov,
+                    Err(..) => return println!("Invalid move \"{line}\" with error {err}"),
+                },
+            };
+            let san = trainer.board.san(mov);
+            let before = trainer.board.clone();
+            match trainer.play(mov) {
+                Ok(Answer::Reply(reply)) => {
+                    let mut board = before;
+                    let _ = board.move_piece(mov);
+                    println!("{san} is right, the reply is {}", board.san(reply));
+                    if io.print() {
+                        self::print(&trainer.board);
+                    }
+                },
+                Ok(Answer::Solved) => {
+                    println!("{san} is right, puzzle solved! Rating {:.0}, streak {}", trainer.rating, trainer.streak);
+                    self::next(trainer);
+                },
+                Ok(Answer::Wrong(expected)) => println!("{san} is wrong, the move was {}. Rating {:.0}, type \"next\" for another puzzle", before.san(expected), trainer.rating),
+                Err(err) => {
+                    errors.push(err);
+                    println!("Could not perform move {mov}: {err}");
+                },
+            }
+        },
+    }
+}
+
+/// Sets the next puzzle, printing its position.
+fn next(trainer: &mut Trainer) {
+    let Some((id, rating, first)) = trainer.next_puzzle().map(|puzzle| (puzzle.id.clone(), puzzle.rating, puzzle.start.san(puzzle.moves[0]))) else {
+        return println!("No puzzles left. Rating {:.0}, solved {} of {}, best streak {}; type \"stop\" to go back to the game", trainer.rating, trainer.solved, trainer.attempted, trainer.best);
+    };
+    println!("Puzzle {id} (rated {rating}), {:?} to play after {first}", trainer.board.state.turn.side);
+    self::print(&trainer.board);
+}
+
+/// Handles a command while replaying a game: stepping through its positions or asking about them.
+fn view(replay: &mut Replay, line: &str) {
+    let moved = match line {
+        "first" => {
+            replay.first();
+            true
+        },
+        "prev" | "back" => replay.back().is_some(),
+        "next" | "forward" => replay.forward().is_some(),
+        "last" => {
+            replay.last();
+            true
+        },
+        "print" => return self::print(replay.board()),
+        "fen" => return println!("{}", replay.board().fen()),
+        "help" => {
+            println!("Replay commands: first, prev, next, last, jump, print, fen, stop, exit");
+            println!("To go to the position after a move, type \"jump\" and its number, with \"...\" for black's move (e.g. \"jump 12\", \"jump 12...\")");
+            println!("To see the status of a piece, type its position (e.g. \"a1\")");
+            return println!("To go back to the game, which is left as it was, type \"stop\"");
+        },
+        line if line.starts_with("jump ") => {
+            let arg = line["jump ".len()..].trim();
+            let (number, side) = match arg.strip_suffix("...") {
+                Some(number) => (number, Side::Black),
+                None => (arg.strip_suffix('.').unwrap_or(arg), Side::White),
+            };
+            match number.parse().ok().and_then(|number| replay.ply_of(number, side)) {
+                Some(ply) => {
+                    replay.jump(ply);
+                    true
+                },
+                None => return println!("No move \"{arg}\" in the game, expected a move number (e.g. \"jump 12\", \"jump 12...\")"),
+            }
+        },
+        line => return match ParsePositions::parse(line) {
+            ParsePositions::Pos(pos) => match replay.board().pieces.at(&pos) {
+                Some(piece) => println!("{:?} {:?} at {pos}, moves: {}", piece.side, piece.kind, piece.moves(replay.board(), pos).map(|pos| pos.to_string()).collect::<Vec<_>>().join(", ")),
+                None => println!("No piece at {pos}"),
+            },
+            _ => println!("Unknown replay command \"{line}\", type \"help\" for replay commands"),
+        },
+    };
+    match moved {
+        true => self::step(replay),
+        false => println!("Already at the {} of the game", if replay.ply() == 0 { "start" } else { "end" }),
+    }
+}
+
+/// Prints the position being replayed, with the move that led to it.
+fn step(replay: &Replay) {
+    match replay.last_move() {
+        Some((before, previous)) => {
+            let san = match previous.dropped {
+                Some(kind) => before.san_drop(kind, previous.mov.to),
+                None => before.san(previous.mov),
+            };
+            let dots = if before.state.turn.side == Side::White { "." } else { "..." };
+            println!("Ply {} of {}, after {}{dots} {san}", replay.ply(), replay.len(), before.state.turn.no + 1);
+        },
+        None => println!("Ply 0 of {}, the start of the game", replay.len()),
+    }
+    self::print(replay.board());
+}
+
+/// Applies an editing command to the position being set up, returning whether it changed.
+fn edit(setup: &mut Setup, line: &str) -> bool {
+    let (command, arg) = line.split_once(' ').unwrap_or((line, ""));
+    match (command, arg.trim()) {
+        ("print", "") => self::print(setup.board()),
+        ("fen", "") => println!("{}", setup.board().fen()),
+        ("clear", "") => {
+            setup.clear();
+            return true;
+        },
+        ("turn", "w" | "white") => {
+            setup.turn(Side::White);
+            return true;
+        },
+        ("turn", "b" | "black") => {
+            setup.turn(Side::Black);
+            return true;
+        },
+        ("castling", castling) => match setup.castling_fen(castling) {
+            Ok(..) => return true,
+            Err(err) => println!("Invalid castling rights \"{castling}\" with error {err}"),
+        },
+        ("ep", "-") => {
+            setup.en_passant(None);
+            return true;
+        },
+        ("ep", pos) => match pos.parse::<Pos>() {
+            Ok(pos) => {
+                setup.en_passant(Some(pos));
+                return true;
+            },
+            Err(err) => println!("Invalid en passant square \"{pos}\" with error {err}"),
+        },
+        ("x", pos) => match pos.parse::<Pos>() {
+            Ok(pos) => {
+                setup.remove(pos);
+                return true;
+            },
+            Err(err) => println!("Invalid square \"{pos}\" with error {err}"),
+        },
+        ("help", "") => {
+            println!("Editing commands: print, fen, clear, turn, castling, ep, x, done, cancel");
+            println!("To place a piece, type its FEN letter (upper case for white) and the square (e.g. \"Ke1\", \"pe7\")");
+            println!("To remove a piece, type \"x\" and the square (e.g. \"x e4\")");
+            println!("To set the side to move, castling rights or en passant square, type \"turn w\", \"castling KQkq\" or \"ep e3\" (\"-\" for none)");
+            println!("To play from the position, type \"done\", or \"cancel\" to go back to the game");
+        },
+        (placement, "") => {
+            let mut chars = placement.chars();
+            let piece = chars.next().and_then(|letter| setup.board().rules.piece(letter).map(|kind| BoardPiece {
+                kind,
+                side: if letter.is_ascii_uppercase() { Side::White } else { Side::Black },
+            }));
+            match piece.zip(chars.as_str().parse::<Pos>().ok()) {
+                Some((piece, pos)) => {
+                    setup.place(pos, piece);
+                    return true;
+                },
+                None => println!("Invalid editing command \"{line}\", type \"help\" for commands"),
+            }
+        },
+        _ => println!("Invalid editing command \"{line}\", type \"help\" for commands"),
+    }
+    false
+}
+
+/// Analyses the position for "analyze [lines] [depth <n> | nodes <n> | time <seconds>]", printing the best lines
+/// as each depth is finished. Without a limit it goes on until Enter is pressed, which needs input from the terminal.
+fn analyze(board: &Board, args: &str, io: &mut Reader) {
+    let mut words = args.split_whitespace().peekable();
+    let lines = match words.peek().and_then(|word| word.parse::<usize>().ok()) {
+        Some(lines) => {
+            words.next();
+            lines
+        },
+        None => 3,
+    };
+    let mut limits = Limits::default();
+    let limit = match (words.next(), words.next(), words.next()) {
+        (None, ..) => Ok(()),
+        (Some("depth"), Some(depth), None) => depth.parse().map(|depth| limits.depth = Some(depth)).map_err(drop),
+        (Some("nodes"), Some(nodes), None) => nodes.parse().map(|nodes| limits.nodes = Some(nodes)).map_err(drop),
+        (Some("time"), Some(time), None) => time.parse().ok().and_then(|time| Duration::try_from_secs_f64(time).ok()).map(|time| limits.time = Some(time)).ok_or(()),
+        _ => Err(()),
+    };
+    if limit.is_err() || lines == 0 {
+        println!("Invalid analysis \"analyze{args}\", expected a number of lines and a limit (e.g. \"analyze 3 depth 4\", \"analyze time 5\")");
+        return;
+    }
+    if board.state.outcome().is_some() {
+        println!("Nothing to analyse, the game is over");
+        return;
+    }
+    let engine = Engine::new();
+    if limits != Limits::default() {
+        engine.analyze(board, limits, lines, &AtomicBool::new(false), |searches| self::lines(board, searches));
+        return;
+    }
+    if !io.print() {
+        println!("Analysis from a file needs a limit (e.g. \"analyze 3 depth 4\")");
+        return;
+    }
+    println!("Analysing, press Enter to stop");
+    let stop = AtomicBool::new(false);
+    std::thread::scope(|scope| {
+        scope.spawn(|| engine.analyze(board, limits, lines, &stop, |searches| self::lines(board, searches)));
+        io.read_line(&mut String::new());
+        stop.store(true, Ordering::Relaxed);
+    });
+}
+
+/// Reviews the game for "review [depth <n>] [<file>]", printing the moves that were not good and how accurately
+/// each side played, and saving the game annotated in PGN to the file if there is one.
+fn review(game: &Game, args: &str) {
+    let mut words = args.split_whitespace();
+    let mut limits = Limits { depth: Some(3), ..Default::default() };
+    let mut path = None;
+    while let Some(word) = words.next() {
+        match (word, words.clone().next().map(str::parse::<u32>)) {
+            ("depth", Some(Ok(depth))) => {
+                words.next();
+                limits.depth = Some(depth);
+            },
+            (word, ..) if path.is_none() && word != "depth" => path = Some(word),
+            _ => {
+                println!("Invalid review \"review{args}\", expected a depth and a file (e.g. \"review depth 3 annotated.pgn\")");
+                return;
+            },
+        }
+    }
+    let review = match Review::new(&Engine::new(), game, limits) {
+        Ok(review) => review,
+        Err(err) => return println!("Could not replay the game with error {err}"),
+    };
+    let mut board = game.start().clone();
+    for (previous, reviewed) in game.moves().iter().zip(&review.moves) {
+        let san = match previous.dropped {
+            Some(kind) => board.san_drop(kind, previous.mov.to),
+            None => board.san(previous.mov),
+        };
+        let number = match reviewed.side {
+            Side::White => format!("{}.", board.state.turn.no + 1),
+            Side::Black => format!("{}...", board.state.turn.no + 1),
+        };
+        if reviewed.judgement >= Judgement::Inaccuracy {
+            let best = reviewed.best.first().map(|mov| board.san(*mov)).unwrap_or_default();
+            println!("{number} {san} is a {}, losing {} centipawns ({} to {}), best was {best}", reviewed.judgement, reviewed.loss, reviewed.before, reviewed.after);
+        }
+        let played = match previous.dropped {
+            Some(kind) => board.drop_piece(kind, previous.mov.to),
+            None => board.move_piece(previous.mov),
+        };
+        if played.is_err() {
+            break;
+        }
+    }
+    for side in Side::sides() {
+        let judgements = [Judgement::Inaccuracy, Judgement::Mistake, Judgement::Blunder]
+            .map(|judgement| review.moves.iter().filter(|reviewed| reviewed.side == side && reviewed.judgement == judgement).count());
+        match review.accuracy(side) {
+            Some(accuracy) => println!("{side:?}: {accuracy:.1}% accuracy, {} inaccuracies, {} mistakes, {} blunders", judgements[0], judgements[1], judgements[2]),
+            None => println!("{side:?}: no moves"),
+        }
+    }
+    if let Some(path) = path {
+        match review.pgn(game).map(|pgn| std::fs::write(path, pgn.to_string())) {
+            Ok(Ok(())) => println!("Saved the annotated game to {path}"),
+            Ok(Err(err)) => println!("Could not save the annotated game to {path} with error {err}"),
+            Err(err) => println!("Could not write the game in PGN with error {err}"),
+        }
+    }
+}
+
+/// Prints the lines of an analysis in SAN, with their scores for the side to move.
+fn lines(board: &Board, searches: &[Search]) {
+    let Some(first) = searches.first() else {
+        return;
+    };
+    println!("Depth {} ({} nodes):", first.depth, first.nodes);
+    for (i, search) in searches.iter().enumerate() {
+        let mut line = board.clone();
+        let moves = search.pv.iter().map_while(|mov| {
+            let san = line.san(*mov);
+            line.move_piece(*mov).ok().map(|()| san)
+        }).collect::<Vec<_>>();
+        println!("{}. {}: {}", i + 1, search.score, moves.join(" "));
+    }
+}
+
+/// Starts the game over from `new`, keeping its time control.
+fn replace(game: &mut Game, mut new: Board) {
+    new.clock = game.board.clock.take();
+    if let Some(clock) = new.clock.as_mut() {
+        clock.reset();
+    }
+    game.restart(new);
+    self::print(&game.board);
+}
+
+/// Handles the commands for playing several games at once, returning whether the line was one.
+fn manage(games: &mut GameManager, line: &str) -> bool {
+    match line {
+        "new" => {
+            let id = games.add(Game::new(Board::default()));
+            println!("Started game {id}");
+        },
+        "list" => for (id, game) in games.iter() {
+            let current = if games.current_id() == Some(id) { "*" } else { " " };
+            let players = match (game.white.as_str(), game.black.as_str()) {
+                ("", "") => String::new(),
+                (white, black) => format!(" {white} vs {black},"),
+            };
+            println!("{current}{id}:{players} {} after {} moves, {}", game.board.rules.name(), game.moves().len(), game.result());
+        },
+        line if line.starts_with("switch") => match line["switch".len()..].trim().parse().ok().and_then(|id| games.switch(id)) {
+            Some(game) => self::print(&game.board),
+            None => println!("No game \"{}\", type \"list\" to see the games", line["switch".len()..].trim()),
+        },
+        _ => return false,
+    }
+    true
+}
+
 pub fn print(board: &Board) {
     println!();
-    if let Some(moves) = board.state.check() {
-        match moves.is_empty() {
-            true => println!("{:?} is in checkmate!", board.state.turn.side),
-            false => println!("{:?} is in check with available moves {moves:?}", board.state.turn.side),
-        }
+    match board.state.outcome() {
+        Some(Outcome::Win(.., "checkmate")) => println!("{:?} is in checkmate!", board.state.turn.side),
+        Some(Outcome::Win(side, "time")) => println!("{:?} has run out of time!", side.other()),
+        Some(Outcome::Win(side, reason)) => println!("{side:?} wins by {reason}!"),
+        Some(Outcome::Draw(reason)) => println!("Draw by {reason}!"),
+        None => if let Some(moves) = board.state.check() {
+            println!("{:?} is in check with available moves {moves:?}", board.state.turn.side);
+        },
+    }
+    if board.rules.drops() {
+        Side::sides().into_iter().for_each(|side| {
+            print!("{:?} hand: ", side);
+            for kind in &board.state.hands[side as usize] {
+                print!("{} ", kind.symbol(side));
+            }
+            println!();
+        });
+    }
+    if let Some(checks) = board.rules.checks() {
+        println!("Checks: White {}/{checks}, Black {}/{checks}", board.state.checks[0], board.state.checks[1]);
     }
-    for y in (0..8).rev() {
-        print!("{} ", y + 1);
-        (0..8).for_each(|x| print!("{} ", board.pieces.at(&Pos { x, y }).map(BoardPiece::symbol).unwrap_or('_')));
+    if let Some(clock) = &board.clock {
+        self::clock(clock);
+    }
+    let size = board.size();
+    let margin = size.height.to_string().len();
+    for y in (0..size.height).rev() {
+        print!("{:<margin$} ", y + 1);
+        (0..size.width).for_each(|x| print!("{} ", board.pieces.at(&Pos { x, y }).map(BoardPiece::symbol).unwrap_or('_')));
         println!("| ");
     }
-    println!("# a b c d e f g h |");
+    print!("{:<margin$} ", '#');
+    (0..size.width).for_each(|x| print!("{} ", (b'a' + x as u8) as char));
+    println!("|");
+
+}
 
+pub fn clock(clock: &Clock) {
+    fn time(time: Duration) -> String {
+        format!("{}:{:02}.{}", time.as_secs() / 60, time.as_secs() % 60, time.subsec_millis() / 100)
+    }
+    Side::sides().into_iter().for_each(|side| {
+        print!("{:?}: {}", side, time(clock.remaining(side)));
+        if clock.running() == Some(side) {
+            print!(" (running)");
+        }
+        println!();
+    });
 }
 
 #[cfg(test)]
 mod tests {
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpStream;
+
+    use chess_lib::MoveError;
 
     macro_rules! case {
         ( $x : literal ) => {
             crate::run(crate::reader::Reader::literal(include_str!($x)))
         };
+        ( $x : literal, $port : expr ) => {
+            crate::run(crate::reader::Reader::literal(include_str!($x).replace("{port}", &$port.to_string()).leak()))
+        };
+    }
+
+    /// A port that is free on the loopback interface, for a test to host on.
+    fn port() -> u16 {
+        std::net::TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap().port()
+    }
+
+    /// Connects to a test hosting on `port` as the opponent, once it listens.
+    fn connect(port: u16) -> (BufReader<TcpStream>, TcpStream) {
+        loop {
+            if let Ok(stream) = TcpStream::connect(("127.0.0.1", port)) {
+                break (BufReader::new(stream.try_clone().unwrap()), stream);
+            }
+            std::thread::sleep(std::time::Duration::from_millis(50));
+        }
+    }
+
+    fn read(reader: &mut BufReader<TcpStream>) -> String {
+        let mut line = String::new();
+        reader.read_line(&mut line).unwrap();
+        line.trim().to_string()
     }
 
     #[test]
     fn castle() {
         assert!(case!("tests/castle_test.txt").is_empty());
-        assert!(case!("tests/castle_test_fail.txt").len() == 1);
+        assert_eq!(case!("tests/castle_test_fail.txt"), [MoveError::CastlingRights]);
     }
 
     #[test]
@@ -132,6 +1043,126 @@ mod tests {
         assert!(case!("tests/en_passant.txt").is_empty());
     }
 
+    #[test]
+    fn edit() {
+        assert!(case!("tests/edit_test.txt").is_empty());
+    }
+
+    #[test]
+    fn games() {
+        assert!(case!("tests/games_test.txt").is_empty());
+    }
+
+    #[test]
+    fn save() {
+        assert!(case!("tests/save_test.txt").is_empty());
+
+        let path = std::env::temp_dir().join(format!("chess-tui-{}.json", std::process::id()));
+        let mut game = crate::save::load(std::path::Path::new("src/tests/game.pgn")).unwrap();
+        game.board.clock = Some(chess_lib::Clock::new("300+2".parse().unwrap()));
+        crate::save::save(&game, &path).unwrap();
+        let loaded = crate::save::load(&path);
+        std::fs::remove_file(&path).unwrap();
+        let loaded = loaded.unwrap();
+        assert_eq!((loaded.white.as_str(), loaded.moves(), loaded.board.fen()), ("Ann", game.moves(), game.board.fen()));
+        assert!(loaded.board.clock.is_some());
+    }
+
+    #[test]
+    fn analyze() {
+        assert!(case!("tests/analyze_test.txt").is_empty());
+    }
+
+    #[test]
+    fn review() {
+        assert!(case!("tests/review_test.txt").is_empty());
+    }
+
+    #[test]
+    fn puzzles() {
+        assert!(case!("tests/puzzle_test.txt").is_empty());
+
+        let (puzzles, skipped) = crate::puzzle::load(std::path::Path::new("src/tests/puzzles.csv")).unwrap();
+        assert_eq!(puzzles.iter().map(|puzzle| puzzle.id.as_str()).collect::<Vec<_>>(), ["00sHx", "back", "queen"]);
+        assert!(matches!(skipped.as_slice(), [crate::puzzle::PuzzleError::Fen(5, ..), crate::puzzle::PuzzleError::Move(6, mov), crate::puzzle::PuzzleError::Columns(7)] if mov == "a1a1"));
+    }
+
+    #[test]
+    fn explore() {
+        assert!(case!("tests/explore_test.txt").is_empty());
+    }
+
+    #[test]
+    fn replay() {
+        assert!(case!("tests/replay_test.txt").is_empty());
+    }
+
+    #[test]
+    fn network() {
+        let port = port();
+        let host = std::thread::spawn(move || case!("tests/host_test.txt", port));
+        assert!(case!("tests/join_test.txt", port).is_empty());
+        assert!(host.join().unwrap().is_empty());
+    }
+
+    #[test]
+    fn reconnect() {
+        let port = port();
+        let host = std::thread::spawn(move || case!("tests/reconnect_test.txt", port));
+
+        let (mut reader, mut stream) = connect(port);
+        let sync = read(&mut reader);
+        let start = sync.strip_prefix("sync white - ").unwrap().to_string();
+        writeln!(stream, "sync - - {start}").unwrap();
+        assert_eq!(read(&mut reader), "move 0 e2e4");
+
+        // Dropped without saying goodbye, the host waits for the game to be taken up again, and takes this side's
+        // game as it is ahead.
+        drop((reader, stream));
+        let (mut reader, mut stream) = connect(port);
+        assert_eq!(read(&mut reader), format!("sync white e2e4 {start}"));
+        writeln!(stream, "sync - e2e4,e7e5 {start}").unwrap();
+        assert_eq!(read(&mut reader), "move 2 g1f3");
+        writeln!(stream, "resign").unwrap();
+        assert_eq!(read(&mut reader), "bye");
+        assert!(host.join().unwrap().is_empty());
+    }
+
+    #[test]
+    fn waiting() {
+        let port = port();
+        let (commands, lines) = std::sync::mpsc::channel();
+        let host = std::thread::spawn(move || crate::run(crate::reader::Reader::Stdin(lines)));
+        let command = |command: &str| commands.send(command.replace("{port}", &port.to_string())).unwrap();
+
+        command("host 127.0.0.1:{port}");
+        let (mut reader, mut stream) = connect(port);
+        let start = read(&mut reader).strip_prefix("sync white - ").unwrap().to_string();
+        writeln!(stream, "sync - - {start}").unwrap();
+        command("e2 e4");
+        assert_eq!(read(&mut reader), "move 0 e2e4");
+
+        // Black never moves, and white can still offer a draw and resign, though not move again.
+        command("d2 d4");
+        command("draw");
+        assert_eq!(read(&mut reader), "draw");
+        command("resign");
+        assert_eq!(read(&mut reader), "resign");
+        assert_eq!(read(&mut reader), "bye");
+
+        // Black leaves on its own turn, which ends the session for white.
+        command("reset");
+        command("host 127.0.0.1:{port}");
+        let (mut reader, mut stream) = connect(port);
+        let start = read(&mut reader).strip_prefix("sync white - ").unwrap().to_string();
+        writeln!(stream, "sync - - {start}").unwrap();
+        command("e2 e4");
+        assert_eq!(read(&mut reader), "move 0 e2e4");
+        writeln!(stream, "bye").unwrap();
+        command("exit");
+        assert!(host.join().unwrap().is_empty());
+    }
+
     #[test]
     fn check() {
         // assert!(case!("tests/check_tester.txt").is_empty());
@@ -0,0 +1,380 @@
+use std::fmt::Display;
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::net::{TcpListener, TcpStream};
+use std::str::FromStr;
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError};
+use std::time::{Duration, Instant};
+
+use chess_lib::{Board, Game, MoveError, Piece, Pos, PreviousMove, Side};
+
+/// How long a player is waited for to come back after the connection is lost, and to join in the first place.
+pub const RECONNECT: Duration = Duration::from_secs(30);
+
+#[derive(Debug)]
+pub enum NetError {
+    Io(std::io::Error),
+    /// A line from the opponent that is not part of the protocol.
+    Message(String),
+    /// A move from the opponent that could not be read, or could not be played with the error.
+    Move(String, Option<MoveError>),
+}
+
+impl Display for NetError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{err}"),
+            Self::Message(line) => write!(f, "Unexpected message \"{line}\" from the opponent"),
+            Self::Move(mov, Some(err)) => write!(f, "Could not play the opponent's move {mov}: {err}"),
+            Self::Move(mov, None) => write!(f, "Invalid move \"{mov}\" from the opponent"),
+        }
+    }
+}
+
+impl std::error::Error for NetError {}
+
+/// A line of the protocol two chess-tui instances play over, one per line of text.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Message {
+    /// The game as the sender has it: the sender's side if it knows it yet, the moves and drops in UCI and the
+    /// position it started from in JSON, written "sync white e2e4,e7e5 {...}" with "-" for an unknown side or
+    /// no moves. Sent by both sides on connecting, and whenever a move could not be played.
+    Sync(Option<Side>, Vec<String>, String),
+    /// A move or drop ("e2e4", "N@f7") after the given number of moves, written "move 0 e2e4".
+    Move(usize, String),
+    Resign,
+    /// Offers a draw, which stands until the opponent has moved.
+    Draw,
+    Accept,
+    Decline,
+    /// Leaves the game, where closing the connection otherwise means it was lost and will be made again.
+    Bye,
+}
+
+impl Display for Message {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Sync(side, moves, start) => {
+                let side = match side {
+                    Some(Side::White) => "white",
+                    Some(Side::Black) => "black",
+                    None => "-",
+                };
+                let moves = match moves.is_empty() {
+                    true => "-".to_string(),
+                    false => moves.join(","),
+                };
+                write!(f, "sync {side} {moves} {start}")
+            },
+            Self::Move(ply, mov) => write!(f, "move {ply} {mov}"),
+            Self::Resign => write!(f, "resign"),
+            Self::Draw => write!(f, "draw"),
+            Self::Accept => write!(f, "accept"),
+            Self::Decline => write!(f, "decline"),
+            Self::Bye => write!(f, "bye"),
+        }
+    }
+}
+
+impl FromStr for Message {
+    type Err = NetError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let invalid = || NetError::Message(s.to_string());
+        match s.splitn(4, ' ').collect::<Vec<_>>().as_slice() {
+            ["sync", side, moves, start] => {
+                let side = match *side {
+                    "white" => Some(Side::White),
+                    "black" => Some(Side::Black),
+                    "-" => None,
+                    _ => return Err(invalid()),
+                };
+                let moves = match *moves {
+                    "-" => Vec::new(),
+                    moves => moves.split(',').map(str::to_string).collect(),
+                };
+                Ok(Self::Sync(side, moves, start.to_string()))
+            },
+            ["move", ply, mov] => ply.parse().map(|ply| Self::Move(ply, mov.to_string())).map_err(|_| invalid()),
+            ["resign"] => Ok(Self::Resign),
+            ["draw"] => Ok(Self::Draw),
+            ["accept"] => Ok(Self::Accept),
+            ["decline"] => Ok(Self::Decline),
+            ["bye"] => Ok(Self::Bye),
+            _ => Err(invalid()),
+        }
+    }
+}
+
+/// How the two games were brought together on connecting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Sync {
+    /// Both had the same game.
+    Same,
+    /// The opponent's game was behind, or differed and this side hosts, so the opponent takes this game.
+    Kept,
+    /// This game was behind, or differed and the opponent hosts, so it was replaced by the opponent's game.
+    Taken,
+}
+
+/// What the opponent did.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Event {
+    /// Played a move or drop, in SAN.
+    Moved(String),
+    Resigned,
+    Offered,
+    Accepted,
+    Declined,
+    Synced(Sync),
+    Left,
+    /// The connection was lost.
+    Lost,
+}
+
+enum Role {
+    Host(TcpListener),
+    Join(String),
+}
+
+/// A game played against another chess-tui over TCP. Both sides keep the game and check every move the other
+/// sends by playing it. Each moves only on its own turn, but may resign, offer a draw or leave at any time.
+pub struct Session {
+    pub side: Side,
+    role: Role,
+    stream: TcpStream,
+    lines: Receiver<String>,
+    /// A draw offered by the opponent, until it is answered or this side moves.
+    pub offered: bool,
+    /// A draw offered to the opponent, until it is answered or the opponent moves.
+    pub offering: bool,
+}
+
+impl Session {
+
+    /// Listens on `addr` (e.g. "0.0.0.0:7878") and waits for the opponent to join, playing `side` in `game`.
+    pub fn host(addr: &str, side: Side, game: &mut Game) -> Result<(Self, Sync), NetError> {
+        let listener = TcpListener::bind(addr).map_err(NetError::Io)?;
+        let (stream, ..) = listener.accept().map_err(NetError::Io)?;
+        Self::start(side, Role::Host(listener), stream, game)
+    }
+
+    /// Joins the game hosted at `addr` (e.g. "127.0.0.1:7878"), trying again until [`RECONNECT`] has passed
+    /// in case it is not hosted yet. The side is the one the host does not play.
+    pub fn join(addr: &str, game: &mut Game) -> Result<(Self, Sync), NetError> {
+        let role = Role::Join(addr.to_string());
+        let stream = Self::open(&role, Instant::now())?;
+        Self::start(Side::White, role, stream, game)
+    }
+
+    fn start(side: Side, role: Role, stream: TcpStream, game: &mut Game) -> Result<(Self, Sync), NetError> {
+        let (.., lines) = mpsc::channel();
+        let mut session = Self { side, role, stream, lines, offered: false, offering: false };
+        let sync = match session.handshake(game) {
+            Err(NetError::Io(..)) => session.reconnect(game)?,
+            sync => sync?,
+        };
+        Ok((session, sync))
+    }
+
+    /// Connects again after the connection was lost, waiting up to [`RECONNECT`] for the opponent.
+    pub fn reconnect(&mut self, game: &mut Game) -> Result<Sync, NetError> {
+        let start = Instant::now();
+        loop {
+            self.stream.shutdown(std::net::Shutdown::Both).ok();
+            let connected = Self::open(&self.role, start).and_then(|stream| {
+                self.stream = stream;
+                self.handshake(game)
+            });
+            match connected {
+                Err(NetError::Io(..)) if start.elapsed() < RECONNECT => std::thread::sleep(Duration::from_millis(100)),
+                connected => return connected,
+            }
+        }
+    }
+
+    /// A new connection to the opponent, who is waited for until [`RECONNECT`] has passed since `start`.
+    fn open(role: &Role, start: Instant) -> Result<TcpStream, NetError> {
+        let waiting = |err: &std::io::Error| start.elapsed() < RECONNECT && err.kind() != ErrorKind::InvalidInput;
+        match role {
+            Role::Host(listener) => {
+                listener.set_nonblocking(true).map_err(NetError::Io)?;
+                let accepted = loop {
+                    match listener.accept() {
+                        Err(err) if err.kind() == ErrorKind::WouldBlock && waiting(&err) => std::thread::sleep(Duration::from_millis(100)),
+                        accepted => break accepted,
+                    }
+                };
+                listener.set_nonblocking(false).map_err(NetError::Io)?;
+                let (stream, ..) = accepted.map_err(NetError::Io)?;
+                stream.set_nonblocking(false).map_err(NetError::Io)?;
+                Ok(stream)
+            },
+            Role::Join(addr) => loop {
+                match TcpStream::connect(addr.as_str()) {
+                    Err(err) if waiting(&err) => std::thread::sleep(Duration::from_millis(100)),
+                    connected => return connected.map_err(NetError::Io),
+                }
+            },
+        }
+    }
+
+    /// Exchanges games with the opponent and settles on one of them, then starts reading what it sends.
+    fn handshake(&mut self, game: &mut Game) -> Result<Sync, NetError> {
+        let side = matches!(self.role, Role::Host(..)).then_some(self.side);
+        let (moves, start) = self::history(game)?;
+        self.send(&Message::Sync(side, moves, start))?;
+        self.stream.set_read_timeout(Some(RECONNECT)).map_err(NetError::Io)?;
+        let mut reader = BufReader::new(self.stream.try_clone().map_err(NetError::Io)?);
+        let mut line = String::new();
+        if reader.read_line(&mut line).map_err(NetError::Io)? == 0 {
+            return Err(NetError::Io(ErrorKind::UnexpectedEof.into()));
+        }
+        self.stream.set_read_timeout(None).map_err(NetError::Io)?;
+        let Message::Sync(theirs, moves, start) = line.parse()? else {
+            return Err(NetError::Message(line.trim().to_string()));
+        };
+        if let (Role::Join(..), Some(theirs)) = (&self.role, theirs) {
+            self.side = theirs.other();
+        }
+        let sync = self.settle(game, moves, &start)?;
+
+        // Lines are read on their own thread so that the opponent can be waited for while it is not our turn.
+        let (sender, lines) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in reader.lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        self.lines = lines;
+        (self.offered, self.offering) = (false, false);
+        Ok(sync)
+    }
+
+    /// Takes the opponent's game if it is ahead of this one, or if they differ and the opponent hosts.
+    fn settle(&mut self, game: &mut Game, moves: Vec<String>, start: &str) -> Result<Sync, NetError> {
+        let (ours, ..) = self::history(game)?;
+        let board = serde_json::from_str::<Board>(start).map_err(|_| NetError::Message(start.to_string()))?;
+        // Compared by position rather than JSON, which may list the same things in another order.
+        let same = board.rules.name() == game.start().rules.name() && board.fen() == game.start().fen();
+        if same && ours == moves {
+            return Ok(Sync::Same);
+        }
+        let behind = same && moves.starts_with(&ours);
+        let ahead = same && ours.starts_with(&moves);
+        if ahead || (!behind && matches!(self.role, Role::Host(..))) {
+            return Ok(Sync::Kept);
+        }
+        let mut taken = game.clone();
+        taken.restart(board);
+        for mov in &moves {
+            self::play(&mut taken.board, mov)?;
+        }
+        *game = taken;
+        Ok(Sync::Taken)
+    }
+
+    /// Sends this side's game, for the opponent to settle the two again.
+    fn resync(&mut self, game: &Game) -> Result<(), NetError> {
+        let (moves, start) = self::history(game)?;
+        self.send(&Message::Sync(None, moves, start))
+    }
+
+    pub fn send(&mut self, message: &Message) -> Result<(), NetError> {
+        writeln!(self.stream, "{message}").and_then(|()| self.stream.flush()).map_err(NetError::Io)
+    }
+
+    /// Sends a move or drop just played in `game`, which ends any draw offered to this side.
+    pub fn send_move(&mut self, game: &Game) -> Result<(), NetError> {
+        let replay = game.replay().map_err(|err| NetError::Move(String::new(), Some(err)))?;
+        let (board, last) = replay.last_move().ok_or(NetError::Move(String::new(), None))?;
+        self.offered = false;
+        self.send(&Message::Move(game.moves().len() - 1, self::notation(board, last)))
+    }
+
+    /// Waits up to `timeout` for the opponent to do something and applies it to `game`, giving `None` if they did
+    /// nothing. A move that cannot be played is an error, after which this side's game is sent so that the two can
+    /// be settled.
+    pub fn receive(&mut self, game: &mut Game, timeout: Duration) -> Result<Option<Event>, NetError> {
+        let line = match self.lines.recv_timeout(timeout) {
+            Ok(line) => line,
+            Err(RecvTimeoutError::Timeout) => return Ok(None),
+            Err(RecvTimeoutError::Disconnected) => return Ok(Some(Event::Lost)),
+        };
+        let opponent = self.side.other();
+        let event = match line.parse()? {
+            Message::Sync(.., moves, start) => {
+                let sync = self.settle(game, moves, &start)?;
+                if sync == Sync::Kept {
+                    self.resync(game)?;
+                }
+                Ok(Event::Synced(sync))
+            },
+            Message::Move(ply, mov) => {
+                let san = match ply == game.moves().len() && game.board.state.turn.side == opponent {
+                    true => self::play(&mut game.board, &mov),
+                    false => Err(NetError::Move(mov, None)),
+                };
+                if san.is_err() {
+                    self.resync(game)?;
+                }
+                self.offering = false;
+                san.map(Event::Moved)
+            },
+            Message::Resign => game.resign(opponent).map(|()| Event::Resigned).map_err(|err| NetError::Move("resign".to_string(), Some(err))),
+            Message::Draw => {
+                self.offered = true;
+                Ok(Event::Offered)
+            },
+            Message::Accept if self.offering => {
+                self.offering = false;
+                game.draw().map(|()| Event::Accepted).map_err(|err| NetError::Move("accept".to_string(), Some(err)))
+            },
+            Message::Decline if self.offering => {
+                self.offering = false;
+                Ok(Event::Declined)
+            },
+            Message::Bye => Ok(Event::Left),
+            _ => Err(NetError::Message(line)),
+        };
+        event.map(Some)
+    }
+
+}
+
+/// The moves of a game as written in the protocol, and the position it started from in JSON.
+fn history(game: &Game) -> Result<(Vec<String>, String), NetError> {
+    let replay = game.replay().map_err(|err| NetError::Move(String::new(), Some(err)))?;
+    let start = serde_json::to_string(&replay.positions()[0]).expect("Could not write a board in JSON!");
+    let moves = replay.moves().map(|(board, previous)| self::notation(board, previous)).collect();
+    Ok((moves, start))
+}
+
+/// A move or drop as written in the protocol: in UCI, with castling as the king taking its rook, or as the piece
+/// letter, "@" and the square.
+fn notation(board: &Board, previous: PreviousMove) -> String {
+    match previous.dropped {
+        Some(kind) => format!("{}@{}", kind.letter(), previous.mov.to),
+        None => board.uci(previous.mov, true),
+    }
+}
+
+/// Plays a move or drop written as in the protocol, returning it in SAN.
+fn play(board: &mut Board, mov: &str) -> Result<String, NetError> {
+    let invalid = |err| NetError::Move(mov.to_string(), err);
+    match mov.split_once('@') {
+        Some((piece, pos)) => {
+            let kind = piece.chars().next().and_then(Piece::from_letter).filter(|_| piece.len() == 1).ok_or(invalid(None))?;
+            let pos = pos.parse::<Pos>().map_err(|_| invalid(None))?;
+            let san = board.san_drop(kind, pos);
+            board.drop_piece(kind, pos).map(|()| san).map_err(|err| invalid(Some(err)))
+        },
+        None => {
+            let parsed = board.parse_uci(mov).map_err(|_| invalid(None))?;
+            let san = board.san(parsed);
+            board.move_piece(parsed).map(|()| san).map_err(|err| invalid(Some(err)))
+        },
+    }
+}
@@ -0,0 +1,203 @@
+use std::fmt::Display;
+use std::path::Path;
+
+use chess_lib::{Board, Move, MoveError, Outcome, ParseFenError, Pos};
+
+/// The rating a player starts from, and how far a puzzle moves it.
+const START: f64 = 1500.0;
+const K: f64 = 32.0;
+
+#[derive(Debug)]
+pub enum PuzzleError {
+    Io(std::io::Error),
+    /// A line without the columns of a puzzle, with its line number.
+    Columns(usize),
+    Fen(usize, ParseFenError),
+    /// A solution move that could not be played, with its line number.
+    Move(usize, String),
+    Empty,
+}
+
+impl Display for PuzzleError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{err}"),
+            Self::Columns(line) => write!(f, "Expected an id, FEN, moves and rating on line {line}"),
+            Self::Fen(line, err) => write!(f, "Invalid FEN on line {line} with error {err}"),
+            Self::Move(line, mov) => write!(f, "Invalid move {mov} on line {line}"),
+            Self::Empty => write!(f, "No puzzles found"),
+        }
+    }
+}
+
+impl std::error::Error for PuzzleError {}
+
+pub struct Puzzle {
+    pub id: String,
+    /// The position before the opponent's first move.
+    pub start: Board,
+    /// The opponent's first move, then the solution and the opponent's replies in turn.
+    pub moves: Vec<Move>,
+    pub rating: u32,
+    pub themes: Vec<String>,
+}
+
+/// Reads puzzles from CSV in the column layout of the lichess puzzle database: id, FEN, moves in UCI, rating and
+/// then optional columns, of which only the themes (the eighth) are kept. A header line is skipped, and so are
+/// lines that can not be read, which are returned as errors next to the puzzles.
+pub fn load(path: &Path) -> Result<(Vec<Puzzle>, Vec<PuzzleError>), PuzzleError> {
+    let text = std::fs::read_to_string(path).map_err(PuzzleError::Io)?;
+    let (mut puzzles, mut skipped) = (Vec::new(), Vec::new());
+    for (i, line) in text.lines().enumerate().filter(|(.., line)| !line.trim().is_empty() && !line.starts_with("PuzzleId")) {
+        match parse(i + 1, line) {
+            Ok(puzzle) => puzzles.push(puzzle),
+            Err(err) => skipped.push(err),
+        }
+    }
+    match puzzles.is_empty() {
+        true => Err(PuzzleError::Empty),
+        false => Ok((puzzles, skipped)),
+    }
+}
+
+/// Reads the puzzle on a line, with its number.
+fn parse(number: usize, line: &str) -> Result<Puzzle, PuzzleError> {
+    let columns = line.split(',').map(str::trim).collect::<Vec<_>>();
+    let (id, fen, moves, rating) = match *columns.as_slice() {
+        [id, fen, moves, rating, ..] => (id, fen, moves, rating.parse().map_err(|_| PuzzleError::Columns(number))?),
+        _ => return Err(PuzzleError::Columns(number)),
+    };
+    let start = Board::from_fen(fen).map_err(|err| PuzzleError::Fen(number, err))?;
+    let mut board = start.clone();
+    let moves = moves.split_whitespace().map(|uci| {
+        let mov = board.parse_uci(uci).map_err(|_| PuzzleError::Move(number, uci.to_string()))?;
+        board.move_piece(mov).map_err(|_| PuzzleError::Move(number, uci.to_string()))?;
+        Ok(mov)
+    }).collect::<Result<Vec<_>, _>>()?;
+    if moves.len() < 2 {
+        return Err(PuzzleError::Columns(number));
+    }
+    let themes = columns.get(7).map(|themes| themes.split_whitespace().map(str::to_string).collect()).unwrap_or_default();
+    Ok(Puzzle { id: id.to_string(), start, moves, rating, themes })
+}
+
+/// What came of a move played in a puzzle.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Answer {
+    /// Right, and the opponent replied with the move.
+    Reply(Move),
+    Solved,
+    /// Wrong, the solution was the move.
+    Wrong(Move),
+}
+
+/// Sets puzzles one after another, each the unseen one closest to the player's rating, keeping the rating and
+/// streak of puzzles solved.
+pub struct Trainer {
+    puzzles: Vec<Puzzle>,
+    seen: Vec<bool>,
+    current: Option<usize>,
+    /// The number of the puzzle's moves played so far.
+    progress: usize,
+    pub board: Board,
+    pub rating: f64,
+    pub streak: u32,
+    pub best: u32,
+    pub solved: u32,
+    pub attempted: u32,
+}
+
+impl Trainer {
+
+    pub fn new(puzzles: Vec<Puzzle>) -> Self {
+        let seen = vec![false; puzzles.len()];
+        Self { puzzles, seen, current: None, progress: 0, board: Board::default(), rating: START, streak: 0, best: 0, solved: 0, attempted: 0 }
+    }
+
+    /// The puzzle being solved, `None` once it is over.
+    pub fn puzzle(&self) -> Option<&Puzzle> {
+        self.current.map(|index| &self.puzzles[index]).filter(|puzzle| self.progress < puzzle.moves.len())
+    }
+
+    /// Moves on to the next puzzle, playing the opponent's first move. A puzzle left unsolved counts as failed.
+    pub fn next_puzzle(&mut self) -> Option<&Puzzle> {
+        if self.puzzle().is_some() {
+            self.finish(false);
+        }
+        let index = (0..self.puzzles.len()).filter(|index| !self.seen[*index])
+            .min_by(|a, b| (self.puzzles[*a].rating as f64 - self.rating).abs().total_cmp(&(self.puzzles[*b].rating as f64 - self.rating).abs()))?;
+        self.seen[index] = true;
+        self.current = Some(index);
+        let puzzle = &self.puzzles[index];
+        self.board = puzzle.start.clone();
+        self.board.move_piece(puzzle.moves[0]).expect("Could not play a puzzle move that was loaded!");
+        self.progress = 1;
+        Some(puzzle)
+    }
+
+    /// Checks a move against the solution, where any mate also solves the puzzle. Illegal moves are errors and
+    /// leave the puzzle as it was.
+    pub fn play(&mut self, mov: Move) -> Result<Answer, MoveError> {
+        let (index, expected) = match self.current {
+            Some(index) if self.progress < self.puzzles[index].moves.len() => (index, self.puzzles[index].moves[self.progress]),
+            _ => return Err(MoveError::GameOver),
+        };
+        let mut played = self.board.clone();
+        played.move_piece(mov)?;
+        let mut solution = self.board.clone();
+        solution.move_piece(expected)?;
+        let mate = matches!(played.state.outcome(), Some(Outcome::Win(side, ..)) if side == self.board.state.turn.side);
+        if played.fen() != solution.fen() && !mate {
+            self.finish(false);
+            return Ok(Answer::Wrong(expected));
+        }
+
+        self.board = played;
+        self.progress += 1;
+        let moves = &self.puzzles[index].moves;
+        match moves.get(self.progress).copied().filter(|_| !mate) {
+            Some(reply) => {
+                self.board.move_piece(reply)?;
+                self.progress += 1;
+                Ok(Answer::Reply(reply))
+            },
+            None => {
+                self.finish(true);
+                Ok(Answer::Solved)
+            },
+        }
+    }
+
+    /// The square of the piece to move next.
+    pub fn hint(&self) -> Option<Pos> {
+        self.puzzle().map(|puzzle| puzzle.moves[self.progress].from)
+    }
+
+    /// Gives up on the puzzle, returning the moves left in its solution.
+    pub fn give_up(&mut self) -> Vec<Move> {
+        let moves = self.puzzle().map(|puzzle| puzzle.moves[self.progress..].to_vec()).unwrap_or_default();
+        if !moves.is_empty() {
+            self.finish(false);
+        }
+        moves
+    }
+
+    fn finish(&mut self, solved: bool) {
+        let Some(puzzle) = self.current.map(|index| &self.puzzles[index]) else {
+            return;
+        };
+        let expected = 1.0 / (1.0 + 10f64.powf((puzzle.rating as f64 - self.rating) / 400.0));
+        self.rating += K * (if solved { 1.0 } else { 0.0 } - expected);
+        self.attempted += 1;
+        match solved {
+            true => {
+                self.solved += 1;
+                self.streak += 1;
+                self.best = self.best.max(self.streak);
+            },
+            false => self.streak = 0,
+        }
+        self.progress = puzzle.moves.len();
+    }
+
+}
@@ -1,9 +1,12 @@
 use std::fs::File;
-use std::io::{BufRead, BufReader, Cursor, Stdin};
+use std::io::{BufRead, BufReader, Cursor};
+use std::sync::mpsc::{self, Receiver};
 
 pub enum Reader {
     File(BufReader<File>),
-    Stdin(Stdin),
+    /// Lines typed by the user, read on their own thread so that they can be checked for while waiting on
+    /// something else.
+    Stdin(Receiver<String>),
     Literal(Cursor<&'static str>),
 }
 
@@ -12,10 +15,22 @@ impl Reader {
     pub fn new(mut args: impl Iterator<Item = String>) -> Self {
         match args.next() {
             Some(arg) => Self::File(BufReader::new(File::open(arg).expect("Could not read provided file!"))),
-            None => Self::Stdin(std::io::stdin()),
+            None => Self::stdin(),
         }
     }
 
+    fn stdin() -> Self {
+        let (sender, lines) = mpsc::channel();
+        std::thread::spawn(move || {
+            for line in std::io::stdin().lines().map_while(Result::ok) {
+                if sender.send(line).is_err() {
+                    break;
+                }
+            }
+        });
+        Self::Stdin(lines)
+    }
+
     pub fn literal(s: &'static str) -> Self {
         Self::Literal(Cursor::new(s))
     }
@@ -23,15 +38,36 @@ impl Reader {
     pub fn read_line(&mut self, buf: &mut String) -> bool {
         let cont = match self {
             Self::File(reader) => reader.read_line(buf),
-            Self::Stdin(reader) => reader.read_line(buf),
+            Self::Stdin(lines) => {
+                if let Ok(line) = lines.recv() {
+                    *buf += &line;
+                    buf.push('\n');
+                }
+                Ok(buf.len())
+            },
             Self::Literal(reader) => reader.read_line(buf),
         }.inspect_err(|err| eprintln!("Error: {err}")).is_ok();
         if !self.print() && buf.is_empty() {
-            *self = Self::Stdin(std::io::stdin());
+            *self = Self::stdin();
         }
         cont
     }
 
+    /// Reads a line if the user has typed one, without waiting. Files are only read with [`Self::read_line`].
+    pub fn poll(&mut self, buf: &mut String) -> bool {
+        match self {
+            Self::Stdin(lines) => match lines.try_recv() {
+                Ok(line) => {
+                    *buf += &line;
+                    buf.push('\n');
+                    true
+                },
+                Err(..) => false,
+            },
+            Self::File(..) | Self::Literal(..) => false,
+        }
+    }
+
     pub fn print(&self) -> bool {
         matches!(self, Reader::Stdin(..))
     }
@@ -0,0 +1,76 @@
+use std::fmt::Display;
+use std::path::Path;
+
+use serde::{Deserialize, Serialize};
+
+use chess_lib::{Game, MoveError, ParsePgnError, Pgn};
+
+/// The version of the JSON save format, raised whenever saved games can no longer be read as before.
+pub const VERSION: u32 = 1;
+
+#[derive(Serialize)]
+struct SaveRef<'a> {
+    version: u32,
+    game: &'a Game,
+}
+
+#[derive(Deserialize)]
+struct Version {
+    version: u32,
+}
+
+#[derive(Deserialize)]
+struct Save {
+    game: Game,
+}
+
+#[derive(Debug)]
+pub enum SaveError {
+    Io(std::io::Error),
+    Json(serde_json::Error),
+    Pgn(ParsePgnError),
+    Move(MoveError),
+    Version(u32),
+}
+
+impl Display for SaveError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Self::Io(err) => write!(f, "{err}"),
+            Self::Json(err) => write!(f, "Invalid save file: {err}"),
+            Self::Pgn(err) => write!(f, "Invalid PGN: {err}"),
+            Self::Move(err) => write!(f, "Could not write the moves: {err}"),
+            Self::Version(version) => write!(f, "Unsupported save version {version}, expected {VERSION}"),
+        }
+    }
+}
+
+impl std::error::Error for SaveError {}
+
+/// Whether a file is read and written as PGN rather than JSON.
+fn pgn(path: &Path) -> bool {
+    path.extension().is_some_and(|extension| extension.eq_ignore_ascii_case("pgn"))
+}
+
+/// Saves a game as PGN if the file ends in ".pgn", otherwise as JSON, which also keeps the time left on the clock.
+pub fn save(game: &Game, path: &Path) -> Result<(), SaveError> {
+    let contents = if self::pgn(path) {
+        game.pgn().map_err(SaveError::Move)?.to_string()
+    } else {
+        serde_json::to_string_pretty(&SaveRef { version: VERSION, game }).map_err(SaveError::Json)?
+    };
+    std::fs::write(path, contents).map_err(SaveError::Io)
+}
+
+/// Loads a game saved by [`save`], or any PGN file with one game in it.
+pub fn load(path: &Path) -> Result<Game, SaveError> {
+    let contents = std::fs::read_to_string(path).map_err(SaveError::Io)?;
+    if self::pgn(path) {
+        return contents.parse::<Pgn>().and_then(|pgn| pgn.game()).map_err(SaveError::Pgn);
+    }
+    let Version { version } = serde_json::from_str(&contents).map_err(SaveError::Json)?;
+    if version != VERSION {
+        return Err(SaveError::Version(version));
+    }
+    serde_json::from_str::<Save>(&contents).map(|save| save.game).map_err(SaveError::Json)
+}
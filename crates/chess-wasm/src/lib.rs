@@ -0,0 +1,164 @@
+//! WebAssembly bindings for `chess-lib`, built with `wasm-pack build crates/chess-wasm`.
+//!
+//! Errors are thrown to JavaScript as strings.
+
+use wasm_bindgen::prelude::*;
+
+use chess_lib::{variant, Board, Move, Outcome, Pgn, Rules, Side, Standard};
+
+fn rules(name: Option<String>) -> Result<&'static dyn Rules, String> {
+    match name {
+        Some(name) => variant(&name).ok_or_else(|| format!("Unknown variant {name}")),
+        None => Ok(&Standard),
+    }
+}
+
+/// A game, from its starting position.
+#[wasm_bindgen(js_name = Board)]
+pub struct Game {
+    start: Board,
+    board: Board,
+    moves: Vec<Move>,
+    chess960: bool,
+}
+
+#[wasm_bindgen(js_class = Board)]
+impl Game {
+
+    /// The starting position of a variant, standard chess if none is given.
+    #[wasm_bindgen(constructor)]
+    pub fn new(variant: Option<String>) -> Result<Game, String> {
+        Ok(Self::from_board(Board::new(rules(variant)?), false))
+    }
+
+    /// A position in FEN, X-FEN or Shredder-FEN. Castling is written as the king taking its rook if the castling
+    /// rights show a Chess960 position.
+    #[wasm_bindgen(js_name = fromFen)]
+    pub fn from_fen(fen: &str, variant: Option<String>) -> Result<Game, String> {
+        let board = Board::from_variant_fen(fen, rules(variant)?).map_err(|err| err.to_string())?;
+        let chess960 = board.is_chess960();
+        Ok(Self::from_board(board, chess960))
+    }
+
+    /// One of the 960 starting positions of Chess960, numbered 0 to 959.
+    pub fn chess960(n: u16) -> Result<Game, String> {
+        let board = Board::chess960(n).ok_or_else(|| format!("No Chess960 position {n}"))?;
+        Ok(Self::from_board(board, true))
+    }
+
+    pub fn fen(&self) -> String {
+        self.board.fen()
+    }
+
+    /// The side to move, "w" or "b".
+    pub fn turn(&self) -> String {
+        match self.board.state.turn.side {
+            Side::White => "w",
+            Side::Black => "b",
+        }.to_string()
+    }
+
+    #[wasm_bindgen(js_name = legalMoves)]
+    pub fn legal_moves(&self) -> Vec<String> {
+        self.board.moves().map(|mov| self.board.uci(mov, self.chess960)).collect()
+    }
+
+    #[wasm_bindgen(js_name = legalMovesSan)]
+    pub fn legal_moves_san(&self) -> Vec<String> {
+        self.board.moves().map(|mov| self.board.san(mov)).collect()
+    }
+
+    /// Plays a move in UCI notation and returns it in SAN.
+    #[wasm_bindgen(js_name = playUci)]
+    pub fn play_uci(&mut self, uci: &str) -> Result<String, String> {
+        let mov = self.board.parse_uci(uci).map_err(|err| err.to_string())?;
+        self.play(mov)
+    }
+
+    /// Plays a move in SAN and returns it as written by the board.
+    #[wasm_bindgen(js_name = playSan)]
+    pub fn play_san(&mut self, san: &str) -> Result<String, String> {
+        let mov = self.board.parse_san(san).map_err(|err| err.to_string())?;
+        self.play(mov)
+    }
+
+    #[wasm_bindgen(js_name = isCheck)]
+    pub fn is_check(&self) -> bool {
+        self.board.state.check().is_some()
+    }
+
+    #[wasm_bindgen(js_name = isCheckmate)]
+    pub fn is_checkmate(&self) -> bool {
+        matches!(self.board.state.outcome(), Some(Outcome::Win(.., "checkmate")))
+    }
+
+    #[wasm_bindgen(js_name = isGameOver)]
+    pub fn is_game_over(&self) -> bool {
+        self.board.state.outcome().is_some()
+    }
+
+    /// The result as in PGN: "1-0", "0-1", "1/2-1/2" or "*" while the game goes on.
+    pub fn result(&self) -> String {
+        Pgn::result(self.board.state.outcome()).to_string()
+    }
+
+    /// How the game ended, e.g. "checkmate" or "stalemate".
+    pub fn reason(&self) -> Option<String> {
+        self.board.state.outcome().map(|outcome| match outcome {
+            Outcome::Win(.., reason) | Outcome::Draw(reason) => reason.to_string(),
+        })
+    }
+
+    /// The game so far in PGN.
+    pub fn pgn(&self) -> Result<String, String> {
+        Pgn::new(&self.start, self.moves.iter().copied()).map(|pgn| pgn.to_string()).map_err(|err| err.to_string())
+    }
+
+}
+
+impl Game {
+
+    fn from_board(board: Board, chess960: bool) -> Self {
+        Self { start: board.clone(), board, moves: Vec::new(), chess960 }
+    }
+
+    fn play(&mut self, mov: Move) -> Result<String, String> {
+        let san = self.board.san(mov);
+        self.board.move_piece(mov).map_err(|err| err.to_string())?;
+        self.moves.push(mov);
+        Ok(san)
+    }
+
+}
+
+#[cfg(test)]
+mod tests {
+    use super::Game;
+
+    #[test]
+    fn game() {
+        let mut game = Game::new(None).unwrap();
+        assert_eq!(game.legal_moves().len(), 20);
+        assert!(game.legal_moves_san().contains(&"Nf3".to_string()));
+        assert_eq!(game.play_uci("f2f3").unwrap(), "f3");
+        assert_eq!(game.play_san("e5").unwrap(), "e5");
+        assert_eq!(game.play_uci("g2g4").unwrap(), "g4");
+        assert!(game.play_san("Ke6").is_err());
+        assert_eq!(game.play_san("Qh4").unwrap(), "Qh4#");
+        assert!(game.is_check() && game.is_checkmate() && game.is_game_over());
+        assert_eq!(game.result(), "0-1");
+        assert!(game.pgn().unwrap().ends_with("\n1. f3 e5 2. g4 Qh4# 0-1\n"));
+
+        let game = Game::from_fen("4k3/8/8/8/8/8/8/4K2R w K - 0 1", None).unwrap();
+        assert!(game.legal_moves().contains(&"e1g1".to_string()));
+        assert_eq!(game.turn(), "w");
+        assert!(Game::from_fen("8/8", None).is_err());
+        assert!(Game::new(Some("capablanca".to_string())).is_ok());
+        assert!(Game::chess960(0).unwrap().legal_moves().iter().all(|uci| uci.len() == 4));
+
+        let mut game = Game::from_fen("4k3/8/8/8/8/8/8/RK5R w HA - 0 1", None).unwrap();
+        assert!(game.legal_moves().contains(&"b1a1".to_string()));
+        assert_eq!(game.play_uci("b1a1").unwrap(), "O-O-O");
+        assert_eq!(game.fen(), "4k3/8/8/8/8/8/8/2KR3R b - - 1 1");
+    }
+}
@@ -0,0 +1,35 @@
+//! Run with `wasm-pack test --node crates/chess-wasm`.
+
+#![cfg(target_arch = "wasm32")]
+
+use wasm_bindgen_test::wasm_bindgen_test;
+
+use chess_wasm::Game;
+
+#[wasm_bindgen_test]
+fn scholars_mate() {
+    let mut game = Game::new(None).unwrap();
+    for san in ["e4", "e5", "Bc4", "Nc6", "Qh5", "Nf6"] {
+        game.play_san(san).unwrap();
+    }
+    assert_eq!(game.play_uci("h5f7").unwrap(), "Qxf7#");
+    assert!(game.is_checkmate());
+    assert_eq!(game.result(), "1-0");
+    assert!(game.pgn().unwrap().contains("4. Qxf7# 1-0"));
+}
+
+#[wasm_bindgen_test]
+fn errors() {
+    assert!(Game::from_fen("not a fen", None).is_err());
+    assert!(Game::new(Some("unknown".to_string())).is_err());
+    assert!(Game::new(None).unwrap().play_uci("e2e5").is_err());
+}
+
+#[wasm_bindgen_test]
+fn chess960_castling() {
+    // The king takes its rook in the legal moves of a Chess960 FEN, and the same move castles when played back.
+    let mut game = Game::from_fen("4k3/8/8/8/8/8/8/RK5R w HA - 0 1", None).unwrap();
+    let castle = game.legal_moves().into_iter().find(|uci| uci == "b1h1").unwrap();
+    assert_eq!(game.play_uci(&castle).unwrap(), "O-O");
+    assert_eq!(game.fen(), "4k3/8/8/8/8/8/8/R4RK1 b - - 1 1");
+}